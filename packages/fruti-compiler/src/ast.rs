@@ -4,6 +4,7 @@
 // This is the output of the parser and input to semantic analysis.
 
 use crate::span::{Span, Spanned};
+use std::fmt;
 
 /// A complete Fruti source file
 #[derive(Debug, Clone, PartialEq)]
@@ -22,12 +23,18 @@ pub enum Item {
     TypeAlias(TypeAlias),
     Const(Const),
     Import(Import),
+    Mod(Mod),
 }
 
 /// Function definition
 #[derive(Debug, Clone, PartialEq)]
 pub struct Function {
+    pub doc: Option<String>, // Text of the preceding doc comment
+    pub attributes: Vec<Attribute>,
     pub name: Spanned<String>,
+    pub generics: Vec<Spanned<String>>, // fn max<T>(...)
+    pub bounds: Vec<TraitBound>,        // From <T: Ord> and `where T: Ord`
+    pub receiver: Option<SelfKind>,     // Methods only: fn area(&self)
     pub params: Vec<Param>,
     pub return_type: Option<Type>,
     pub body: Block,
@@ -35,16 +42,51 @@ pub struct Function {
     pub is_pub: bool,
 }
 
+/// Attribute: #[inline], #[inline(always)], #[cold]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub name: Spanned<String>,
+    pub args: Vec<Spanned<String>>, // Each comma-separated argument as source text
+}
+
+/// Constraint on a generic parameter: T: Display + Clone
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitBound {
+    pub param: Spanned<String>,
+    pub traits: Vec<Spanned<String>>,
+}
+
+/// How a method takes its receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfKind {
+    Value, // self
+    Ref,   // &self
+    Own,   // own self
+}
+
+impl SelfKind {
+    /// The receiver as written in a parameter list
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SelfKind::Value => "self",
+            SelfKind::Ref => "&self",
+            SelfKind::Own => "own self",
+        }
+    }
+}
+
 /// Function parameter
 #[derive(Debug, Clone, PartialEq)]
 pub struct Param {
     pub name: Spanned<String>,
     pub ty: Type,
+    pub default: Option<Expr>, // greeting: str = "Hello"
 }
 
 /// Struct definition
 #[derive(Debug, Clone, PartialEq)]
 pub struct Struct {
+    pub doc: Option<String>,
     pub name: Spanned<String>,
     pub fields: Vec<Field>,
     pub is_pub: bool,
@@ -61,6 +103,7 @@ pub struct Field {
 /// Enum definition
 #[derive(Debug, Clone, PartialEq)]
 pub struct Enum {
+    pub doc: Option<String>,
     pub name: Spanned<String>,
     pub variants: Vec<Variant>,
     pub is_pub: bool,
@@ -71,6 +114,7 @@ pub struct Enum {
 pub struct Variant {
     pub name: Spanned<String>,
     pub data: VariantData,
+    pub discriminant: Option<Expr>, // Red = 1; unit variants only
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -83,7 +127,9 @@ pub enum VariantData {
 /// Trait definition
 #[derive(Debug, Clone, PartialEq)]
 pub struct Trait {
+    pub doc: Option<String>,
     pub name: Spanned<String>,
+    pub supertraits: Vec<Spanned<String>>, // trait Ord: Eq + PartialOrd
     pub methods: Vec<TraitMethod>,
     pub is_pub: bool,
 }
@@ -92,6 +138,7 @@ pub struct Trait {
 #[derive(Debug, Clone, PartialEq)]
 pub struct TraitMethod {
     pub name: Spanned<String>,
+    pub receiver: Option<SelfKind>, // fn display(&self)
     pub params: Vec<Param>,
     pub return_type: Option<Type>,
 }
@@ -107,6 +154,7 @@ pub struct Impl {
 /// Type alias
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeAlias {
+    pub doc: Option<String>,
     pub name: Spanned<String>,
     pub ty: Type,
     pub is_pub: bool,
@@ -115,16 +163,29 @@ pub struct TypeAlias {
 /// Constant definition
 #[derive(Debug, Clone, PartialEq)]
 pub struct Const {
+    pub doc: Option<String>,
     pub name: Spanned<String>,
     pub ty: Type,
     pub value: Expr,
     pub is_pub: bool,
 }
 
-/// Import statement
+/// Import statement: import a::b, import a::b as c, import a::*, import a::{b, c}
 #[derive(Debug, Clone, PartialEq)]
 pub struct Import {
     pub path: Vec<Spanned<String>>,
+    pub alias: Option<Spanned<String>>, // Name after `as`
+    pub glob: bool,                     // Trailing `::*`; `path` is the module
+    pub group: Vec<Spanned<String>>,    // Names in trailing `::{...}`; `path` is the module
+}
+
+/// Inline module: mod name { items }
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mod {
+    pub doc: Option<String>,
+    pub name: Spanned<String>,
+    pub items: Vec<Item>,
+    pub is_pub: bool,
 }
 
 /// Type representation
@@ -133,6 +194,12 @@ pub enum Type {
     /// Simple type: i32, String, etc.
     Simple(Spanned<String>),
 
+    /// Generic type: Option<i32>, Result<T, E>
+    Generic {
+        name: Spanned<String>,
+        args: Vec<Type>,
+    },
+
     /// Reference type: &T
     Ref(Box<Type>),
 
@@ -142,8 +209,11 @@ pub enum Type {
     /// Tuple type: (i32, String)
     Tuple(Vec<Type>),
 
-    /// Array type: [i32; 10]
-    Array(Box<Type>, Option<usize>),
+    /// Array type: [i32; 10], [u8; SIZE]
+    Array(Box<Type>, Option<Box<Expr>>),
+
+    /// Anonymous bounded type: impl Display, impl Iterator + Clone
+    ImplTrait(Vec<Spanned<String>>),
 
     /// Function type: fn(i32) -> String
     Function {
@@ -181,10 +251,10 @@ pub enum Stmt {
     Return(Option<Expr>),
 
     /// Break statement: break;
-    Break,
+    Break(Span),
 
     /// Continue statement: continue;
-    Continue,
+    Continue(Span),
 
     /// While loop: while x < 10 { ... }
     While { condition: Expr, body: Block },
@@ -215,10 +285,18 @@ pub enum ExprKind {
     String(String),
     Char(char),
     Bool(bool),
+    Byte(u8),            // b'A'
+    ByteString(Vec<u8>), // b"bytes"
 
     /// Identifier: x, foo
     Ident(String),
 
+    /// Path to an enum variant or module item: Color::Red, a::b::f
+    Path {
+        path: Vec<Spanned<String>>,
+        type_args: Vec<Type>, // Turbofish on the next-to-last segment: Vec::<i32>::new
+    },
+
     /// Binary operation: a + b, x == y
     Binary {
         op: BinOp,
@@ -232,10 +310,11 @@ pub enum ExprKind {
         expr: Box<Expr>,
     },
 
-    /// Function call: foo(x, y)
+    /// Function call: foo(x, y), parse::<i32>(s)
     Call {
         func: Box<Expr>,
         args: Vec<Expr>,
+        type_args: Vec<Type>, // Explicit turbofish arguments
     },
 
     /// Method call: obj.method(x)
@@ -271,6 +350,12 @@ pub enum ExprKind {
         else_block: Option<Block>,
     },
 
+    /// Pattern test in an `if` condition: if let Some(x) = a && x > 0 { ... }
+    Let {
+        pattern: Pattern,
+        expr: Box<Expr>,
+    },
+
     /// Match expression: match x { ... }
     Match {
         expr: Box<Expr>,
@@ -292,9 +377,10 @@ pub enum ExprKind {
         fields: Vec<(Spanned<String>, Expr)>,
     },
 
-    /// Lambda: |x| x + 1
+    /// Lambda: |x| x + 1, |x: i32| -> i32 { x + 1 }
     Lambda {
         params: Vec<Param>,
+        return_type: Option<Type>,
         body: Box<Expr>,
     },
 
@@ -391,6 +477,29 @@ pub enum Pattern {
         name: String,
         patterns: Vec<Pattern>,
     },
+
+    /// Struct or struct-like enum variant: Point { x, y: 0 }. Shorthand
+    /// fields hold an `Ident` pattern of the same name.
+    Struct {
+        name: String,
+        fields: Vec<(Spanned<String>, Pattern)>,
+    },
+
+    /// Literal range: 1..=9, 0..10
+    Range {
+        start: Literal,
+        end: Literal,
+        inclusive: bool,
+    },
+
+    /// Alternatives, any of which may match: 1 | 2 | 3
+    Or(Vec<Pattern>),
+
+    /// Binding that also tests a subpattern: n @ 1..=9
+    Binding {
+        name: Spanned<String>,
+        pattern: Box<Pattern>,
+    },
 }
 
 /// Literal values
@@ -500,4 +609,624 @@ impl BinOp {
                 | BinOp::RemAssign
         )
     }
+
+    /// The operator as written in source
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::And => "and",
+            BinOp::Or => "or",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::BitXor => "^",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::Assign => "=",
+            BinOp::AddAssign => "+=",
+            BinOp::SubAssign => "-=",
+            BinOp::MulAssign => "*=",
+            BinOp::DivAssign => "/=",
+            BinOp::RemAssign => "%=",
+        }
+    }
+}
+
+impl UnOp {
+    /// The operator as written in source
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnOp::Neg => "-",
+            UnOp::Not => "not ",
+            UnOp::BitNot => "~",
+        }
+    }
+}
+
+/// Renders a type in source syntax, e.g. `fn(&str) -> Option<i32>`
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Simple(name) => write!(f, "{}", name.value),
+            Type::Generic { name, args } => {
+                write!(f, "{}<", name.value)?;
+                write_list(f, args)?;
+                write!(f, ">")
+            }
+            Type::Ref(inner) => write!(f, "&{}", inner),
+            Type::Own(inner) => write!(f, "own {}", inner),
+            Type::Tuple(types) => {
+                write!(f, "(")?;
+                write_list(f, types)?;
+                write!(f, ")")
+            }
+            Type::Array(elem, None) => write!(f, "[{}]", elem),
+            Type::Array(elem, Some(size)) => {
+                write!(f, "[{}; ", elem)?;
+                write_size(f, size, 0)?;
+                write!(f, "]")
+            }
+            Type::ImplTrait(bounds) => {
+                let names: Vec<&str> = bounds.iter().map(|b| b.value.as_str()).collect();
+                write!(f, "impl {}", names.join(" + "))
+            }
+            Type::Function {
+                params,
+                return_type,
+            } => {
+                write!(f, "fn(")?;
+                write_list(f, params)?;
+                write!(f, ")")?;
+                // A missing return type parses as unit, so leave it off again
+                match return_type.as_ref() {
+                    Type::Tuple(types) if types.is_empty() => Ok(()),
+                    ty => write!(f, " -> {}", ty),
+                }
+            }
+            Type::Infer => write!(f, "_"),
+        }
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, types: &[Type]) -> fmt::Result {
+    for (i, ty) in types.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", ty)?;
+    }
+    Ok(())
+}
+
+/// Render an array length, parenthesizing operands that bind looser than
+/// the operator around them (`min_precedence`)
+fn write_size(f: &mut fmt::Formatter<'_>, expr: &Expr, min_precedence: u8) -> fmt::Result {
+    match &expr.kind {
+        ExprKind::Integer(n) => write!(f, "{}", n),
+        ExprKind::Ident(name) => write!(f, "{}", name),
+        ExprKind::Path { path, .. } => {
+            let names: Vec<&str> = path.iter().map(|s| s.value.as_str()).collect();
+            write!(f, "{}", names.join("::"))
+        }
+        ExprKind::Unary { op, expr } => {
+            write!(f, "{}", op.symbol())?;
+            // Operands of a prefix operator bind tighter than any binary one
+            write_size(f, expr, u8::MAX)
+        }
+        ExprKind::Binary { op, left, right } => {
+            let precedence = op.precedence();
+            let parens = precedence < min_precedence;
+            if parens {
+                write!(f, "(")?;
+            }
+            write_size(f, left, precedence)?;
+            write!(f, " {} ", op.symbol())?;
+            write_size(f, right, precedence + 1)?;
+            if parens {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        // Array lengths are constant expressions, so nothing else is valid
+        _ => write!(f, "..."),
+    }
+}
+
+// Span normalization, so that structurally equal trees compare equal
+// regardless of where they appeared in the source
+
+const CANONICAL_SPAN: Span = Span { start: 0, end: 0 };
+
+fn strip_name<T>(name: &mut Spanned<T>) {
+    name.span = CANONICAL_SPAN;
+}
+
+impl Module {
+    /// Reset every span in the tree to a canonical value
+    pub fn strip_spans(&mut self) {
+        for item in &mut self.items {
+            item.strip_spans();
+        }
+    }
+}
+
+impl Item {
+    /// Text of the doc comment written before the item, if it can have one
+    pub fn doc(&self) -> Option<&str> {
+        match self {
+            Item::Function(func) => func.doc.as_deref(),
+            Item::Struct(s) => s.doc.as_deref(),
+            Item::Enum(e) => e.doc.as_deref(),
+            Item::Trait(t) => t.doc.as_deref(),
+            Item::TypeAlias(alias) => alias.doc.as_deref(),
+            Item::Const(c) => c.doc.as_deref(),
+            Item::Mod(m) => m.doc.as_deref(),
+            Item::Impl(_) | Item::Import(_) => None,
+        }
+    }
+
+    fn strip_spans(&mut self) {
+        match self {
+            Item::Function(func) => func.strip_spans(),
+            Item::Struct(s) => {
+                strip_name(&mut s.name);
+                s.fields.iter_mut().for_each(Field::strip_spans);
+            }
+            Item::Enum(e) => {
+                strip_name(&mut e.name);
+                for variant in &mut e.variants {
+                    strip_name(&mut variant.name);
+                    match &mut variant.data {
+                        VariantData::Unit => {}
+                        VariantData::Tuple(types) => types.iter_mut().for_each(Type::strip_spans),
+                        VariantData::Struct(fields) => {
+                            fields.iter_mut().for_each(Field::strip_spans)
+                        }
+                    }
+                    if let Some(discriminant) = &mut variant.discriminant {
+                        discriminant.strip_spans();
+                    }
+                }
+            }
+            Item::Trait(t) => {
+                strip_name(&mut t.name);
+                t.supertraits.iter_mut().for_each(strip_name);
+                for method in &mut t.methods {
+                    strip_name(&mut method.name);
+                    method.params.iter_mut().for_each(Param::strip_spans);
+                    if let Some(ty) = &mut method.return_type {
+                        ty.strip_spans();
+                    }
+                }
+            }
+            Item::Impl(i) => {
+                if let Some(name) = &mut i.trait_name {
+                    strip_name(name);
+                }
+                strip_name(&mut i.type_name);
+                i.methods.iter_mut().for_each(Function::strip_spans);
+            }
+            Item::TypeAlias(t) => {
+                strip_name(&mut t.name);
+                t.ty.strip_spans();
+            }
+            Item::Const(c) => {
+                strip_name(&mut c.name);
+                c.ty.strip_spans();
+                c.value.strip_spans();
+            }
+            Item::Import(i) => {
+                i.path.iter_mut().for_each(strip_name);
+                i.group.iter_mut().for_each(strip_name);
+                if let Some(alias) = &mut i.alias {
+                    strip_name(alias);
+                }
+            }
+            Item::Mod(m) => {
+                strip_name(&mut m.name);
+                m.items.iter_mut().for_each(Item::strip_spans);
+            }
+        }
+    }
+}
+
+impl Function {
+    fn strip_spans(&mut self) {
+        for attr in &mut self.attributes {
+            strip_name(&mut attr.name);
+            attr.args.iter_mut().for_each(strip_name);
+        }
+        strip_name(&mut self.name);
+        self.generics.iter_mut().for_each(strip_name);
+        for bound in &mut self.bounds {
+            strip_name(&mut bound.param);
+            bound.traits.iter_mut().for_each(strip_name);
+        }
+        self.params.iter_mut().for_each(Param::strip_spans);
+        if let Some(ty) = &mut self.return_type {
+            ty.strip_spans();
+        }
+        self.body.strip_spans();
+    }
+}
+
+impl Param {
+    fn strip_spans(&mut self) {
+        strip_name(&mut self.name);
+        self.ty.strip_spans();
+        if let Some(default) = &mut self.default {
+            default.strip_spans();
+        }
+    }
+}
+
+impl Field {
+    fn strip_spans(&mut self) {
+        strip_name(&mut self.name);
+        self.ty.strip_spans();
+    }
+}
+
+impl Type {
+    fn strip_spans(&mut self) {
+        match self {
+            Type::Simple(name) => strip_name(name),
+            Type::Generic { name, args } => {
+                strip_name(name);
+                args.iter_mut().for_each(Type::strip_spans);
+            }
+            Type::Ref(inner) | Type::Own(inner) => inner.strip_spans(),
+            Type::Tuple(types) => types.iter_mut().for_each(Type::strip_spans),
+            Type::Array(elem, size) => {
+                elem.strip_spans();
+                if let Some(size) = size {
+                    size.strip_spans();
+                }
+            }
+            Type::ImplTrait(bounds) => bounds.iter_mut().for_each(strip_name),
+            Type::Function {
+                params,
+                return_type,
+            } => {
+                params.iter_mut().for_each(Type::strip_spans);
+                return_type.strip_spans();
+            }
+            Type::Infer => {}
+        }
+    }
+}
+
+impl Block {
+    fn strip_spans(&mut self) {
+        self.span = CANONICAL_SPAN;
+        self.stmts.iter_mut().for_each(Stmt::strip_spans);
+        if let Some(expr) = &mut self.expr {
+            expr.strip_spans();
+        }
+    }
+}
+
+impl Stmt {
+    fn strip_spans(&mut self) {
+        match self {
+            Stmt::Let {
+                name, ty, value, ..
+            } => {
+                strip_name(name);
+                if let Some(ty) = ty {
+                    ty.strip_spans();
+                }
+                if let Some(value) = value {
+                    value.strip_spans();
+                }
+            }
+            Stmt::Expr(expr) => expr.strip_spans(),
+            Stmt::Return(expr) => {
+                if let Some(expr) = expr {
+                    expr.strip_spans();
+                }
+            }
+            Stmt::Break(span) | Stmt::Continue(span) => *span = CANONICAL_SPAN,
+            Stmt::While { condition, body } => {
+                condition.strip_spans();
+                body.strip_spans();
+            }
+            Stmt::For { var, iter, body } => {
+                strip_name(var);
+                iter.strip_spans();
+                body.strip_spans();
+            }
+            Stmt::Loop { body } => body.strip_spans(),
+        }
+    }
+}
+
+impl Pattern {
+    fn strip_spans(&mut self) {
+        match self {
+            Pattern::Wildcard | Pattern::Ident(_) | Pattern::Literal(_) | Pattern::Range { .. } => {
+            }
+            Pattern::Tuple(patterns)
+            | Pattern::Variant { patterns, .. }
+            | Pattern::Or(patterns) => patterns.iter_mut().for_each(Pattern::strip_spans),
+            Pattern::Struct { fields, .. } => {
+                for (name, pattern) in fields {
+                    strip_name(name);
+                    pattern.strip_spans();
+                }
+            }
+            Pattern::Binding { name, pattern } => {
+                strip_name(name);
+                pattern.strip_spans();
+            }
+        }
+    }
+}
+
+impl Expr {
+    /// Reset every span in the expression to a canonical value
+    pub fn strip_spans(&mut self) {
+        self.span = CANONICAL_SPAN;
+
+        match &mut self.kind {
+            ExprKind::Integer(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::Char(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Byte(_)
+            | ExprKind::ByteString(_)
+            | ExprKind::Ident(_) => {}
+            ExprKind::Path { path, type_args } => {
+                path.iter_mut().for_each(strip_name);
+                type_args.iter_mut().for_each(Type::strip_spans);
+            }
+            ExprKind::Binary { left, right, .. } => {
+                left.strip_spans();
+                right.strip_spans();
+            }
+            ExprKind::Unary { expr, .. } | ExprKind::Await(expr) | ExprKind::Try(expr) => {
+                expr.strip_spans()
+            }
+            ExprKind::Let { pattern, expr } => {
+                pattern.strip_spans();
+                expr.strip_spans();
+            }
+            ExprKind::Call {
+                func,
+                args,
+                type_args,
+            } => {
+                func.strip_spans();
+                args.iter_mut().for_each(Expr::strip_spans);
+                type_args.iter_mut().for_each(Type::strip_spans);
+            }
+            ExprKind::MethodCall {
+                receiver,
+                method,
+                args,
+            } => {
+                receiver.strip_spans();
+                strip_name(method);
+                args.iter_mut().for_each(Expr::strip_spans);
+            }
+            ExprKind::Field { expr, field } => {
+                expr.strip_spans();
+                strip_name(field);
+            }
+            ExprKind::Index { expr, index } => {
+                expr.strip_spans();
+                index.strip_spans();
+            }
+            ExprKind::Range { start, end, .. } => {
+                for bound in [start, end].into_iter().flatten() {
+                    bound.strip_spans();
+                }
+            }
+            ExprKind::If {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                condition.strip_spans();
+                then_block.strip_spans();
+                if let Some(block) = else_block {
+                    block.strip_spans();
+                }
+            }
+            ExprKind::Match { expr, arms } => {
+                expr.strip_spans();
+                for arm in arms {
+                    arm.pattern.strip_spans();
+                    if let Some(guard) = &mut arm.guard {
+                        guard.strip_spans();
+                    }
+                    arm.body.strip_spans();
+                }
+            }
+            ExprKind::Block(block) => block.strip_spans(),
+            ExprKind::Tuple(exprs) | ExprKind::Array(exprs) => {
+                exprs.iter_mut().for_each(Expr::strip_spans)
+            }
+            ExprKind::StructLit { name, fields } => {
+                strip_name(name);
+                for (field, value) in fields {
+                    strip_name(field);
+                    value.strip_spans();
+                }
+            }
+            ExprKind::Lambda {
+                params,
+                return_type,
+                body,
+            } => {
+                params.iter_mut().for_each(Param::strip_spans);
+                if let Some(ty) = return_type {
+                    ty.strip_spans();
+                }
+                body.strip_spans();
+            }
+            ExprKind::Cast { expr, ty } | ExprKind::Is { expr, ty } => {
+                expr.strip_spans();
+                ty.strip_spans();
+            }
+        }
+    }
+}
+
+// Node counting, for parser throughput figures
+
+impl Module {
+    /// Number of items, statements and expressions in the tree
+    pub fn node_count(&self) -> usize {
+        self.items.iter().map(Item::node_count).sum()
+    }
+}
+
+impl Item {
+    fn node_count(&self) -> usize {
+        1 + match self {
+            Item::Function(func) => func.body.node_count(),
+            Item::Impl(imp) => imp.methods.iter().map(|m| m.body.node_count()).sum(),
+            Item::Const(c) => c.value.node_count(),
+            Item::Mod(m) => m.items.iter().map(Item::node_count).sum(),
+            _ => 0,
+        }
+    }
+}
+
+impl Block {
+    fn node_count(&self) -> usize {
+        let stmts: usize = self.stmts.iter().map(Stmt::node_count).sum();
+        stmts + self.expr.as_ref().map_or(0, |e| e.node_count())
+    }
+}
+
+impl Stmt {
+    fn node_count(&self) -> usize {
+        1 + match self {
+            Stmt::Let { value, .. } => value.as_ref().map_or(0, Expr::node_count),
+            Stmt::Expr(expr) => expr.node_count(),
+            Stmt::Return(expr) => expr.as_ref().map_or(0, Expr::node_count),
+            Stmt::Break(_) | Stmt::Continue(_) => 0,
+            Stmt::While { condition, body } => condition.node_count() + body.node_count(),
+            Stmt::For { iter, body, .. } => iter.node_count() + body.node_count(),
+            Stmt::Loop { body } => body.node_count(),
+        }
+    }
+}
+
+impl Expr {
+    fn node_count(&self) -> usize {
+        let all = |exprs: &[Expr]| exprs.iter().map(Expr::node_count).sum::<usize>();
+        1 + match &self.kind {
+            ExprKind::Integer(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::Char(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Byte(_)
+            | ExprKind::ByteString(_)
+            | ExprKind::Ident(_)
+            | ExprKind::Path { .. } => 0,
+            ExprKind::Binary { left, right, .. } => left.node_count() + right.node_count(),
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Field { expr, .. }
+            | ExprKind::Let { expr, .. }
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::Cast { expr, .. }
+            | ExprKind::Is { expr, .. }
+            | ExprKind::Lambda { body: expr, .. } => expr.node_count(),
+            ExprKind::Call { func, args, .. } => func.node_count() + all(args),
+            ExprKind::MethodCall { receiver, args, .. } => receiver.node_count() + all(args),
+            ExprKind::Index { expr, index } => expr.node_count() + index.node_count(),
+            ExprKind::Range { start, end, .. } => [start, end]
+                .into_iter()
+                .flatten()
+                .map(|e| e.node_count())
+                .sum(),
+            ExprKind::If {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                condition.node_count()
+                    + then_block.node_count()
+                    + else_block.as_ref().map_or(0, Block::node_count)
+            }
+            ExprKind::Match { expr, arms } => {
+                let arms: usize = arms
+                    .iter()
+                    .map(|arm| {
+                        arm.guard.as_ref().map_or(0, Expr::node_count) + arm.body.node_count()
+                    })
+                    .sum();
+                expr.node_count() + arms
+            }
+            ExprKind::Block(block) => block.node_count(),
+            ExprKind::Tuple(exprs) | ExprKind::Array(exprs) => all(exprs),
+            ExprKind::StructLit { fields, .. } => fields.iter().map(|(_, e)| e.node_count()).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse_stripped(source: &str) -> Module {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let mut module = Parser::new(tokens).parse_module().unwrap();
+        module.strip_spans();
+        module
+    }
+
+    #[test]
+    fn test_type_display_round_trips() {
+        for source in [
+            "i32",
+            "Result<Option<i32>, str>",
+            "&own Vec<u8>",
+            "(i32, (bool, char))",
+            "()",
+            "[u8]",
+            "[u8; 16]",
+            "[u8; (SIZE + 1) * 2 - -1]",
+            "impl Display + Clone",
+            "fn(i32, &str) -> bool",
+            "fn()",
+            "fn() -> fn(i32)",
+        ] {
+            let module = parse_stripped(&format!("fn f(x: {}) {{}}", source));
+            let Item::Function(func) = &module.items[0] else {
+                panic!("expected function");
+            };
+            assert_eq!(func.params[0].ty.to_string(), source);
+        }
+        assert_eq!(Type::Infer.to_string(), "_");
+    }
+
+    #[test]
+    fn test_strip_spans_ignores_formatting() {
+        let compact = "fn f(a: i32, b: i32) -> i32 { return a+b; }";
+        let spaced = "fn f(a: i32, b: i32) -> i32 {\n    return a + b;\n}";
+
+        assert_eq!(parse_stripped(compact), parse_stripped(spaced));
+        assert_ne!(
+            parse_stripped(compact),
+            parse_stripped("fn f(a: i32, b: i32) -> i32 { return a-b; }")
+        );
+    }
 }
@@ -3,23 +3,193 @@
 // Translates the typed AST into LLVM IR
 
 use crate::ast::*;
-use crate::error::Result;
+use crate::error::{Error, ErrorKind, Result};
+use crate::span::{Span, Spanned};
+use anyhow::Context;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
 
 /// Code generator for LLVM IR
 pub struct CodeGen {
     // For now, we'll just generate a textual representation of LLVM IR
     // Once inkwell is enabled, this will use LLVM Context, Module, Builder
     module_name: String,
+    warnings: Vec<Spanned<String>>,
+    skipped: Vec<Spanned<String>>,
+    /// Variants of each enum in the module, in declaration (tag) order
+    enums: HashMap<String, Vec<EnumVariant>>,
+    /// Field names of each struct in the module, in declaration (slot) order
+    structs: HashMap<String, Vec<String>>,
+    /// String literals used by function bodies, emitted as `@.str.N`
+    strings: Vec<String>,
+}
+
+/// An enum variant as laid out in its tagged union
+#[derive(Debug, Clone)]
+struct EnumVariant {
+    name: String,
+    /// Number of payload slots the variant uses
+    fields: usize,
+    /// Field names of a struct variant, in slot order
+    names: Vec<String>,
+}
+
+/// AST constructs that code generation currently lowers, by variant name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub items: &'static [&'static str],
+    pub exprs: &'static [&'static str],
+}
+
+/// What `CodeGen::emit` writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Textual LLVM IR, as generated
+    LlvmIr,
+    /// Target assembly, via `llc`
+    Asm,
+    /// Object file, via `llc`
+    Obj,
+}
+
+impl OutputFormat {
+    /// The file extension for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::LlvmIr => "ll",
+            OutputFormat::Asm => "s",
+            OutputFormat::Obj => "o",
+        }
+    }
 }
 
 impl CodeGen {
     pub fn new(module_name: String) -> Self {
-        CodeGen { module_name }
+        CodeGen {
+            module_name,
+            warnings: Vec::new(),
+            skipped: Vec::new(),
+            enums: HashMap::new(),
+            structs: HashMap::new(),
+            strings: Vec::new(),
+        }
+    }
+
+    /// The `Item` and `ExprKind` variants that are lowered to IR
+    pub fn capabilities() -> Capabilities {
+        Capabilities {
+            items: &["Function", "Impl", "Enum", "Struct"],
+            // Function bodies are still emitted as stubs, except a method
+            // body that is a single `match self` on an enum, a single `match`
+            // on a string parameter, a body that just builds the struct or
+            // tuple it returns, and simple statements: i32 arithmetic over
+            // literals, parameters, and `let` bindings, `println` of a string
+            // literal, `if`/`else`, and `while` and `loop` compared on i32s
+            exprs: &[
+                "If",
+                "Match",
+                "StructLit",
+                "Tuple",
+                "Integer",
+                "Ident",
+                "Binary",
+                "String",
+            ],
+        }
+    }
+
+    /// Warnings produced during the last `generate_module` call
+    pub fn warnings(&self) -> &[Spanned<String>] {
+        &self.warnings
+    }
+
+    /// Constructs the last `generate_module` call left out of the IR
+    pub fn skipped(&self) -> &[Spanned<String>] {
+        &self.skipped
+    }
+
+    /// Write generated `ir` to `path` in `format`. Assembly and object files
+    /// are produced by piping the IR through `llc` for the host target.
+    pub fn emit(&self, ir: &str, format: OutputFormat, path: &Path) -> anyhow::Result<()> {
+        let filetype = match format {
+            OutputFormat::LlvmIr => {
+                return fs::write(path, ir).with_context(|| format!("Failed to write {:?}", path));
+            }
+            OutputFormat::Asm => "asm",
+            OutputFormat::Obj => "obj",
+        };
+
+        let mut llc = Command::new("llc");
+        // LLVM 14 and older parse `ptr` only in opaque pointer mode
+        if llc_major_version().is_some_and(|v| v < 15) {
+            llc.arg("-opaque-pointers");
+        }
+        let mut child = llc
+            .arg(format!("-filetype={}", filetype))
+            // Linkers default to position-independent executables
+            .arg("-relocation-model=pic")
+            .arg("-o")
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run llc; is LLVM installed?")?;
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(ir.as_bytes())
+            .context("Failed to pass IR to llc")?;
+
+        let output = child.wait_with_output().context("Failed to run llc")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("{}", stderr.trim_end()))
+                .with_context(|| format!("llc failed to write {:?}", path));
+        }
+        Ok(())
     }
 
     /// Generate LLVM IR for a module
     pub fn generate_module(&mut self, module: &Module) -> Result<String> {
         let mut ir = String::new();
+        self.warnings.clear();
+        self.skipped.clear();
+        self.enums.clear();
+        self.structs.clear();
+        self.strings.clear();
+
+        // Enum and struct layouts are needed by functions declared before them
+        for item in &module.items {
+            if let Item::Struct(s) = item {
+                let fields = s.fields.iter().map(|f| f.name.value.clone()).collect();
+                self.structs.insert(s.name.value.clone(), fields);
+            }
+            if let Item::Enum(e) = item {
+                let variants = e
+                    .variants
+                    .iter()
+                    .map(|v| EnumVariant {
+                        name: v.name.value.clone(),
+                        fields: match &v.data {
+                            VariantData::Unit => 0,
+                            VariantData::Tuple(types) => types.len(),
+                            VariantData::Struct(fields) => fields.len(),
+                        },
+                        names: match &v.data {
+                            VariantData::Struct(fields) => {
+                                fields.iter().map(|f| f.name.value.clone()).collect()
+                            }
+                            _ => Vec::new(),
+                        },
+                    })
+                    .collect();
+                self.enums.insert(e.name.value.clone(), variants);
+            }
+        }
 
         // Module header
         ir.push_str(&format!("; ModuleID = '{}'\n", self.module_name));
@@ -29,68 +199,1244 @@ impl CodeGen {
 
         // Generate declarations for built-in functions
         ir.push_str("; Built-in functions\n");
-        ir.push_str("declare i32 @printf(i8*, ...)\n");
-        ir.push_str("declare i32 @puts(i8*)\n\n");
+        ir.push_str("declare i32 @printf(ptr, ...)\n");
+        ir.push_str("declare i32 @puts(ptr)\n");
+        ir.push_str("declare i32 @strcmp(ptr, ptr)\n\n");
+
+        // Items are generated first, since they intern the string constants
+        // that go ahead of them
+        let header = ir;
+        let mut ir = String::new();
 
         // Generate code for each item
         for item in &module.items {
             match item {
                 Item::Function(func) => {
-                    let func_ir = self.generate_function(func)?;
+                    let func_ir = self.generate_function(func, None)?;
                     ir.push_str(&func_ir);
                     ir.push('\n');
                 }
+                Item::Enum(e) => {
+                    ir.push_str(&self.generate_enum(&e.name.value));
+                    ir.push('\n');
+                }
+                Item::Struct(s) => {
+                    let fields: Vec<String> =
+                        s.fields.iter().map(|f| self.llvm_type(&f.ty)).collect();
+                    ir.push_str(&format!(
+                        "%{} = type {{ {} }}\n\n",
+                        mangle(&[&self.module_name, &s.name.value], &[]),
+                        fields.join(", ")
+                    ));
+                }
+                Item::Impl(imp) => {
+                    for method in &imp.methods {
+                        let func_ir = self.generate_function(method, Some(&imp.type_name.value))?;
+                        ir.push_str(&func_ir);
+                        ir.push('\n');
+                    }
+                }
                 _ => {
                     // TODO: Implement other item types
+                    self.skipped.push(describe_item(item));
                 }
             }
         }
 
-        Ok(ir)
+        let mut strings = String::new();
+        for (i, text) in self.strings.iter().enumerate() {
+            strings.push_str(&format!(
+                "@.str.{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n",
+                i,
+                text.len() + 1,
+                escape_bytes(text)
+            ));
+        }
+        if !strings.is_empty() {
+            strings.push('\n');
+        }
+
+        Ok(header + &strings + &ir)
     }
 
-    /// Generate LLVM IR for a function
-    fn generate_function(&mut self, func: &Function) -> Result<String> {
+    /// Generate LLVM IR for a function, or a method of the `owner` type
+    fn generate_function(&mut self, func: &Function, owner: Option<&str>) -> Result<String> {
         let mut ir = String::new();
 
         // Function signature
         // Main function always returns i32 in LLVM by convention
-        // For simplicity, all typed functions return i32 in Phase 1
-        let return_ty = if func.name.value == "main" || func.return_type.is_some() {
-            "i32"
-        } else {
-            "void"
+        let return_ty = match &func.return_type {
+            _ if func.name.value == "main" => "i32".to_string(),
+            Some(ty) => self.llvm_type(ty),
+            None => "void".to_string(),
         };
 
-        ir.push_str(&format!("define {} @{}(", return_ty, func.name.value));
+        let symbol = if owner.is_none() && func.name.value == "main" {
+            "main".to_string()
+        } else {
+            let mut path = vec![self.module_name.as_str()];
+            path.extend(owner);
+            path.push(&func.name.value);
+            mangle(&path, &[])
+        };
+        ir.push_str(&format!("define {} @{}(", return_ty, symbol));
 
-        // Parameters
-        for (i, param) in func.params.iter().enumerate() {
+        // Parameters: function values and enums are passed as pointers,
+        // structs and tuples by value, everything else as i32 for now
+        let owner_enum = owner.and_then(|o| self.enums.get(o).cloned());
+        let self_ty = if owner_enum.is_some() { "ptr" } else { "i32" };
+        let receiver = func.receiver.map(|_| (self_ty.to_string(), "self"));
+        let params: Vec<(String, &str)> = receiver
+            .into_iter()
+            .chain(
+                func.params
+                    .iter()
+                    .map(|p| (self.llvm_type(&p.ty), p.name.value.as_str())),
+            )
+            .collect();
+        for (i, (ty, name)) in params.iter().enumerate() {
             if i > 0 {
                 ir.push_str(", ");
             }
-            ir.push_str("i32 %");
-            ir.push_str(&param.name.value);
+            ir.push_str(&format!("{} %{}", ty, name));
         }
 
-        ir.push_str(") {\n");
+        ir.push(')');
+        for attr in self.function_attributes(func) {
+            ir.push(' ');
+            ir.push_str(attr);
+        }
+        ir.push_str(" {\n");
         ir.push_str("entry:\n");
 
         // Function body
+        if let (Some(variants), Some(_), Some(arms)) =
+            (&owner_enum, func.receiver, self_match_arms(&func.body))
+        {
+            let owner = owner.unwrap_or_default();
+            ir.push_str(&self.generate_self_match(func, owner, variants, arms, &return_ty));
+            ir.push_str("}\n");
+            return Ok(ir);
+        }
+
+        if let Some((param, arms)) = string_match(func) {
+            // The pattern strings are globals, so they go ahead of the function
+            let (globals, body) = self.generate_string_match(func, owner, param, arms, &return_ty);
+            ir.push_str(&body);
+            ir.push_str("}\n");
+            return Ok(globals + &ir);
+        }
+
+        if let Some(aggregate) =
+            sole_expr(&func.body).and_then(|e| self.generate_aggregate(e, &return_ty, &params))
+        {
+            ir.push_str(&aggregate);
+            ir.push_str("}\n");
+            return Ok(ir);
+        }
+
+        let exit = match return_ty.as_str() {
+            _ if func.name.value == "main" && func.return_type.is_none() => {
+                Some(Exit::Implicit("ret i32 0"))
+            }
+            "i32" => Some(Exit::Value),
+            "void" => Some(Exit::Implicit("ret void")),
+            _ => None,
+        };
+        if let Some(exit) = exit {
+            // Function pointers that take and return i32 can be called
+            let callees: Vec<&str> = func
+                .params
+                .iter()
+                .filter(|p| match &p.ty {
+                    Type::Function {
+                        params,
+                        return_type,
+                    } => params
+                        .iter()
+                        .chain([&**return_type])
+                        .all(|ty| self.llvm_type(ty) == "i32"),
+                    _ => false,
+                })
+                .map(|p| p.name.value.as_str())
+                .collect();
+            let interned = self.strings.len();
+            let mut lowering = BodyLowering::new(&params, &callees, exit, &mut self.strings);
+            if lowering.body(&func.body)? {
+                ir.push_str(&lowering.finish());
+                ir.push_str("}\n");
+                return Ok(ir);
+            }
+            // Drop the strings only the abandoned lowering used
+            self.strings.truncate(interned);
+        }
+
         // For MVP, we'll just generate a simple return
+        if !func.body.stmts.is_empty() || func.body.expr.is_some() {
+            self.skipped.push(Spanned::new(
+                format!("body of function '{}'", func.name.value),
+                func.body.span,
+            ));
+        }
+
         if func.name.value == "main" {
             ir.push_str("  ; Main function body\n");
             ir.push_str("  ret i32 0\n");
         } else if return_ty == "void" {
             ir.push_str("  ret void\n");
         } else {
-            ir.push_str("  ret i32 0\n");
+            let zero = match return_ty.as_str() {
+                "i32" => "0",
+                "ptr" => "null",
+                _ => "zeroinitializer",
+            };
+            ir.push_str(&format!("  ret {} {}\n", return_ty, zero));
         }
 
         ir.push_str("}\n");
 
         Ok(ir)
     }
+
+    /// Build a returned struct or tuple literal with `insertvalue` and return
+    /// it by value. Only literals whose fields are integer literals or
+    /// parameters are lowered; anything else is left to the stub body.
+    fn generate_aggregate(
+        &self,
+        expr: &Expr,
+        return_ty: &str,
+        params: &[(String, &str)],
+    ) -> Option<String> {
+        let values: Vec<&Expr> = match &expr.kind {
+            ExprKind::StructLit { name, fields } => {
+                // Slots follow the declaration, not the literal's field order
+                let layout = self.structs.get(&name.value)?;
+                layout
+                    .iter()
+                    .map(|field| {
+                        fields
+                            .iter()
+                            .find(|(f, _)| f.value == *field)
+                            .map(|(_, value)| value)
+                    })
+                    .collect::<Option<_>>()?
+            }
+            ExprKind::Tuple(exprs) if !exprs.is_empty() => exprs.iter().collect(),
+            _ => return None,
+        };
+
+        let mut ir = String::new();
+        let mut current = "undef".to_string();
+        for (slot, value) in values.iter().enumerate() {
+            let (ty, operand) = match &value.kind {
+                ExprKind::Integer(n) => ("i32", n.to_string()),
+                ExprKind::Ident(name) => {
+                    let (ty, _) = params.iter().find(|(_, param)| param == name)?;
+                    (ty.as_str(), format!("%{}", name))
+                }
+                _ => return None,
+            };
+            let reg = format!("%agg{}", slot);
+            ir.push_str(&format!(
+                "  {} = insertvalue {} {}, {} {}, {}\n",
+                reg, return_ty, current, ty, operand, slot
+            ));
+            current = reg;
+        }
+        ir.push_str(&format!("  ret {} {}\n", return_ty, current));
+        Some(ir)
+    }
+
+    /// LLVM type for a source type: structs by their named type, tuples as
+    /// literal structs, enums, strings, and function values behind a pointer,
+    /// and everything else as i32 for now
+    fn llvm_type(&self, ty: &Type) -> String {
+        match ty {
+            Type::Simple(name) if self.structs.contains_key(&name.value) => {
+                format!("%{}", mangle(&[&self.module_name, &name.value], &[]))
+            }
+            Type::Simple(name) if self.enums.contains_key(&name.value) => "ptr".to_string(),
+            Type::Simple(name) if name.value == "str" => "ptr".to_string(),
+            Type::Function { .. } => "ptr".to_string(),
+            Type::Tuple(types) if !types.is_empty() => {
+                let fields: Vec<String> = types.iter().map(|t| self.llvm_type(t)).collect();
+                format!("{{ {} }}", fields.join(", "))
+            }
+            _ => "i32".to_string(),
+        }
+    }
+
+    /// Emit an enum's tagged-union type, `{ i32 tag, [N x i32] payload }` with
+    /// N the widest variant's field count, and a constructor per variant that
+    /// writes the tag and payload through an out pointer
+    fn generate_enum(&self, name: &str) -> String {
+        let variants = &self.enums[name];
+        let ty = format!("%{}", mangle(&[&self.module_name, name], &[]));
+        let slots = variants.iter().map(|v| v.fields).max().unwrap_or(0);
+
+        let mut ir = format!("{} = type {{ i32, [{} x i32] }}\n", ty, slots);
+        for (tag, variant) in variants.iter().enumerate() {
+            let symbol = mangle(&[&self.module_name, name, &variant.name], &[]);
+            let params: String = (0..variant.fields)
+                .map(|i| format!(", i32 %p{}", i))
+                .collect();
+
+            ir.push_str(&format!(
+                "\ndefine void @{}(ptr %out{}) {{\n",
+                symbol, params
+            ));
+            ir.push_str("entry:\n");
+            ir.push_str(&format!(
+                "  %tag = getelementptr {}, ptr %out, i32 0, i32 0\n",
+                ty
+            ));
+            ir.push_str(&format!("  store i32 {}, ptr %tag\n", tag));
+            for i in 0..variant.fields {
+                ir.push_str(&format!(
+                    "  %slot{} = getelementptr {}, ptr %out, i32 0, i32 1, i32 {}\n",
+                    i, ty, i
+                ));
+                ir.push_str(&format!("  store i32 %p{}, ptr %slot{}\n", i, i));
+            }
+            ir.push_str("  ret void\n}\n");
+        }
+
+        ir
+    }
+
+    /// Lower a method body of the form `match self { ... }` to a `switch` on
+    /// the enum tag. Each arm loads the payload fields it binds; arm values
+    /// other than integer literals and bindings are not lowered yet.
+    fn generate_self_match(
+        &mut self,
+        func: &Function,
+        owner: &str,
+        variants: &[EnumVariant],
+        arms: &[MatchArm],
+        return_ty: &str,
+    ) -> String {
+        let ty = format!("%{}", mangle(&[&self.module_name, owner], &[]));
+        let tag_of = |name: &str| variants.iter().position(|v| v.name == name);
+
+        let mut ir = String::new();
+        ir.push_str(&format!(
+            "  %tag.ptr = getelementptr {}, ptr %self, i32 0, i32 0\n",
+            ty
+        ));
+        ir.push_str("  %tag = load i32, ptr %tag.ptr\n");
+
+        // Route each tag to the first arm that matches it
+        let mut cases = Vec::new();
+        let mut default = "match.none".to_string();
+        for (i, arm) in arms.iter().enumerate() {
+            let alternatives = match &arm.pattern {
+                Pattern::Or(alternatives) => alternatives.as_slice(),
+                pattern => std::slice::from_ref(pattern),
+            };
+            // `_` and a binding that isn't a variant name catch every other tag
+            let catch_all = alternatives.iter().any(|pattern| match pattern {
+                Pattern::Wildcard => true,
+                Pattern::Ident(name) => tag_of(name).is_none(),
+                _ => false,
+            });
+            if catch_all {
+                default = format!("arm{}", i);
+                break;
+            }
+            for pattern in alternatives {
+                if let Pattern::Variant { name, .. }
+                | Pattern::Struct { name, .. }
+                | Pattern::Ident(name) = pattern
+                {
+                    if let Some(tag) = tag_of(name) {
+                        if !cases.iter().any(|(t, _)| *t == tag) {
+                            cases.push((tag, format!("arm{}", i)));
+                        }
+                    }
+                }
+            }
+        }
+
+        ir.push_str(&format!("  switch i32 %tag, label %{} [\n", default));
+        for (tag, label) in &cases {
+            ir.push_str(&format!("    i32 {}, label %{}\n", tag, label));
+        }
+        ir.push_str("  ]\n");
+
+        for (i, arm) in arms.iter().enumerate() {
+            ir.push_str(&format!("\narm{}:\n", i));
+
+            // Load the payload fields bound by the pattern
+            let mut bindings = HashMap::new();
+            let slots: Vec<(usize, &Pattern)> = match &arm.pattern {
+                Pattern::Variant { patterns, .. } => patterns.iter().enumerate().collect(),
+                Pattern::Struct { name, fields } => {
+                    let names = variants
+                        .iter()
+                        .find(|v| v.name == *name)
+                        .map(|v| v.names.as_slice())
+                        .unwrap_or_default();
+                    fields
+                        .iter()
+                        .filter_map(|(field, pattern)| {
+                            let slot = names.iter().position(|n| *n == field.value)?;
+                            Some((slot, pattern))
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            for (slot, pattern) in slots {
+                if let Pattern::Ident(binding) = pattern {
+                    let reg = format!("%arm{}.{}", i, binding);
+                    ir.push_str(&format!(
+                        "  {}.ptr = getelementptr {}, ptr %self, i32 0, i32 1, i32 {}\n",
+                        reg, ty, slot
+                    ));
+                    ir.push_str(&format!("  {} = load i32, ptr {}.ptr\n", reg, reg));
+                    bindings.insert(binding.as_str(), reg);
+                }
+            }
+
+            if return_ty == "void" {
+                ir.push_str("  ret void\n");
+                continue;
+            }
+            let value = match &arm.body.kind {
+                ExprKind::Integer(n) => n.to_string(),
+                ExprKind::Ident(name) if bindings.contains_key(name.as_str()) => {
+                    bindings[name.as_str()].clone()
+                }
+                _ => {
+                    self.skipped.push(Spanned::new(
+                        format!("match arm in function '{}'", func.name.value),
+                        arm.body.span,
+                    ));
+                    "0".to_string()
+                }
+            };
+            ir.push_str(&format!("  ret i32 {}\n", value));
+        }
+
+        if default == "match.none" {
+            ir.push_str("\nmatch.none:\n  unreachable\n");
+        }
+
+        ir
+    }
+
+    /// Lower a body of the form `match param { "a" => ..., _ => ... }` on a
+    /// string parameter. LLVM's `switch` only takes integers, so each string
+    /// arm becomes a `strcmp` against a private constant, tried in order.
+    /// Returns the constants' definitions and the function body.
+    fn generate_string_match(
+        &mut self,
+        func: &Function,
+        owner: Option<&str>,
+        param: &str,
+        arms: &[MatchArm],
+        return_ty: &str,
+    ) -> (String, String) {
+        let module_name = self.module_name.clone();
+        let mut path = vec![module_name.as_str()];
+        path.extend(owner);
+        path.push(&func.name.value);
+
+        let mut globals = String::new();
+        let mut ir = String::new();
+        let mut exhaustive = false;
+
+        for (i, arm) in arms.iter().enumerate() {
+            let Pattern::Literal(Literal::String(text)) = &arm.pattern else {
+                // A wildcard or binding catches everything left
+                ir.push_str(&format!("  br label %arm{}\n", i));
+                exhaustive = true;
+                break;
+            };
+
+            let label = format!("str{}", i);
+            let constant = mangle(&[path.as_slice(), &[&label]].concat(), &[]);
+            let bytes = text.len() + 1;
+            globals.push_str(&format!(
+                "@{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n",
+                constant,
+                bytes,
+                escape_bytes(text)
+            ));
+
+            let next = if i + 1 < arms.len() {
+                format!("case{}", i + 1)
+            } else {
+                "match.none".to_string()
+            };
+            ir.push_str(&format!(
+                "  %cmp{} = call i32 @strcmp(ptr %{}, ptr @{})\n",
+                i, param, constant
+            ));
+            ir.push_str(&format!("  %eq{} = icmp eq i32 %cmp{}, 0\n", i, i));
+            ir.push_str(&format!(
+                "  br i1 %eq{}, label %arm{}, label %{}\n",
+                i, i, next
+            ));
+            if i + 1 < arms.len() {
+                ir.push_str(&format!("\n{}:\n", next));
+            }
+        }
+
+        for (i, arm) in arms.iter().enumerate() {
+            ir.push_str(&format!("\narm{}:\n", i));
+            if return_ty == "void" {
+                ir.push_str("  ret void\n");
+                continue;
+            }
+            let value = match &arm.body.kind {
+                ExprKind::Integer(n) => n.to_string(),
+                _ => {
+                    self.skipped.push(Spanned::new(
+                        format!("match arm in function '{}'", func.name.value),
+                        arm.body.span,
+                    ));
+                    "0".to_string()
+                }
+            };
+            ir.push_str(&format!("  ret i32 {}\n", value));
+        }
+
+        if !exhaustive {
+            ir.push_str("\nmatch.none:\n  unreachable\n");
+        }
+        globals.push('\n');
+
+        (globals, ir)
+    }
+
+    /// Translate source attributes into LLVM function attributes
+    fn function_attributes(&mut self, func: &Function) -> Vec<&'static str> {
+        let mut attrs = Vec::new();
+
+        for attr in &func.attributes {
+            let args: Vec<&str> = attr.args.iter().map(|a| a.value.as_str()).collect();
+            let llvm_attr = match (attr.name.value.as_str(), args.as_slice()) {
+                ("inline", []) => Some("inlinehint"),
+                ("inline", ["always"]) => Some("alwaysinline"),
+                ("inline", ["never"]) => Some("noinline"),
+                ("cold", []) => Some("cold"),
+                _ => None,
+            };
+
+            match llvm_attr {
+                Some(llvm_attr) => {
+                    if !attrs.contains(&llvm_attr) {
+                        attrs.push(llvm_attr);
+                    }
+                }
+                None => self.warnings.push(Spanned::new(
+                    format!(
+                        "Unknown attribute '{}' on function '{}' ignored",
+                        attr.name.value, func.name.value
+                    ),
+                    attr.name.span,
+                )),
+            }
+        }
+
+        attrs
+    }
+}
+
+/// Lowers a function body of simple statements: i32 arithmetic over integer
+/// literals, parameters, and `let` bindings, assignments, `println` of a
+/// string literal, `if`/`else`, and `while` and `loop` with `break` and
+/// `continue`. Each binding gets a stack slot it is stored to and loaded
+/// from; each operation and load gets the next unnamed SSA temporary.
+struct BodyLowering<'a> {
+    ir: String,
+    /// Slots for every binding, hoisted to the entry block
+    allocas: String,
+    next_temp: usize,
+    next_loop: usize,
+    next_if: usize,
+    /// The label of the block being emitted, which a `phi` names as the
+    /// predecessor its value comes from
+    block: String,
+    /// How the function returns
+    exit: Exit,
+    /// Whether the block being emitted already ended in a branch or `ret`
+    terminated: bool,
+    /// The SSA value of each i32 parameter
+    params: HashMap<String, String>,
+    /// The SSA value of each function pointer parameter that can be called
+    callees: HashMap<String, String>,
+    /// The stack slot of each `let` binding in scope
+    variables: HashMap<String, String>,
+    /// How many slots each binding name has had, so bindings in sibling
+    /// blocks get their own
+    slots: HashMap<String, usize>,
+    /// Where `break` and `continue` go for each enclosing loop, innermost last
+    loops: Vec<LoopTargets>,
+    /// The module's string constants, which string literals are added to
+    strings: &'a mut Vec<String>,
+}
+
+/// How a lowered body leaves its function
+enum Exit {
+    /// With the i32 value of its trailing expression or `return`
+    Value,
+    /// By reaching the end or a bare `return`, with this instruction
+    Implicit(&'static str),
+}
+
+/// The blocks a loop's `continue` and `break` branch to
+struct LoopTargets {
+    continue_to: String,
+    break_to: String,
+}
+
+impl<'a> BodyLowering<'a> {
+    fn new(
+        params: &[(String, &'a str)],
+        callees: &[&str],
+        exit: Exit,
+        strings: &'a mut Vec<String>,
+    ) -> Self {
+        let scalars = params.iter().filter(|(ty, _)| ty == "i32");
+        BodyLowering {
+            ir: String::new(),
+            allocas: String::new(),
+            next_temp: 0,
+            next_loop: 0,
+            next_if: 0,
+            block: "entry".to_string(),
+            exit,
+            terminated: false,
+            params: scalars
+                .map(|(_, name)| (name.to_string(), format!("%{}", name)))
+                .collect(),
+            callees: callees
+                .iter()
+                .map(|name| (name.to_string(), format!("%{}", name)))
+                .collect(),
+            variables: HashMap::new(),
+            slots: HashMap::new(),
+            loops: Vec::new(),
+            strings,
+        }
+    }
+
+    /// Lower a function body through to its `ret`, or return false if it
+    /// uses anything not lowered yet
+    fn body(&mut self, block: &Block) -> Result<bool> {
+        let (stmts, tail) = match self.exit {
+            Exit::Value => block_value(block),
+            Exit::Implicit(_) => (block.stmts.as_slice(), block.expr.as_deref()),
+        };
+        if !self.stmts(stmts)? {
+            return Ok(false);
+        }
+        if self.terminated {
+            return Ok(true);
+        }
+
+        match (tail, &self.exit) {
+            (Some(expr), Exit::Implicit(_)) => Ok(self.statement(expr)? && self.exit(None)?),
+            (expr, _) => self.exit(expr),
+        }
+    }
+
+    /// The IR of the lowered body, slots first
+    fn finish(self) -> String {
+        self.allocas + &self.ir
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) -> Result<bool> {
+        for stmt in stmts {
+            // Anything after a `return`, `break`, or `continue` is unreachable
+            if self.terminated {
+                break;
+            }
+
+            let lowered = match stmt {
+                Stmt::Let {
+                    name, ty, value, ..
+                } if ty
+                    .as_ref()
+                    .is_none_or(|ty| matches!(ty, Type::Simple(t) if t.value == "i32")) =>
+                {
+                    self.binding(&name.value, value.as_ref())?
+                }
+                Stmt::Expr(expr) => self.statement(expr)?,
+                Stmt::Return(value) => self.exit(value.as_ref())?,
+                Stmt::While { condition, body } => self.lower_loop(Some(condition), body)?,
+                Stmt::Loop { body } => self.lower_loop(None, body)?,
+                Stmt::Break(_) | Stmt::Continue(_) => self.jump(stmt)?,
+                _ => false,
+            };
+            if !lowered {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Give a `let` binding its slot and store its initial value, if any
+    fn binding(&mut self, name: &str, value: Option<&Expr>) -> Result<bool> {
+        let value = match value {
+            Some(value) => match self.expr(value)? {
+                Some(value) => Some(value),
+                None => return Ok(false),
+            },
+            None => None,
+        };
+
+        let count = self.slots.entry(name.to_string()).or_default();
+        let slot = match *count {
+            0 => format!("%{}.addr", name),
+            n => format!("%{}.addr{}", name, n),
+        };
+        *count += 1;
+
+        self.allocas.push_str(&format!("  {} = alloca i32\n", slot));
+        if let Some(value) = value {
+            self.ir
+                .push_str(&format!("  store i32 {}, ptr {}\n", value, slot));
+        }
+        self.variables.insert(name.to_string(), slot);
+        Ok(true)
+    }
+
+    /// Emit the `ret` for a `return` or the end of the body
+    fn exit(&mut self, value: Option<&Expr>) -> Result<bool> {
+        let ret = match (value, &self.exit) {
+            (Some(value), Exit::Value) => match self.expr(value)? {
+                Some(value) => format!("ret i32 {}", value),
+                None => return Ok(false),
+            },
+            (None, Exit::Implicit(ret)) => ret.to_string(),
+            _ => return Ok(false),
+        };
+        self.ir.push_str(&format!("  {}\n", ret));
+        self.terminated = true;
+        Ok(true)
+    }
+
+    /// Lower a `while` loop, or a `loop` when there is no condition
+    fn lower_loop(&mut self, condition: Option<&Expr>, body: &Block) -> Result<bool> {
+        let id = self.next_loop;
+        self.next_loop += 1;
+        let head = format!("loop{}.head", id);
+        let body_label = format!("loop{}.body", id);
+        let end = format!("loop{}.end", id);
+
+        self.branch(&head);
+        if let Some(condition) = condition {
+            let Some(value) = self.condition(condition)? else {
+                return Ok(false);
+            };
+            self.ir.push_str(&format!(
+                "  br i1 {}, label %{}, label %{}\n",
+                value, body_label, end
+            ));
+            self.label(&body_label);
+        }
+
+        self.loops.push(LoopTargets {
+            continue_to: head.clone(),
+            break_to: end.clone(),
+        });
+        let scope = self.variables.clone();
+        let mut lowered = self.stmts(&body.stmts)?;
+        if let (true, false, Some(expr)) = (lowered, self.terminated, body.expr.as_deref()) {
+            lowered = self.statement(expr)?;
+        }
+        self.variables = scope;
+        self.loops.pop();
+        if !lowered {
+            return Ok(false);
+        }
+
+        if !self.terminated {
+            self.ir.push_str(&format!("  br label %{}\n", head));
+        }
+        self.label(&end);
+        Ok(true)
+    }
+
+    /// Branch to the target of a `break` or `continue`
+    fn jump(&mut self, stmt: &Stmt) -> Result<bool> {
+        let innermost = self.loops.last();
+        let (keyword, span, target) = match stmt {
+            Stmt::Break(span) => ("break", *span, innermost.map(|t| t.break_to.clone())),
+            Stmt::Continue(span) => ("continue", *span, innermost.map(|t| t.continue_to.clone())),
+            _ => unreachable!("jump only lowers `break` and `continue`"),
+        };
+        let Some(target) = target else {
+            return Err(Error::new(
+                ErrorKind::InternalError,
+                span,
+                format!("`{}` has no enclosing loop to branch to", keyword),
+            ));
+        };
+
+        self.ir.push_str(&format!("  br label %{}\n", target));
+        self.terminated = true;
+        Ok(true)
+    }
+
+    /// Fall through into a new block
+    fn branch(&mut self, label: &str) {
+        self.ir.push_str(&format!("  br label %{}\n", label));
+        self.label(label);
+    }
+
+    /// Start emitting into a new block
+    fn label(&mut self, label: &str) {
+        self.ir.push_str(&format!("\n{}:\n", label));
+        self.block = label.to_string();
+        self.terminated = false;
+    }
+
+    /// Lower an `if` to a conditional branch into `then` and `else` blocks
+    /// that meet again at `merge`. With `value` set, the `if` must have an
+    /// `else` and the branch values are joined with a `phi`, which is
+    /// returned; otherwise the result is empty. None if anything in it isn't
+    /// lowered yet.
+    fn lower_if(&mut self, expr: &Expr, value: bool) -> Result<Option<String>> {
+        let ExprKind::If {
+            condition,
+            then_block,
+            else_block,
+        } = &expr.kind
+        else {
+            return Ok(None);
+        };
+        if value && else_block.is_none() {
+            return Ok(None);
+        }
+
+        let id = self.next_if;
+        self.next_if += 1;
+        let then_label = format!("if{}.then", id);
+        let else_label = format!("if{}.else", id);
+        let merge = format!("if{}.merge", id);
+
+        let Some(condition) = self.condition(condition)? else {
+            return Ok(None);
+        };
+        let otherwise = if else_block.is_some() {
+            &else_label
+        } else {
+            &merge
+        };
+        self.ir.push_str(&format!(
+            "  br i1 {}, label %{}, label %{}\n",
+            condition, then_label, otherwise
+        ));
+
+        // The value each branch that reaches `merge` leaves, and its block
+        let mut incoming = Vec::new();
+        let branches = [
+            (&then_label, Some(then_block)),
+            (&else_label, else_block.as_ref()),
+        ];
+        for (label, block) in branches {
+            let Some(block) = block else {
+                continue;
+            };
+            self.label(label);
+            let scope = self.variables.clone();
+            let result = self.if_branch(block, value)?;
+            self.variables = scope;
+            let Some(result) = result else {
+                return Ok(None);
+            };
+            if !self.terminated {
+                incoming.push(format!("[ {}, %{} ]", result, self.block));
+                self.ir.push_str(&format!("  br label %{}\n", merge));
+            }
+        }
+
+        // When every branch returns or jumps, nothing reaches `merge`
+        if incoming.is_empty() && else_block.is_some() {
+            self.terminated = true;
+            return Ok((!value).then(String::new));
+        }
+
+        self.label(&merge);
+        if !value {
+            return Ok(Some(String::new()));
+        }
+        let temp = self.temp();
+        self.ir
+            .push_str(&format!("  {} = phi i32 {}\n", temp, incoming.join(", ")));
+        Ok(Some(temp))
+    }
+
+    /// Lower one branch of an `if`, returning its trailing value when
+    /// `value` is set (empty otherwise, or if the branch doesn't fall
+    /// through), or None if anything in it isn't lowered yet
+    fn if_branch(&mut self, block: &Block, value: bool) -> Result<Option<String>> {
+        let (stmts, tail) = if value {
+            block_value(block)
+        } else {
+            (block.stmts.as_slice(), block.expr.as_deref())
+        };
+        if !self.stmts(stmts)? {
+            return Ok(None);
+        }
+        if self.terminated {
+            return Ok(Some(String::new()));
+        }
+        match (tail, value) {
+            (Some(expr), true) => self.expr(expr),
+            (Some(expr), false) => Ok(self.statement(expr)?.then(String::new)),
+            (None, true) => Ok(None),
+            (None, false) => Ok(Some(String::new())),
+        }
+    }
+
+    /// Lower a condition to an i1. Other values are i32s, so a bool
+    /// variable holds 0 or 1 and is compared against 0.
+    fn condition(&mut self, expr: &Expr) -> Result<Option<String>> {
+        let (op, left, right) = match &expr.kind {
+            ExprKind::Bool(b) => return Ok(Some(b.to_string())),
+            ExprKind::Binary { op, left, right } => (op, left, right),
+            ExprKind::Ident(_) => {
+                let Some(value) = self.expr(expr)? else {
+                    return Ok(None);
+                };
+                let temp = self.temp();
+                self.ir
+                    .push_str(&format!("  {} = icmp ne i32 {}, 0\n", temp, value));
+                return Ok(Some(temp));
+            }
+            _ => return Ok(None),
+        };
+        let predicate = match op {
+            BinOp::Eq => "eq",
+            BinOp::Ne => "ne",
+            BinOp::Lt => "slt",
+            BinOp::Le => "sle",
+            BinOp::Gt => "sgt",
+            BinOp::Ge => "sge",
+            _ => return Ok(None),
+        };
+        let (Some(left), Some(right)) = (self.expr(left)?, self.expr(right)?) else {
+            return Ok(None);
+        };
+
+        let temp = self.temp();
+        self.ir.push_str(&format!(
+            "  {} = icmp {} i32 {}, {}\n",
+            temp, predicate, left, right
+        ));
+        Ok(Some(temp))
+    }
+
+    /// Lower an expression evaluated for its effect
+    fn statement(&mut self, expr: &Expr) -> Result<bool> {
+        match &expr.kind {
+            ExprKind::If { .. } => Ok(self.lower_if(expr, false)?.is_some()),
+            ExprKind::Binary {
+                op: BinOp::Assign,
+                left,
+                right,
+            } => {
+                let slot = match &left.kind {
+                    ExprKind::Ident(name) => self.variables.get(name).cloned(),
+                    _ => None,
+                };
+                let (Some(slot), Some(value)) = (slot, self.expr(right)?) else {
+                    return Ok(false);
+                };
+                self.ir
+                    .push_str(&format!("  store i32 {}, ptr {}\n", value, slot));
+                Ok(true)
+            }
+            ExprKind::Call { func, args, .. } if matches!(&func.kind, ExprKind::Ident(name) if name == "println") =>
+            {
+                let [Expr {
+                    kind: ExprKind::String(text),
+                    ..
+                }] = args.as_slice()
+                else {
+                    return Ok(false);
+                };
+
+                let (constant, bytes) = self.string(text);
+                let pointer = self.temp();
+                self.ir.push_str(&format!(
+                    "  {} = getelementptr [{} x i8], ptr {}, i64 0, i64 0\n",
+                    pointer, bytes, constant
+                ));
+                let result = self.temp();
+                self.ir
+                    .push_str(&format!("  {} = call i32 @puts(ptr {})\n", result, pointer));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// The module constant holding `text`, and its length with the nul
+    fn string(&mut self, text: &str) -> (String, usize) {
+        let index = match self.strings.iter().position(|s| s == text) {
+            Some(index) => index,
+            None => {
+                self.strings.push(text.to_string());
+                self.strings.len() - 1
+            }
+        };
+        (format!("@.str.{}", index), text.len() + 1)
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<Option<String>> {
+        match &expr.kind {
+            ExprKind::Integer(n) => Ok(i32::try_from(*n).ok().map(|n| n.to_string())),
+            ExprKind::Ident(name) => {
+                if let Some(slot) = self.variables.get(name).cloned() {
+                    let temp = self.temp();
+                    self.ir
+                        .push_str(&format!("  {} = load i32, ptr {}\n", temp, slot));
+                    Ok(Some(temp))
+                } else {
+                    // Non-i32 parameters, module items, and prelude names
+                    // such as `None` aren't lowered yet
+                    Ok(self.params.get(name).cloned())
+                }
+            }
+            ExprKind::Binary { op, left, right } => {
+                let opcode = match op {
+                    BinOp::Add => "add",
+                    BinOp::Sub => "sub",
+                    BinOp::Mul => "mul",
+                    BinOp::Div => "sdiv",
+                    _ => return Ok(None),
+                };
+                let (Some(left), Some(right)) = (self.expr(left)?, self.expr(right)?) else {
+                    return Ok(None);
+                };
+
+                let temp = self.temp();
+                self.ir.push_str(&format!(
+                    "  {} = {} i32 {}, {}\n",
+                    temp, opcode, left, right
+                ));
+                Ok(Some(temp))
+            }
+            ExprKind::Call { func, args, .. } => {
+                let callee = match &func.kind {
+                    ExprKind::Ident(name) if !self.variables.contains_key(name) => {
+                        self.callees.get(name).cloned()
+                    }
+                    _ => None,
+                };
+                let Some(callee) = callee else {
+                    return Ok(None);
+                };
+
+                let mut operands = Vec::new();
+                for arg in args {
+                    let Some(value) = self.expr(arg)? else {
+                        return Ok(None);
+                    };
+                    operands.push(format!("i32 {}", value));
+                }
+                let temp = self.temp();
+                self.ir.push_str(&format!(
+                    "  {} = call i32 {}({})\n",
+                    temp,
+                    callee,
+                    operands.join(", ")
+                ));
+                Ok(Some(temp))
+            }
+            ExprKind::If { .. } => self.lower_if(expr, true),
+            _ => Ok(None),
+        }
+    }
+
+    /// The next unnamed SSA temporary
+    fn temp(&mut self) -> String {
+        let temp = format!("%{}", self.next_temp);
+        self.next_temp += 1;
+        temp
+    }
+}
+
+/// A block's statements and the expression giving its value. A block-like
+/// expression on the last line parses as a statement, so it counts as the
+/// value.
+fn block_value(block: &Block) -> (&[Stmt], Option<&Expr>) {
+    match (block.expr.as_deref(), block.stmts.split_last()) {
+        (Some(expr), _) => (&block.stmts, Some(expr)),
+        (None, Some((Stmt::Expr(expr), rest)))
+            if matches!(
+                expr.kind,
+                ExprKind::If { .. } | ExprKind::Match { .. } | ExprKind::Block(_)
+            ) =>
+        {
+            (rest, Some(expr))
+        }
+        _ => (&block.stmts, None),
+    }
+}
+
+/// Mangle a symbol name for LLVM. Every symbol except `main` is mangled so that
+/// functions from different modules, methods of different types, and generic
+/// instantiations can't collide:
+///
+/// - `.` joins path segments: `app.parse` for function `parse` in module
+///   `app`, `app.Point.new` for method `new` on `Point`
+/// - `$` precedes each generic argument: `app.max$i32`, `app.Pair.new$i32$str`
+///
+/// Neither character can appear in a Fruti identifier, but the module name
+/// comes from the file stem, so `%`, `.` and `$` in a segment are escaped as
+/// `%25`, `%2E` and `%24`: `a.b.fruti` gives `a%2Eb.f`, not `a.b.f`. Names
+/// with characters LLVM doesn't allow in a bare identifier (a module named
+/// after `my app.fruti`) are quoted.
+pub fn mangle(path: &[&str], generic_args: &[&str]) -> String {
+    let segments: Vec<String> = path
+        .iter()
+        .map(|segment| {
+            segment
+                .replace('%', "%25")
+                .replace('.', "%2E")
+                .replace('$', "%24")
+        })
+        .collect();
+    let mut name = segments.join(".");
+    for arg in generic_args {
+        name.push('$');
+        name.push_str(arg);
+    }
+
+    let is_bare = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '$' | '.' | '_'));
+    if is_bare {
+        name
+    } else {
+        format!("\"{}\"", name.replace('\\', "\\5C").replace('"', "\\22"))
+    }
+}
+
+/// The expression a body consists of, possibly behind a `return`
+fn sole_expr(body: &Block) -> Option<&Expr> {
+    match (body.stmts.as_slice(), &body.expr) {
+        ([], Some(expr)) => Some(expr),
+        // A block-like expression on the last line parses as a statement
+        ([Stmt::Expr(expr) | Stmt::Return(Some(expr))], None) => Some(expr),
+        _ => None,
+    }
+}
+
+/// The arms of a body consisting only of `match self { ... }` whose arms
+/// a `switch` on the tag can pick: no guards, and payload patterns that only
+/// bind or ignore fields
+fn self_match_arms(body: &Block) -> Option<&[MatchArm]> {
+    fn binds_only(pattern: &Pattern) -> bool {
+        matches!(pattern, Pattern::Ident(_) | Pattern::Wildcard)
+    }
+    fn switchable(pattern: &Pattern) -> bool {
+        match pattern {
+            Pattern::Wildcard | Pattern::Ident(_) => true,
+            Pattern::Variant { patterns, .. } => patterns.iter().all(binds_only),
+            Pattern::Struct { fields, .. } => fields.iter().all(|(_, p)| binds_only(p)),
+            Pattern::Or(alternatives) => alternatives.iter().all(switchable),
+            _ => false,
+        }
+    }
+
+    match &sole_expr(body)?.kind {
+        ExprKind::Match { expr, arms } if matches!(&expr.kind, ExprKind::Ident(n) if n == "self") =>
+        {
+            let switchable = arms
+                .iter()
+                .all(|arm| arm.guard.is_none() && switchable(&arm.pattern));
+            switchable.then_some(arms.as_slice())
+        }
+        _ => None,
+    }
+}
+
+/// The scrutinee parameter and arms of a body consisting only of a `match` on
+/// a `str` parameter whose arms are string literals up to an optional
+/// catch-all, with no guards
+fn string_match(func: &Function) -> Option<(&str, &[MatchArm])> {
+    let ExprKind::Match { expr, arms } = &sole_expr(&func.body)?.kind else {
+        return None;
+    };
+    let ExprKind::Ident(name) = &expr.kind else {
+        return None;
+    };
+    let param = func.params.iter().find(|p| p.name.value == *name)?;
+    if !matches!(&param.ty, Type::Simple(ty) if ty.value == "str") {
+        return None;
+    }
+
+    let lowerable = arms.iter().all(|arm| {
+        arm.guard.is_none()
+            && matches!(
+                arm.pattern,
+                Pattern::Literal(Literal::String(_)) | Pattern::Wildcard | Pattern::Ident(_)
+            )
+    });
+    let has_string = arms
+        .iter()
+        .any(|arm| matches!(arm.pattern, Pattern::Literal(Literal::String(_))));
+    (lowerable && has_string).then_some((param.name.value.as_str(), arms.as_slice()))
+}
+
+/// The major version of the `llc` on the path, e.g. 14 for "LLVM version 14.0.6"
+fn llc_major_version() -> Option<u32> {
+    let output = Command::new("llc").arg("--version").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let version = text.split("LLVM version ").nth(1)?;
+    version.split('.').next()?.trim().parse().ok()
+}
+
+/// Escape a string for an LLVM `c"..."` constant
+fn escape_bytes(text: &str) -> String {
+    text.bytes()
+        .map(|b| {
+            if (b == b' ' || b.is_ascii_graphic()) && b != b'"' && b != b'\\' {
+                (b as char).to_string()
+            } else {
+                format!("\\{:02X}", b)
+            }
+        })
+        .collect()
+}
+
+/// Describe an item for the skipped-constructs list, e.g. "struct 'Point'"
+fn describe_item(item: &Item) -> Spanned<String> {
+    let (kind, name) = match item {
+        Item::Function(f) => ("function", &f.name),
+        Item::Struct(s) => ("struct", &s.name),
+        Item::Enum(e) => ("enum", &e.name),
+        Item::Trait(t) => ("trait", &t.name),
+        Item::Impl(i) => ("impl", &i.type_name),
+        Item::TypeAlias(t) => ("type alias", &t.name),
+        Item::Const(c) => ("const", &c.name),
+        Item::Mod(m) => ("module", &m.name),
+        Item::Import(i) => {
+            let mut path: Vec<&str> = i.path.iter().map(|p| p.value.as_str()).collect();
+            let group: Vec<&str> = i.group.iter().map(|p| p.value.as_str()).collect();
+            let group = format!("{{{}}}", group.join(", "));
+            if i.glob {
+                path.push("*");
+            } else if !i.group.is_empty() {
+                path.push(&group);
+            }
+            let span = match (i.path.first(), i.path.last()) {
+                (Some(first), Some(last)) => first.span.merge(last.span),
+                _ => Span::empty(0),
+            };
+            return Spanned::new(format!("import '{}'", path.join("::")), span);
+        }
+    };
+
+    Spanned::new(format!("{} '{}'", kind, name.value), name.span)
 }
 
 // Placeholder for when we enable inkwell
@@ -310,3 +1656,326 @@ impl<'ctx> CodeGen<'ctx> {
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn generate(source: &str) -> (String, Vec<Spanned<String>>) {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let module = Parser::new(tokens).parse_module().unwrap();
+        let mut codegen = CodeGen::new("test".to_string());
+        let ir = codegen.generate_module(&module).unwrap();
+        (ir, codegen.warnings().to_vec())
+    }
+
+    #[test]
+    fn test_inline_always_attribute() {
+        let (ir, warnings) = generate("#[inline(always)]\nfn f() {\n}\n");
+        assert!(ir.contains("define void @test.f() alwaysinline {"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_inline_and_cold_attributes() {
+        let (ir, _) = generate("#[inline] fn f() {}\n#[cold] fn g() {}\n");
+        assert!(ir.contains("define void @test.f() inlinehint {"));
+        assert!(ir.contains("define void @test.g() cold {"));
+    }
+
+    #[test]
+    fn test_unsupported_constructs_reported() {
+        let source = "type Id = i32\nfn main() {\n    let s = \"five\";\n}\nfn empty() {}\n";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let module = Parser::new(tokens).parse_module().unwrap();
+        let mut codegen = CodeGen::new("test".to_string());
+        codegen.generate_module(&module).unwrap();
+
+        let skipped: Vec<&str> = codegen.skipped().iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(skipped, ["type alias 'Id'", "body of function 'main'"]);
+        assert_eq!(
+            &source[codegen.skipped()[0].span.start..codegen.skipped()[0].span.end],
+            "Id"
+        );
+
+        // Imports are described as they're written
+        let tokens = Lexer::new("import std::io::{Read, Write}\nimport math::*\n")
+            .tokenize()
+            .unwrap();
+        let module = Parser::new(tokens).parse_module().unwrap();
+        let mut codegen = CodeGen::new("test".to_string());
+        codegen.generate_module(&module).unwrap();
+        let skipped: Vec<&str> = codegen.skipped().iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(
+            skipped,
+            ["import 'std::io::{Read, Write}'", "import 'math::*'"]
+        );
+
+        assert_eq!(
+            CodeGen::capabilities().items,
+            ["Function", "Impl", "Enum", "Struct"]
+        );
+    }
+
+    #[test]
+    fn test_methods_get_distinct_mangled_names() {
+        let source = "struct A { x: i32 }\nstruct B { y: i32 }\nimpl A {\n    fn new() {}\n}\nimpl B {\n    fn new() {}\n}\nfn main() {}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains("define void @test.A.new() {"));
+        assert!(ir.contains("define void @test.B.new() {"));
+        assert!(ir.contains("define i32 @main() {"));
+    }
+
+    #[test]
+    fn test_struct_return_by_value() {
+        let source = "struct Point { x: i32, y: i32 }\nfn make(y: i32) -> Point {\n    Point { y: y, x: 1 }\n}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains("%test.Point = type { i32, i32 }"));
+        assert!(ir.contains("define %test.Point @test.make(i32 %y) {"));
+        assert!(ir.contains("  %agg0 = insertvalue %test.Point undef, i32 1, 0\n"));
+        assert!(ir.contains("  %agg1 = insertvalue %test.Point %agg0, i32 %y, 1\n"));
+        assert!(ir.contains("  ret %test.Point %agg1\n"));
+    }
+
+    #[test]
+    fn test_nested_struct_field_uses_its_type() {
+        let source = "struct Point { x: i32, y: i32 }\nstruct Line { start: Point, width: i32 }\nfn line(p: Point) -> Line {\n    Line { start: p, width: 1 }\n}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains("%test.Line = type { %test.Point, i32 }"));
+        assert!(ir.contains("  %agg0 = insertvalue %test.Line undef, %test.Point %p, 0\n"));
+        assert!(ir.contains("  %agg1 = insertvalue %test.Line %agg0, i32 1, 1\n"));
+    }
+
+    #[test]
+    fn test_tuple_return_by_value() {
+        let (ir, _) = generate("fn pair(a: i32) -> (i32, i32) {\n    return (a, 2);\n}\n");
+        assert!(ir.contains("define { i32, i32 } @test.pair(i32 %a) {"));
+        assert!(ir.contains("  ret { i32, i32 } %agg1\n"));
+    }
+
+    #[test]
+    fn test_function_parameters_are_pointers() {
+        let (ir, _) = generate("fn apply(f: fn(i32) -> i32, x: i32) -> i32 {}\n");
+        assert!(ir.contains("define i32 @test.apply(ptr %f, i32 %x) {"));
+    }
+
+    #[test]
+    fn test_call_through_function_pointer() {
+        let source = "fn apply(f: fn(i32, i32) -> i32, x: i32) -> i32 {\n    f(x, 2) + 1\n}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains("define i32 @test.apply(ptr %f, i32 %x) {"));
+        assert!(ir.contains("  %0 = call i32 %f(i32 %x, i32 2)\n  %1 = add i32 %0, 1\n"));
+    }
+
+    #[test]
+    fn test_enum_method_switches_on_tag() {
+        let source = "enum Shape { Circle(i32), Rect(i32, i32), Empty }\nimpl Shape {\n    fn size(self) -> i32 {\n        match self {\n            Circle(r) => r,\n            Rect(w, h) => w,\n            Empty => 0\n        }\n    }\n}\n";
+        let (ir, _) = generate(source);
+
+        assert!(ir.contains("%test.Shape = type { i32, [2 x i32] }"));
+        assert!(ir.contains("define void @test.Shape.Rect(ptr %out, i32 %p0, i32 %p1) {"));
+        assert!(ir.contains("define i32 @test.Shape.size(ptr %self) {"));
+        assert!(ir.contains(
+            "  switch i32 %tag, label %match.none [\n    i32 0, label %arm0\n    i32 1, label %arm1\n    i32 2, label %arm2\n  ]"
+        ));
+        assert!(ir.contains("  ret i32 %arm0.r\n"));
+    }
+
+    #[test]
+    fn test_enum_method_catch_all_binding_is_default() {
+        let source = "enum Shape { Circle(i32), Empty }\nimpl Shape {\n    fn size(self) -> i32 {\n        match self {\n            Circle(r) => r,\n            other => 0\n        }\n    }\n}\n";
+        let (ir, _) = generate(source);
+
+        assert!(ir.contains("  switch i32 %tag, label %arm1 [\n    i32 0, label %arm0\n  ]"));
+        assert!(!ir.contains("match.none"));
+    }
+
+    #[test]
+    fn test_enum_method_with_refutable_arms_is_stubbed() {
+        for arm in ["Circle(0) => 1,", "Circle(r) if r > 0 => r,"] {
+            let source = format!(
+                "enum Shape {{ Circle(i32), Empty }}\nimpl Shape {{\n    fn size(self) -> i32 {{\n        match self {{\n            {}\n            _ => 0\n        }}\n    }}\n}}\n",
+                arm
+            );
+            let module = Parser::new(Lexer::new(&source).tokenize().unwrap())
+                .parse_module()
+                .unwrap();
+            let mut codegen = CodeGen::new("test".to_string());
+            let ir = codegen.generate_module(&module).unwrap();
+
+            assert!(!ir.contains("switch"));
+            assert!(ir.contains("define i32 @test.Shape.size(ptr %self) {\nentry:\n  ret i32 0\n}"));
+            let skipped: Vec<&str> = codegen.skipped().iter().map(|s| s.value.as_str()).collect();
+            assert_eq!(skipped, ["body of function 'size'"]);
+        }
+    }
+
+    #[test]
+    fn test_string_match_compares_with_strcmp() {
+        let source = "fn code(s: str) -> i32 {\n    match s {\n        \"get\" => 1,\n        \"put\" => 2,\n        _ => 0\n    }\n}\n";
+        let (ir, _) = generate(source);
+
+        assert!(ir.contains("declare i32 @strcmp(ptr, ptr)\n"));
+        assert!(
+            ir.contains("@test.code.str1 = private unnamed_addr constant [4 x i8] c\"put\\00\"\n")
+        );
+        assert!(ir.contains("define i32 @test.code(ptr %s) {"));
+        assert!(ir.contains("  %cmp0 = call i32 @strcmp(ptr %s, ptr @test.code.str0)\n"));
+        assert!(ir.contains("  br i1 %eq1, label %arm1, label %case2\n"));
+        assert!(ir.contains("case2:\n  br label %arm2\n"));
+        assert!(ir.contains("arm2:\n  ret i32 0\n"));
+        assert!(!ir.contains("match.none"));
+    }
+
+    #[test]
+    fn test_arithmetic_body() {
+        let (ir, _) = generate("fn f() -> i32 {\n    2 + 3 * 4\n}\n");
+        assert!(ir.contains(
+            "define i32 @test.f() {\nentry:\n  %0 = mul i32 3, 4\n  %1 = add i32 2, %0\n  ret i32 %1\n}"
+        ));
+
+        let source =
+            "fn avg(a: i32, b: i32) -> i32 {\n    let sum = a + b;\n    return sum / 2;\n}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains(
+            "  %sum.addr = alloca i32\n  %0 = add i32 %a, %b\n  store i32 %0, ptr %sum.addr\n  %1 = load i32, ptr %sum.addr\n  %2 = sdiv i32 %1, 2\n  ret i32 %2\n"
+        ));
+    }
+
+    #[test]
+    fn test_println_uses_string_constants() {
+        let source = "fn greet() {\n    println(\"hi\\n\");\n}\nfn main() {\n    println(\"hi\\n\");\n    println(\"bye\")\n}\n";
+        let (ir, _) = generate(source);
+
+        // Equal literals share a constant, which goes ahead of the functions
+        assert!(ir.contains(
+            "@.str.0 = private unnamed_addr constant [4 x i8] c\"hi\\0A\\00\"\n@.str.1 = private unnamed_addr constant [4 x i8] c\"bye\\00\"\n"
+        ));
+        assert!(ir.find("@.str.0 =").unwrap() < ir.find("define void @test.greet").unwrap());
+        assert!(ir.contains(
+            "define void @test.greet() {\nentry:\n  %0 = getelementptr [4 x i8], ptr @.str.0, i64 0, i64 0\n  %1 = call i32 @puts(ptr %0)\n  ret void\n}"
+        ));
+        assert!(ir.contains("  %3 = call i32 @puts(ptr %2)\n  ret i32 0\n}"));
+    }
+
+    #[test]
+    fn test_unit_function_ending_in_call() {
+        // The call on the last line is the body's value, which a unit function drops
+        let (ir, _) = generate("fn greet() {\n    println(\"hi\")\n}\n");
+        assert!(ir.contains("  %1 = call i32 @puts(ptr %0)\n  ret void\n}"));
+
+        // A call codegen can't lower yet is reported, not miscompiled
+        let source = "fn log(x: i32) {\n}\nfn f() {\n    log(2)\n}\n";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let module = Parser::new(tokens).parse_module().unwrap();
+        let mut codegen = CodeGen::new("test".to_string());
+        codegen.generate_module(&module).unwrap();
+        let skipped: Vec<&str> = codegen.skipped().iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(skipped, ["body of function 'f'"]);
+    }
+
+    #[test]
+    fn test_while_loop_blocks_and_back_edge() {
+        let source = "fn count(n: i32) -> i32 {\n    let mut i = 0;\n    while i < n {\n        i = i + 1;\n    }\n    i\n}\n";
+        let (ir, _) = generate(source);
+
+        for label in ["loop0.head:", "loop0.body:", "loop0.end:"] {
+            assert_eq!(ir.matches(label).count(), 1, "{}", label);
+        }
+        // The condition is evaluated afresh in the head on every iteration
+        assert!(ir.contains(
+            "  br label %loop0.head\n\nloop0.head:\n  %0 = load i32, ptr %i.addr\n  %1 = icmp slt i32 %0, %n\n"
+        ));
+        // The body ends with the back-edge to the head
+        assert!(ir.contains("  store i32 %3, ptr %i.addr\n  br label %loop0.head\n\nloop0.end:\n"));
+    }
+
+    #[test]
+    fn test_nested_loops_branch_to_their_own_targets() {
+        let source = "fn count(n: i32) -> i32 {\n    let mut total = 0;\n    let mut i = 0;\n    while i < n {\n        i = i + 1;\n        loop {\n            total = total + i;\n            break;\n        }\n        continue;\n    }\n    total\n}\n";
+        let (ir, _) = generate(source);
+
+        assert!(ir.contains(
+            "  %1 = icmp slt i32 %0, %n\n  br i1 %1, label %loop0.body, label %loop0.end\n"
+        ));
+        // The inner `break` leaves only the inner loop, and the outer
+        // `continue` goes back to the outer condition
+        assert!(ir.contains(
+            "  br label %loop1.end\n\nloop1.end:\n  br label %loop0.head\n\nloop0.end:\n"
+        ));
+
+        let tokens = Lexer::new("fn main() {\n    break;\n}\n")
+            .tokenize()
+            .unwrap();
+        let module = Parser::new(tokens).parse_module().unwrap();
+        let err = CodeGen::new("test".to_string())
+            .generate_module(&module)
+            .unwrap_err();
+        assert_eq!(err.kind, crate::error::ErrorKind::InternalError);
+        assert_eq!(err.message, "`break` has no enclosing loop to branch to");
+    }
+
+    #[test]
+    fn test_if_else_joins_values_with_phi() {
+        let (ir, _) = generate("fn pick(c: bool) -> i32 {\n    if c { 1 } else { 2 }\n}\n");
+        assert!(ir.contains(
+            "entry:\n  %0 = icmp ne i32 %c, 0\n  br i1 %0, label %if0.then, label %if0.else\n\nif0.then:\n  br label %if0.merge\n\nif0.else:\n  br label %if0.merge\n\nif0.merge:\n  %1 = phi i32 [ 1, %if0.then ], [ 2, %if0.else ]\n  ret i32 %1\n"
+        ));
+
+        // Without an `else` there is no value to join, and a branch that
+        // returns doesn't reach the merge block
+        let source =
+            "fn clamp(n: i32) -> i32 {\n    if n > 9 {\n        return 9;\n    }\n    n\n}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains(
+            "  br i1 %0, label %if0.then, label %if0.merge\n\nif0.then:\n  ret i32 9\n\nif0.merge:\n  ret i32 %n\n"
+        ));
+    }
+
+    #[test]
+    fn test_let_bindings_load_from_slots() {
+        let (ir, _) = generate("fn f() -> i32 {\n    let x = 5;\n    x + 1\n}\n");
+        assert!(ir.contains(
+            "  %x.addr = alloca i32\n  store i32 5, ptr %x.addr\n  %0 = load i32, ptr %x.addr\n  %1 = add i32 %0, 1\n"
+        ));
+
+        let source = "fn f() -> i32 {\n    let mut x = 1;\n    x = x * 2;\n    x\n}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains(
+            "  %0 = load i32, ptr %x.addr\n  %1 = mul i32 %0, 2\n  store i32 %1, ptr %x.addr\n  %2 = load i32, ptr %x.addr\n"
+        ));
+
+        // Constants aren't lowered yet, so the body is left as a stub
+        let source = "const N: i32 = 2;\nfn f() -> i32 {\n    N + 1\n}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains("define i32 @test.f() {\nentry:\n  ret i32 0\n}"));
+
+        // So are prelude names and enum variants
+        let source = "enum Color { Red, Green }\nfn f() -> i32 {\n    let x = None;\n    let c = Red;\n    1\n}\n";
+        let (ir, _) = generate(source);
+        assert!(ir.contains("define i32 @test.f() {\nentry:\n  ret i32 0\n}"));
+    }
+
+    #[test]
+    fn test_mangle() {
+        assert_eq!(
+            mangle(&["app", "Pair", "new"], &["i32", "str"]),
+            "app.Pair.new$i32$str"
+        );
+        assert_eq!(mangle(&["my app", "run"], &[]), "\"my app.run\"");
+
+        // A dotted file stem can't pass for a nested path
+        assert_ne!(mangle(&["a.b", "f"], &[]), mangle(&["a", "b", "f"], &[]));
+        assert_eq!(mangle(&["a.b", "f"], &[]), "\"a%2Eb.f\"");
+    }
+
+    #[test]
+    fn test_unknown_attribute_warns() {
+        let (ir, warnings) = generate("#[frobnicate] fn f() {}\n");
+        assert!(ir.contains("define void @test.f() {"));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].value.contains("frobnicate"));
+    }
+}
@@ -0,0 +1,238 @@
+// Constant Evaluation - Fruti Compiler
+//
+// Folds compile-time constant expressions: literals, references to other
+// constants, and unary, arithmetic, bitwise, logical, and comparison
+// operators over them. Array lengths and constant initializers go through
+// here, so both accept exactly the same expressions.
+//
+// Integer arithmetic is checked, so overflow and division by zero are errors
+// rather than wrapped values. Floats are negated directly rather than
+// subtracted from zero, so that `-0.0` keeps its sign.
+
+use crate::ast::*;
+use crate::error::{Error, ErrorKind, Result};
+
+/// Names visible to a constant expression
+pub trait ConstEnv {
+    /// The folded value of `name`, if it names a constant
+    fn const_value(&self, name: &str) -> Option<Literal>;
+}
+
+/// Whether `expr` can be evaluated at compile time
+pub fn is_const_expr(expr: &Expr, consts: &dyn ConstEnv) -> bool {
+    eval_const(expr, consts).is_ok()
+}
+
+/// Evaluate a constant expression, or report the subexpression that isn't one
+pub fn eval_const(expr: &Expr, consts: &dyn ConstEnv) -> Result<Literal> {
+    match &expr.kind {
+        ExprKind::Integer(value) => Ok(Literal::Integer(*value)),
+        ExprKind::Float(value) => Ok(Literal::Float(*value)),
+        ExprKind::String(value) => Ok(Literal::String(value.clone())),
+        ExprKind::Char(value) => Ok(Literal::Char(*value)),
+        ExprKind::Bool(value) => Ok(Literal::Bool(*value)),
+        ExprKind::Byte(value) => Ok(Literal::Integer(i64::from(*value))),
+        ExprKind::Ident(name) => consts.const_value(name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::SemanticError,
+                expr.span,
+                format!("'{}' is not a constant", name),
+            )
+        }),
+        ExprKind::Unary { op, expr: operand } => match (op, eval_const(operand, consts)?) {
+            (UnOp::Neg, Literal::Integer(v)) => v
+                .checked_neg()
+                .map(Literal::Integer)
+                .ok_or_else(|| overflow(expr)),
+            (UnOp::Neg, Literal::Float(v)) => Ok(Literal::Float(-v)),
+            (UnOp::Not, Literal::Bool(b)) => Ok(Literal::Bool(!b)),
+            (UnOp::BitNot, Literal::Integer(v)) => Ok(Literal::Integer(!v)),
+            (op, value) => Err(Error::new(
+                ErrorKind::TypeMismatch,
+                expr.span,
+                format!(
+                    "Operator {:?} cannot be applied to {:?} in a constant expression",
+                    op, value
+                ),
+            )),
+        },
+        ExprKind::Binary { op, left, right } => {
+            let l = eval_const(left, consts)?;
+            let r = eval_const(right, consts)?;
+            eval_binary(*op, l, r, expr)
+        }
+        ExprKind::Call { .. } | ExprKind::MethodCall { .. } => Err(Error::new(
+            ErrorKind::SemanticError,
+            expr.span,
+            "Function calls are not allowed in constant expressions",
+        )),
+        _ => Err(Error::new(
+            ErrorKind::SemanticError,
+            expr.span,
+            "Expression is not a compile-time constant",
+        )),
+    }
+}
+
+fn eval_binary(op: BinOp, l: Literal, r: Literal, expr: &Expr) -> Result<Literal> {
+    use BinOp::*;
+
+    let value = match (&l, &r) {
+        (Literal::Integer(a), Literal::Integer(b)) => {
+            let (a, b) = (*a, *b);
+            if matches!(op, Div | Rem) && b == 0 {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    expr.span,
+                    "Division by zero in constant expression",
+                ));
+            }
+            let shift = u32::try_from(b).ok();
+            let folded = match op {
+                Add => a.checked_add(b),
+                Sub => a.checked_sub(b),
+                Mul => a.checked_mul(b),
+                Div => a.checked_div(b),
+                Rem => a.checked_rem(b),
+                BitAnd => Some(a & b),
+                BitOr => Some(a | b),
+                BitXor => Some(a ^ b),
+                Shl => shift.and_then(|s| a.checked_shl(s)),
+                Shr => shift.and_then(|s| a.checked_shr(s)),
+                _ => return compare(op, a.cmp(&b), &l, &r, expr),
+            };
+            Literal::Integer(folded.ok_or_else(|| overflow(expr))?)
+        }
+        (Literal::Float(a), Literal::Float(b)) => match op {
+            Add => Literal::Float(a + b),
+            Sub => Literal::Float(a - b),
+            Mul => Literal::Float(a * b),
+            Div => Literal::Float(a / b),
+            Rem => Literal::Float(a % b),
+            // Any comparison with NaN is false, except !=
+            _ => match a.partial_cmp(b) {
+                Some(ordering) => return compare(op, ordering, &l, &r, expr),
+                None if matches!(op, Eq | Lt | Le | Gt | Ge) => Literal::Bool(false),
+                None if op == Ne => Literal::Bool(true),
+                None => return Err(unsupported(op, &l, &r, expr)),
+            },
+        },
+        (Literal::Bool(a), Literal::Bool(b)) => match op {
+            And => Literal::Bool(*a && *b),
+            Or => Literal::Bool(*a || *b),
+            _ => return compare(op, a.cmp(b), &l, &r, expr),
+        },
+        (Literal::String(a), Literal::String(b)) => return compare(op, a.cmp(b), &l, &r, expr),
+        (Literal::Char(a), Literal::Char(b)) => return compare(op, a.cmp(b), &l, &r, expr),
+        _ => return Err(unsupported(op, &l, &r, expr)),
+    };
+
+    Ok(value)
+}
+
+/// Fold a comparison operator given how its operands order
+fn compare(
+    op: BinOp,
+    ordering: std::cmp::Ordering,
+    l: &Literal,
+    r: &Literal,
+    expr: &Expr,
+) -> Result<Literal> {
+    let result = match op {
+        BinOp::Eq => ordering.is_eq(),
+        BinOp::Ne => ordering.is_ne(),
+        BinOp::Lt => ordering.is_lt(),
+        BinOp::Le => ordering.is_le(),
+        BinOp::Gt => ordering.is_gt(),
+        BinOp::Ge => ordering.is_ge(),
+        _ => return Err(unsupported(op, l, r, expr)),
+    };
+    Ok(Literal::Bool(result))
+}
+
+fn overflow(expr: &Expr) -> Error {
+    Error::new(
+        ErrorKind::SemanticError,
+        expr.span,
+        "Constant arithmetic overflowed",
+    )
+}
+
+fn unsupported(op: BinOp, l: &Literal, r: &Literal, expr: &Expr) -> Error {
+    Error::new(
+        ErrorKind::TypeMismatch,
+        expr.span,
+        format!(
+            "Operator {:?} cannot be applied to {:?} and {:?} in a constant expression",
+            op, l, r
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_source;
+    use std::collections::HashMap;
+
+    impl ConstEnv for HashMap<&str, Literal> {
+        fn const_value(&self, name: &str) -> Option<Literal> {
+            self.get(name).cloned()
+        }
+    }
+
+    /// The initializer of `const X: i32 = <source>;`
+    fn expr(source: &str) -> Expr {
+        let module = parse_source(&format!("const X: i32 = {};", source)).unwrap();
+        match module.items.into_iter().next() {
+            Some(Item::Const(c)) => c.value,
+            other => panic!("expected a const, found {:?}", other),
+        }
+    }
+
+    fn eval(source: &str) -> Result<Literal> {
+        let consts = HashMap::from([("SIZE", Literal::Integer(8))]);
+        eval_const(&expr(source), &consts)
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval("2 * 3 + 1").unwrap(), Literal::Integer(7));
+        assert_eq!(eval("SIZE - 1").unwrap(), Literal::Integer(7));
+        assert_eq!(eval("-(1 << 4)").unwrap(), Literal::Integer(-16));
+        assert_eq!(eval("1.5 * 2.0").unwrap(), Literal::Float(3.0));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(eval("SIZE > 4").unwrap(), Literal::Bool(true));
+        assert_eq!(eval("1 == 2 or true").unwrap(), Literal::Bool(true));
+        assert_eq!(eval("\"a\" != \"b\"").unwrap(), Literal::Bool(true));
+    }
+
+    #[test]
+    fn test_rejects_calls() {
+        let err = eval("foo()").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(
+            err.message,
+            "Function calls are not allowed in constant expressions"
+        );
+        assert!(!is_const_expr(&expr("1 + foo()"), &HashMap::new()));
+        assert!(is_const_expr(&expr("1 + 2"), &HashMap::new()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(eval("n + 1").unwrap_err().message, "'n' is not a constant");
+        assert!(eval("1 / 0")
+            .unwrap_err()
+            .message
+            .contains("Division by zero"));
+        assert!(eval("9223372036854775807 + 1")
+            .unwrap_err()
+            .message
+            .contains("overflowed"));
+        assert_eq!(eval("1 + true").unwrap_err().kind, ErrorKind::TypeMismatch);
+    }
+}
@@ -0,0 +1,510 @@
+// Desugar - Fruti Compiler
+//
+// Lowers surface syntax into simpler core forms ahead of code generation.
+// Runs after type checking, so rewrites can rely on a well-typed module.
+//
+// `expr?` becomes a match that returns the failure variant from the
+// enclosing function and otherwise yields the success value:
+//
+//     match expr { Ok(value) => value, Error(error) => { return Error(error); } }
+//     match expr { Some(value) => value, None => { return None; } }
+//
+// The arms only refer to their own bindings, so the names can't capture
+// anything from the surrounding code.
+//
+// Compound assignment, counted `for` loops and `if let` reduce to plain
+// assignment, `while` and `match`:
+//
+//     x += e                   =>  x = x + e
+//     for i in a..b { body }   =>  { let mut __for_index = a; let __for_end = b;
+//                                    while __for_index < __for_end {
+//                                        let i = __for_index;
+//                                        __for_index = __for_index + 1;
+//                                        body } }
+//     if let P = e { a } else { b }  =>  match e { P => { a }, _ => { b } }
+//
+// The loop counter steps before the body runs, so `continue` can't skip it.
+// Compound assignment only desugars when the target is a plain place, so
+// `a[next()] += 1` still calls `next` once.
+// Every node built here carries the span of the construct it replaces.
+
+use crate::ast::*;
+use crate::span::{Span, Spanned};
+
+/// Desugar every function body in a module in place
+pub fn desugar_module(module: &mut Module) {
+    desugar_items(&mut module.items);
+}
+
+fn desugar_items(items: &mut [Item]) {
+    for item in items {
+        match item {
+            Item::Function(func) => desugar_function(func),
+            Item::Impl(imp) => imp.methods.iter_mut().for_each(desugar_function),
+            Item::Mod(m) => desugar_items(&mut m.items),
+            _ => {}
+        }
+    }
+}
+
+fn desugar_function(func: &mut Function) {
+    let returns = propagated_enum(func.return_type.as_ref());
+    desugar_block(&mut func.body, returns);
+}
+
+/// The enum a `?` propagates into, given the enclosing return type. The type
+/// checker has already required the operand to be the same enum.
+fn propagated_enum(return_type: Option<&Type>) -> Option<&'static str> {
+    match return_type {
+        Some(Type::Generic { name, .. }) if name.value == "Result" => Some("Result"),
+        Some(Type::Generic { name, .. }) if name.value == "Option" => Some("Option"),
+        _ => None,
+    }
+}
+
+fn desugar_block(block: &mut Block, returns: Option<&'static str>) {
+    for stmt in &mut block.stmts {
+        match stmt {
+            Stmt::Let { value, .. } => {
+                if let Some(value) = value {
+                    desugar_expr(value, returns);
+                }
+            }
+            Stmt::Expr(expr) => desugar_expr(expr, returns),
+            Stmt::Return(expr) => {
+                if let Some(expr) = expr {
+                    desugar_expr(expr, returns);
+                }
+            }
+            Stmt::Break(_) | Stmt::Continue(_) => {}
+            Stmt::While { condition, body } => {
+                desugar_expr(condition, returns);
+                desugar_block(body, returns);
+            }
+            Stmt::For { iter, body, .. } => {
+                desugar_expr(iter, returns);
+                desugar_block(body, returns);
+            }
+            Stmt::Loop { body } => desugar_block(body, returns),
+        }
+        if let Some(lowered) = lower_range_for(stmt) {
+            *stmt = lowered;
+        }
+    }
+    if let Some(expr) = &mut block.expr {
+        desugar_expr(expr, returns);
+    }
+}
+
+fn desugar_expr(expr: &mut Expr, returns: Option<&'static str>) {
+    match &mut expr.kind {
+        ExprKind::Integer(_)
+        | ExprKind::Float(_)
+        | ExprKind::String(_)
+        | ExprKind::Char(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Byte(_)
+        | ExprKind::ByteString(_)
+        | ExprKind::Ident(_)
+        | ExprKind::Path { .. } => {}
+        ExprKind::Binary { op, left, right } => {
+            desugar_expr(left, returns);
+            desugar_expr(right, returns);
+            if let Some(base) = compound_base(*op).filter(|_| is_pure_place(left)) {
+                let value = Expr::new(
+                    ExprKind::Binary {
+                        op: base,
+                        left: left.clone(),
+                        right: right.clone(),
+                    },
+                    expr.span,
+                );
+                *op = BinOp::Assign;
+                **right = value;
+            }
+        }
+        ExprKind::Unary { expr, .. } | ExprKind::Await(expr) | ExprKind::Let { expr, .. } => {
+            desugar_expr(expr, returns)
+        }
+        ExprKind::Try(operand) => {
+            desugar_expr(operand, returns);
+            if let Some(enum_name) = returns {
+                let operand = std::mem::replace(operand.as_mut(), Expr::ident("", expr.span));
+                *expr = lower_try(operand, enum_name, expr.span);
+            }
+        }
+        ExprKind::Call { func, args, .. } => {
+            desugar_expr(func, returns);
+            args.iter_mut().for_each(|arg| desugar_expr(arg, returns));
+        }
+        ExprKind::MethodCall { receiver, args, .. } => {
+            desugar_expr(receiver, returns);
+            args.iter_mut().for_each(|arg| desugar_expr(arg, returns));
+        }
+        ExprKind::Field { expr, .. } => desugar_expr(expr, returns),
+        ExprKind::Index { expr, index } => {
+            desugar_expr(expr, returns);
+            desugar_expr(index, returns);
+        }
+        ExprKind::Range { start, end, .. } => {
+            for bound in [start, end].into_iter().flatten() {
+                desugar_expr(bound, returns);
+            }
+        }
+        ExprKind::If {
+            condition,
+            then_block,
+            else_block,
+        } => {
+            desugar_expr(condition, returns);
+            desugar_block(then_block, returns);
+            if let Some(block) = else_block {
+                desugar_block(block, returns);
+            }
+            if let ExprKind::Let { .. } = condition.kind {
+                *expr = lower_if_let(std::mem::replace(expr, Expr::ident("", expr.span)));
+            }
+        }
+        ExprKind::Match { expr, arms } => {
+            desugar_expr(expr, returns);
+            for arm in arms {
+                if let Some(guard) = &mut arm.guard {
+                    desugar_expr(guard, returns);
+                }
+                desugar_expr(&mut arm.body, returns);
+            }
+        }
+        ExprKind::Block(block) => desugar_block(block, returns),
+        ExprKind::Tuple(exprs) | ExprKind::Array(exprs) => {
+            exprs.iter_mut().for_each(|e| desugar_expr(e, returns))
+        }
+        ExprKind::StructLit { fields, .. } => {
+            for (_, value) in fields {
+                desugar_expr(value, returns);
+            }
+        }
+        ExprKind::Lambda {
+            return_type, body, ..
+        } => {
+            // `?` inside a lambda returns from the lambda, not the enclosing function
+            let returns = propagated_enum(return_type.as_ref());
+            desugar_expr(body, returns);
+        }
+        ExprKind::Cast { expr, .. } | ExprKind::Is { expr, .. } => desugar_expr(expr, returns),
+    }
+}
+
+/// The arithmetic behind a compound assignment: `+=` is `+`
+fn compound_base(op: BinOp) -> Option<BinOp> {
+    match op {
+        BinOp::AddAssign => Some(BinOp::Add),
+        BinOp::SubAssign => Some(BinOp::Sub),
+        BinOp::MulAssign => Some(BinOp::Mul),
+        BinOp::DivAssign => Some(BinOp::Div),
+        BinOp::RemAssign => Some(BinOp::Rem),
+        _ => None,
+    }
+}
+
+/// A place that can be read again without side effects: `x`, `p.x`, `a[i]`.
+/// Other targets keep their compound operator so they're evaluated once.
+fn is_pure_place(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Ident(_) => true,
+        ExprKind::Field { expr, .. } => is_pure_place(expr),
+        ExprKind::Index { expr, index } => {
+            is_pure_place(expr) && matches!(index.kind, ExprKind::Ident(_) | ExprKind::Integer(_))
+        }
+        _ => false,
+    }
+}
+
+/// Rewrite `for i in a..b` as a counting `while`. Other iterables stay as
+/// they are.
+fn lower_range_for(stmt: &Stmt) -> Option<Stmt> {
+    let Stmt::For { var, iter, body } = stmt else {
+        return None;
+    };
+    let ExprKind::Range {
+        start: Some(start),
+        end: Some(end),
+        inclusive,
+    } = &iter.kind
+    else {
+        return None;
+    };
+
+    let span = iter.span.merge(body.span);
+    let index = || Expr::ident("__for_index", span);
+    let binary = |op, left: Expr, right: Expr| {
+        Expr::new(
+            ExprKind::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            },
+            span,
+        )
+    };
+    let let_stmt = |name: &str, value: Expr, mutable| Stmt::Let {
+        name: Spanned::new(name.to_string(), span),
+        ty: None,
+        value: Some(value),
+        mutable,
+    };
+
+    let mut stmts = vec![
+        Stmt::Let {
+            name: var.clone(),
+            ty: None,
+            value: Some(index()),
+            mutable: false,
+        },
+        Stmt::Expr(binary(
+            BinOp::Assign,
+            index(),
+            binary(BinOp::Add, index(), Expr::integer(1, span)),
+        )),
+    ];
+    stmts.extend(body.stmts.iter().cloned());
+    if let Some(tail) = &body.expr {
+        stmts.push(Stmt::Expr((**tail).clone()));
+    }
+
+    let compare = if *inclusive { BinOp::Le } else { BinOp::Lt };
+    let lowered = Block {
+        stmts: vec![
+            let_stmt("__for_index", (**start).clone(), true),
+            let_stmt("__for_end", (**end).clone(), false),
+            Stmt::While {
+                condition: binary(compare, index(), Expr::ident("__for_end", span)),
+                body: Block {
+                    stmts,
+                    expr: None,
+                    span: body.span,
+                },
+            },
+        ],
+        expr: None,
+        span,
+    };
+    Some(Stmt::Expr(Expr::new(ExprKind::Block(lowered), span)))
+}
+
+/// Rewrite `if let P = e { a } else { b }` as `match e { P => { a }, _ => { b } }`
+fn lower_if_let(expr: Expr) -> Expr {
+    let span = expr.span;
+    let ExprKind::If {
+        condition,
+        then_block,
+        else_block,
+    } = expr.kind
+    else {
+        return expr;
+    };
+    let ExprKind::Let {
+        pattern,
+        expr: scrutinee,
+    } = condition.kind
+    else {
+        unreachable!("lower_if_let is only called on `if let`");
+    };
+
+    let block_expr = |block: Block| Expr::new(ExprKind::Block(block), span);
+    let else_block = else_block.unwrap_or(Block {
+        stmts: Vec::new(),
+        expr: None,
+        span,
+    });
+
+    Expr::new(
+        ExprKind::Match {
+            expr: scrutinee,
+            arms: vec![
+                MatchArm {
+                    pattern,
+                    guard: None,
+                    body: block_expr(then_block),
+                },
+                MatchArm {
+                    pattern: Pattern::Wildcard,
+                    guard: None,
+                    body: block_expr(else_block),
+                },
+            ],
+        },
+        span,
+    )
+}
+
+/// Build the match that `operand?` stands for
+fn lower_try(operand: Expr, enum_name: &str, span: Span) -> Expr {
+    let (success, failure) = match enum_name {
+        "Result" => ("Ok", "Error"),
+        _ => ("Some", "None"),
+    };
+
+    let success_arm = MatchArm {
+        pattern: Pattern::Variant {
+            name: success.to_string(),
+            patterns: vec![Pattern::Ident("value".to_string())],
+        },
+        guard: None,
+        body: Expr::ident("value", span),
+    };
+
+    // Result failures carry their error along; `None` has nothing to carry
+    let (failure_pattern, failure_value) = if enum_name == "Result" {
+        let pattern = Pattern::Variant {
+            name: failure.to_string(),
+            patterns: vec![Pattern::Ident("error".to_string())],
+        };
+        let value = Expr::new(
+            ExprKind::Call {
+                func: Box::new(Expr::ident(failure, span)),
+                args: vec![Expr::ident("error", span)],
+                type_args: Vec::new(),
+            },
+            span,
+        );
+        (pattern, value)
+    } else {
+        (
+            Pattern::Ident(failure.to_string()),
+            Expr::ident(failure, span),
+        )
+    };
+
+    let failure_arm = MatchArm {
+        pattern: failure_pattern,
+        guard: None,
+        body: Expr::new(
+            ExprKind::Block(Block {
+                stmts: vec![Stmt::Return(Some(failure_value))],
+                expr: None,
+                span,
+            }),
+            span,
+        ),
+    };
+
+    Expr::new(
+        ExprKind::Match {
+            expr: Box::new(operand),
+            arms: vec![success_arm, failure_arm],
+        },
+        span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> Module {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::new(tokens).parse_module().unwrap()
+    }
+
+    fn desugared(source: &str) -> Module {
+        let mut module = parse(source);
+        desugar_module(&mut module);
+        module.strip_spans();
+        module
+    }
+
+    fn stripped(source: &str) -> Module {
+        let mut module = parse(source);
+        module.strip_spans();
+        module
+    }
+
+    #[test]
+    fn test_try_on_result_returns_error_early() {
+        let sugared =
+            "fn f(r: Result<i32, str>) -> Result<i32, str> {\n    let x = r?;\n    Ok(x + 1)\n}";
+        let lowered = "fn f(r: Result<i32, str>) -> Result<i32, str> {\n    let x = match r { Ok(value) => value, Error(error) => { return Error(error); } };\n    Ok(x + 1)\n}";
+
+        assert_eq!(desugared(sugared), stripped(lowered));
+    }
+
+    #[test]
+    fn test_try_on_option_returns_none_early() {
+        let sugared = "fn f(o: Option<i32>) -> Option<i32> {\n    Some(o? * 2)\n}";
+        let lowered = "fn f(o: Option<i32>) -> Option<i32> {\n    Some(match o { Some(value) => value, None => { return None; } } * 2)\n}";
+
+        assert_eq!(desugared(sugared), stripped(lowered));
+    }
+
+    /// Type check a desugared module
+    fn checks_after_desugar(source: &str) {
+        let mut module = parse(source);
+        desugar_module(&mut module);
+        crate::semantic::TypeChecker::new()
+            .check_module(&module)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let sugared = "fn f() {\n    let mut x = 1;\n    x += 2;\n}";
+        let lowered = "fn f() {\n    let mut x = 1;\n    x = x + 2;\n}";
+
+        assert_eq!(desugared(sugared), stripped(lowered));
+        checks_after_desugar(sugared);
+
+        // An index with side effects would run twice, so the operator stays
+        let sugared = "fn f(a: Vec<i32>) {\n    a[next()] += 1;\n    a[0] += 1;\n}\nfn next() -> i32 { return 0; }";
+        let lowered = "fn f(a: Vec<i32>) {\n    a[next()] += 1;\n    a[0] = a[0] + 1;\n}\nfn next() -> i32 { return 0; }";
+        assert_eq!(desugared(sugared), stripped(lowered));
+    }
+
+    #[test]
+    fn test_range_for_becomes_while() {
+        let sugared = "fn f() {\n    for i in 0..10 { show(i); }\n}\nfn show(n: i32) { }";
+        let lowered = "fn f() {\n    { let mut __for_index = 0; let __for_end = 10; while __for_index < __for_end { let i = __for_index; __for_index = __for_index + 1; show(i); } }\n}\nfn show(n: i32) { }";
+
+        assert_eq!(desugared(sugared), stripped(lowered));
+        checks_after_desugar(sugared);
+
+        // Inclusive ranges compare with `<=`
+        let module = desugared("fn f() {\n    for i in 1..=3 { }\n}");
+        let Item::Function(func) = &module.items[0] else {
+            panic!("expected function");
+        };
+        let Stmt::Expr(Expr {
+            kind: ExprKind::Block(block),
+            ..
+        }) = &func.body.stmts[0]
+        else {
+            panic!("expected block");
+        };
+        assert!(matches!(
+            &block.stmts[2],
+            Stmt::While {
+                condition: Expr {
+                    kind: ExprKind::Binary { op: BinOp::Le, .. },
+                    ..
+                },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_if_let_becomes_match() {
+        let sugared = "fn f(o: Option<i32>) {\n    if let Some(x) = o { show(x); } else { show(0); }\n}\nfn show(n: i32) { }";
+        let lowered = "fn f(o: Option<i32>) {\n    match o { Some(x) => { show(x); }, _ => { show(0); } }\n}\nfn show(n: i32) { }";
+
+        assert_eq!(desugared(sugared), stripped(lowered));
+        checks_after_desugar(sugared);
+    }
+
+    #[test]
+    fn test_try_in_lambda_uses_lambda_return_type() {
+        let source = "fn f() {\n    let g = |o: Option<i32>| o?;\n}";
+        assert_eq!(desugared(source), stripped(source));
+    }
+}
@@ -1,5 +1,6 @@
 // Error handling for Fruti compiler
 
+use crate::source::Source;
 use crate::span::Span;
 use std::fmt;
 
@@ -10,6 +11,18 @@ pub struct Error {
     pub kind: ErrorKind,
     pub span: Span,
     pub message: String,
+    /// Line/column range, filled in by the lexer for its own errors
+    pub location: Option<Location>,
+}
+
+/// 1-based line and column range of an error, so editors can place a
+/// squiggle without mapping byte offsets themselves. Columns count chars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -17,6 +30,7 @@ pub enum ErrorKind {
     // Lexer errors
     UnterminatedString,
     UnterminatedChar,
+    UnterminatedComment,
     InvalidNumber,
     InvalidChar,
     UnexpectedCharacter,
@@ -29,6 +43,9 @@ pub enum ErrorKind {
     UndefinedVariable,
     TypeMismatch,
     SemanticError,
+
+    // Code generation errors: a construct earlier passes should have rejected
+    InternalError,
 }
 
 impl Error {
@@ -37,12 +54,89 @@ impl Error {
             kind,
             span,
             message: message.into(),
+            location: None,
         }
     }
 
+    pub fn with_location(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
     pub fn lexer_error(span: Span, message: impl Into<String>) -> Self {
         Self::new(ErrorKind::UnexpectedCharacter, span, message)
     }
+
+    /// Render the error rustc-style: the message, the offending line, and a
+    /// `^^^` underline beneath the span
+    pub fn render(&self, source: &str) -> String {
+        self.render_with(source, Palette::default())
+    }
+
+    /// Render with terminal colors from `palette`
+    pub fn render_with(&self, source: &str, palette: Palette) -> String {
+        let source = Source::new(source);
+        let (line, column) = source.location(self.span);
+        let line_text = source.line_text(self.span);
+
+        // Underline at least one column, stopping at the end of the line for
+        // spans that continue onto the next one
+        let line_len = line_text.chars().count();
+        let span_len = source.text(self.span).chars().count();
+        let width = span_len.min(line_len.saturating_sub(column - 1)).max(1);
+
+        let gutter = " ".repeat(line.to_string().len());
+        format!(
+            "{}: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}{}",
+            palette.error("error"),
+            self.message,
+            gutter,
+            line,
+            column,
+            gutter,
+            line,
+            line_text,
+            gutter,
+            " ".repeat(column - 1),
+            palette.bold(&"^".repeat(width))
+        )
+    }
+}
+
+/// ANSI styling for terminal diagnostics. A disabled palette returns text
+/// unchanged, for logs and pipes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Bold red, for errors
+    pub fn error(&self, text: &str) -> String {
+        self.paint("1;31", text)
+    }
+
+    /// Bold yellow, for warnings
+    pub fn warning(&self, text: &str) -> String {
+        self.paint("1;33", text)
+    }
+
+    /// Bold, for carets and other emphasis
+    pub fn bold(&self, text: &str) -> String {
+        self.paint("1", text)
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -52,3 +146,42 @@ impl fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_underlines_span() {
+        let source = "fn main() {\n    let x: bool = 10;\n}\n";
+        let error = Error::new(ErrorKind::TypeMismatch, Span::new(30, 32), "Type mismatch");
+
+        let rendered = error.render(source);
+        assert_eq!(
+            rendered,
+            "error: Type mismatch\n --> 2:19\n  |\n2 |     let x: bool = 10;\n  |                   ^^"
+        );
+    }
+
+    #[test]
+    fn test_render_clamps_to_line() {
+        let source = "fn main() {\n}\n";
+        let error = Error::new(ErrorKind::ExpectedToken, Span::new(10, 14), "Unclosed");
+        assert!(error
+            .render(source)
+            .ends_with("1 | fn main() {\n  |           ^"));
+
+        // A zero-width span still gets a caret
+        let error = Error::new(ErrorKind::ExpectedToken, Span::new(3, 3), "Expected name");
+        assert!(error.render(source).ends_with("  |    ^"));
+    }
+
+    #[test]
+    fn test_palette() {
+        assert_eq!(Palette::new(false).error("[ERROR]"), "[ERROR]");
+        assert_eq!(
+            Palette::new(true).error("[ERROR]"),
+            "\x1b[1;31m[ERROR]\x1b[0m"
+        );
+    }
+}
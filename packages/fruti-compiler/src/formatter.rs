@@ -0,0 +1,1050 @@
+// Formatter - Fruti Compiler
+//
+// Pretty-prints a parsed module in canonical style: 4-space indentation,
+// one blank line between items, single spaces around binary operators, and
+// explicit semicolons wherever a statement takes one. Parentheses are
+// re-derived from operator precedence, so redundant ones are dropped and
+// required ones are added back.
+//
+// Output is a function of the AST alone, which makes formatting idempotent.
+// Doc comments live on their items and are re-emitted as `///` lines above
+// them, whichever form they were written in. Ordinary `//` and `/* */`
+// comments are not attached to any node, so they can't be put back; rather
+// than silently delete them, callers must not format source that contains
+// them (`fruti fmt` skips such files).
+
+use crate::ast::*;
+use crate::span::Spanned;
+
+/// Indentation per nesting level
+const INDENT: &str = "    ";
+
+/// Binding strength of expressions that are not binary operators; binary
+/// operators sit in between, at `BinOp::precedence()`
+const LAMBDA: u8 = 0;
+const RANGE: u8 = 1;
+const UNARY: u8 = 11;
+const POSTFIX: u8 = 12;
+const PRIMARY: u8 = 13;
+
+/// Format a module as canonical Fruti source, ending with a newline
+pub fn format_module(module: &Module) -> String {
+    let mut printer = Printer::default();
+    printer.items(&module.items);
+    printer.out
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    indent: usize,
+    at_line_start: bool,
+    no_struct: bool, // Set in `if`/`while`/`for`/`match` heads, where `{` opens the block
+}
+
+impl Printer {
+    /// Append `text`, indenting first if it starts a line
+    fn write(&mut self, text: &str) {
+        if self.at_line_start && !text.is_empty() {
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT);
+            }
+            self.at_line_start = false;
+        }
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.at_line_start = true;
+    }
+
+    /// Write `items` with `f`, separated by `, `
+    fn list<T>(&mut self, items: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.write(", ");
+            }
+            f(self, item);
+        }
+    }
+
+    // === Items ===
+
+    fn items(&mut self, items: &[Item]) {
+        for (i, item) in items.iter().enumerate() {
+            // Runs of imports stay together; everything else is spaced out
+            let grouped =
+                i > 0 && matches!((&items[i - 1], item), (Item::Import(_), Item::Import(_)));
+            if i > 0 && !grouped {
+                self.newline();
+            }
+            self.item(item);
+        }
+    }
+
+    fn item(&mut self, item: &Item) {
+        // Functions write their own, as impl methods don't come through here
+        if !matches!(item, Item::Function(_)) {
+            self.doc(item.doc());
+        }
+        match item {
+            Item::Function(func) => self.function(func),
+            Item::Struct(s) => {
+                self.visibility(s.is_pub);
+                self.write(&format!("struct {} ", s.name.value));
+                self.fields(&s.fields);
+                self.newline();
+            }
+            Item::Enum(e) => {
+                self.visibility(e.is_pub);
+                self.write(&format!("enum {} ", e.name.value));
+                self.braced(&e.variants, |p, variant| {
+                    p.write(&variant.name.value);
+                    match &variant.data {
+                        VariantData::Unit => {}
+                        VariantData::Tuple(types) => {
+                            p.write("(");
+                            p.list(types, |p, ty| p.write(&ty.to_string()));
+                            p.write(")");
+                        }
+                        VariantData::Struct(fields) => {
+                            p.write(" { ");
+                            p.list(fields, |p, field| p.field(field));
+                            p.write(" }");
+                        }
+                    }
+                    if let Some(discriminant) = &variant.discriminant {
+                        p.write(" = ");
+                        p.expr(discriminant, LAMBDA);
+                    }
+                    p.write(",");
+                });
+                self.newline();
+            }
+            Item::Trait(t) => {
+                self.visibility(t.is_pub);
+                self.write(&format!("trait {}", t.name.value));
+                if !t.supertraits.is_empty() {
+                    self.write(": ");
+                    self.trait_list(&t.supertraits);
+                }
+                self.write(" ");
+                self.braced(&t.methods, |p, method| {
+                    p.write(&format!("fn {}", method.name.value));
+                    p.params(method.receiver, &method.params);
+                    p.return_type(method.return_type.as_ref());
+                    p.write(";");
+                });
+                self.newline();
+            }
+            Item::Impl(imp) => {
+                self.write("impl ");
+                if let Some(trait_name) = &imp.trait_name {
+                    self.write(&format!("{} for ", trait_name.value));
+                }
+                self.write(&format!("{} ", imp.type_name.value));
+                if imp.methods.is_empty() {
+                    self.write("{}");
+                    self.newline();
+                    return;
+                }
+                self.write("{");
+                self.newline();
+                self.indent += 1;
+                for (i, method) in imp.methods.iter().enumerate() {
+                    if i > 0 {
+                        self.newline();
+                    }
+                    self.function(method);
+                }
+                self.indent -= 1;
+                self.write("}");
+                self.newline();
+            }
+            Item::TypeAlias(alias) => {
+                self.visibility(alias.is_pub);
+                self.write(&format!("type {} = {};", alias.name.value, alias.ty));
+                self.newline();
+            }
+            Item::Const(c) => {
+                self.visibility(c.is_pub);
+                self.write(&format!("const {}: {} = ", c.name.value, c.ty));
+                self.expr(&c.value, LAMBDA);
+                self.write(";");
+                self.newline();
+            }
+            Item::Import(import) => {
+                let path: Vec<&str> = import.path.iter().map(|s| s.value.as_str()).collect();
+                self.write(&format!("import {}", path.join("::")));
+                if import.glob {
+                    self.write("::*");
+                } else if !import.group.is_empty() {
+                    let names: Vec<&str> = import.group.iter().map(|s| s.value.as_str()).collect();
+                    self.write(&format!("::{{{}}}", names.join(", ")));
+                }
+                if let Some(alias) = &import.alias {
+                    self.write(&format!(" as {}", alias.value));
+                }
+                self.write(";");
+                self.newline();
+            }
+            Item::Mod(m) => {
+                self.visibility(m.is_pub);
+                self.write(&format!("mod {} ", m.name.value));
+                if m.items.is_empty() {
+                    self.write("{}");
+                } else {
+                    self.write("{");
+                    self.newline();
+                    self.indent += 1;
+                    self.items(&m.items);
+                    self.indent -= 1;
+                    self.write("}");
+                }
+                self.newline();
+            }
+        }
+    }
+
+    /// Write a doc comment as `///` lines at the current indentation
+    fn doc(&mut self, doc: Option<&str>) {
+        for line in doc.into_iter().flat_map(str::lines) {
+            if line.is_empty() {
+                self.write("///");
+            } else {
+                self.write(&format!("/// {}", line));
+            }
+            self.newline();
+        }
+    }
+
+    fn visibility(&mut self, is_pub: bool) {
+        if is_pub {
+            self.write("pub ");
+        }
+    }
+
+    fn function(&mut self, func: &Function) {
+        self.doc(func.doc.as_deref());
+        for attr in &func.attributes {
+            self.write(&format!("#[{}", attr.name.value));
+            if !attr.args.is_empty() {
+                let args: Vec<&str> = attr.args.iter().map(|a| a.value.as_str()).collect();
+                self.write(&format!("({})", args.join(", ")));
+            }
+            self.write("]");
+            self.newline();
+        }
+
+        self.visibility(func.is_pub);
+        if func.is_async {
+            self.write("async ");
+        }
+        self.write(&format!("fn {}", func.name.value));
+
+        // Bounds print inline on their parameter, however they were written
+        if !func.generics.is_empty() {
+            self.write("<");
+            self.list(&func.generics, |p, generic| {
+                p.write(&generic.value);
+                let traits: Vec<_> = func
+                    .bounds
+                    .iter()
+                    .filter(|b| b.param.value == generic.value)
+                    .flat_map(|b| b.traits.iter().cloned())
+                    .collect();
+                if !traits.is_empty() {
+                    p.write(": ");
+                    p.trait_list(&traits);
+                }
+            });
+            self.write(">");
+        }
+
+        self.params(func.receiver, &func.params);
+        self.return_type(func.return_type.as_ref());
+
+        // Bounds on names that are not generic parameters can only be `where` clauses
+        let stray: Vec<_> = func
+            .bounds
+            .iter()
+            .filter(|b| !func.generics.iter().any(|g| g.value == b.param.value))
+            .collect();
+        if !stray.is_empty() {
+            self.write(" where ");
+            self.list(&stray, |p, bound| {
+                p.write(&format!("{}: ", bound.param.value));
+                p.trait_list(&bound.traits);
+            });
+        }
+
+        self.write(" ");
+        self.block(&func.body);
+        self.newline();
+    }
+
+    fn trait_list(&mut self, traits: &[Spanned<String>]) {
+        let names: Vec<&str> = traits.iter().map(|t| t.value.as_str()).collect();
+        self.write(&names.join(" + "));
+    }
+
+    fn param(&mut self, param: &Param) {
+        self.write(&param.name.value);
+        if param.ty != Type::Infer {
+            self.write(&format!(": {}", param.ty));
+        }
+        if let Some(default) = &param.default {
+            self.write(" = ");
+            self.expr(default, LAMBDA);
+        }
+    }
+
+    /// Write a parenthesized parameter list, receiver first
+    fn params(&mut self, receiver: Option<SelfKind>, params: &[Param]) {
+        self.write("(");
+        if let Some(receiver) = receiver {
+            self.write(receiver.as_str());
+            if !params.is_empty() {
+                self.write(", ");
+            }
+        }
+        self.list(params, |p, param| p.param(param));
+        self.write(")");
+    }
+
+    fn return_type(&mut self, ty: Option<&Type>) {
+        if let Some(ty) = ty {
+            self.write(&format!(" -> {}", ty));
+        }
+    }
+
+    fn field(&mut self, field: &Field) {
+        self.visibility(field.is_pub);
+        self.write(&format!("{}: {}", field.name.value, field.ty));
+    }
+
+    fn fields(&mut self, fields: &[Field]) {
+        self.braced(fields, |p, field| {
+            p.field(field);
+            p.write(",");
+        });
+    }
+
+    /// Write `{}` or a brace-delimited body with one entry per line
+    fn braced<T>(&mut self, entries: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        if entries.is_empty() {
+            self.write("{}");
+            return;
+        }
+        self.write("{");
+        self.newline();
+        self.indent += 1;
+        for entry in entries {
+            f(self, entry);
+            self.newline();
+        }
+        self.indent -= 1;
+        self.write("}");
+    }
+
+    // === Statements ===
+
+    fn block(&mut self, block: &Block) {
+        if block.stmts.is_empty() && block.expr.is_none() {
+            self.write("{}");
+            return;
+        }
+
+        // A block resets the struct literal restriction of an enclosing head
+        let no_struct = std::mem::take(&mut self.no_struct);
+        self.write("{");
+        self.newline();
+        self.indent += 1;
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+            self.newline();
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr, LAMBDA);
+            self.newline();
+        }
+        self.indent -= 1;
+        self.write("}");
+        self.no_struct = no_struct;
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let {
+                name,
+                ty,
+                value,
+                mutable,
+            } => {
+                self.write("let ");
+                if *mutable {
+                    self.write("mut ");
+                }
+                self.write(&name.value);
+                if let Some(ty) = ty {
+                    self.write(&format!(": {}", ty));
+                }
+                if let Some(value) = value {
+                    self.write(" = ");
+                    self.expr(value, LAMBDA);
+                }
+                self.write(";");
+            }
+            Stmt::Expr(expr) => {
+                self.expr(expr, LAMBDA);
+                // Statements led by `if`, `match` or `{` end at their closing brace
+                if !starts_with_block(expr) {
+                    self.write(";");
+                }
+            }
+            Stmt::Return(value) => {
+                self.write("return");
+                if let Some(value) = value {
+                    self.write(" ");
+                    self.expr(value, LAMBDA);
+                }
+                self.write(";");
+            }
+            Stmt::Break(_) => self.write("break;"),
+            Stmt::Continue(_) => self.write("continue;"),
+            Stmt::While { condition, body } => {
+                self.write("while ");
+                self.head(condition);
+                self.block(body);
+            }
+            Stmt::For { var, iter, body } => {
+                self.write(&format!("for {} in ", var.value));
+                self.head(iter);
+                self.block(body);
+            }
+            Stmt::Loop { body } => {
+                self.write("loop ");
+                self.block(body);
+            }
+        }
+    }
+
+    /// Write the expression between a keyword and its block, and the space after it
+    fn head(&mut self, expr: &Expr) {
+        let no_struct = std::mem::replace(&mut self.no_struct, true);
+        self.expr(expr, LAMBDA);
+        self.no_struct = no_struct;
+        self.write(" ");
+    }
+
+    // === Expressions ===
+
+    /// Write `expr`, parenthesized if it binds looser than `min`
+    fn expr(&mut self, expr: &Expr, min: u8) {
+        let parens = binding_power(expr) < min
+            || (self.no_struct && matches!(expr.kind, ExprKind::StructLit { .. }));
+        if parens {
+            let no_struct = std::mem::take(&mut self.no_struct);
+            self.write("(");
+            self.expr_kind(expr);
+            self.write(")");
+            self.no_struct = no_struct;
+        } else {
+            self.expr_kind(expr);
+        }
+    }
+
+    fn expr_kind(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Integer(n) => self.write(&n.to_string()),
+            ExprKind::Float(x) => self.write(&float_literal(*x)),
+            ExprKind::String(s) => self.write(&string_literal(s)),
+            ExprKind::Char(c) => self.write(&char_literal(*c)),
+            ExprKind::Bool(b) => self.write(&b.to_string()),
+            ExprKind::Byte(b) => self.write(&format!("b'{}'", b.escape_ascii())),
+            ExprKind::ByteString(bytes) => self.write(&format!("b\"{}\"", bytes.escape_ascii())),
+            ExprKind::Ident(name) => self.write(name),
+            ExprKind::Path { path, type_args } => {
+                let names: Vec<&str> = path.iter().map(|s| s.value.as_str()).collect();
+                let (last, init) = names.split_last().expect("paths have segments");
+                self.write(&init.join("::"));
+                if !type_args.is_empty() {
+                    self.write("::<");
+                    self.list(type_args, |p, ty| p.write(&ty.to_string()));
+                    self.write(">");
+                }
+                self.write("::");
+                self.write(last);
+            }
+            ExprKind::Binary { op, left, right } => {
+                let prec = op.precedence();
+                let (left_min, right_min) = if op.is_left_associative() {
+                    (prec, prec + 1)
+                } else {
+                    (prec + 1, prec)
+                };
+                self.expr(left, left_min);
+                self.write(&format!(" {} ", op.symbol()));
+                self.expr(right, right_min);
+            }
+            ExprKind::Unary { op, expr: operand } => {
+                self.write(op.symbol());
+                // `- -x` rather than `--x`
+                if *op == UnOp::Neg && self.out.ends_with('-') && starts_with_minus(operand) {
+                    self.write(" ");
+                }
+                self.expr(operand, UNARY);
+            }
+            ExprKind::Call {
+                func,
+                args,
+                type_args,
+            } => {
+                self.expr(func, POSTFIX);
+                if !type_args.is_empty() {
+                    self.write("::<");
+                    self.list(type_args, |p, ty| p.write(&ty.to_string()));
+                    self.write(">");
+                }
+                self.write("(");
+                self.list(args, |p, arg| p.expr(arg, LAMBDA));
+                self.write(")");
+            }
+            ExprKind::MethodCall {
+                receiver,
+                method,
+                args,
+            } => {
+                self.receiver(receiver);
+                self.write(&format!(".{}(", method.value));
+                self.list(args, |p, arg| p.expr(arg, LAMBDA));
+                self.write(")");
+            }
+            ExprKind::Field { expr: base, field } => {
+                self.receiver(base);
+                self.write(&format!(".{}", field.value));
+            }
+            ExprKind::Index { expr: base, index } => {
+                self.expr(base, POSTFIX);
+                self.write("[");
+                self.expr(index, LAMBDA);
+                self.write("]");
+            }
+            ExprKind::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                if let Some(start) = start {
+                    self.expr(start, UNARY);
+                }
+                self.write(if *inclusive { "..=" } else { ".." });
+                if let Some(end) = end {
+                    self.expr(end, UNARY);
+                }
+            }
+            ExprKind::If {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                self.write("if ");
+                self.head(condition);
+                self.block(then_block);
+                if let Some(else_block) = else_block {
+                    self.write(" else ");
+                    self.block(else_block);
+                }
+            }
+            ExprKind::Let {
+                pattern,
+                expr: scrutinee,
+            } => {
+                self.write("let ");
+                self.pattern(pattern);
+                self.write(" = ");
+                self.expr(scrutinee, BinOp::And.precedence() + 1);
+            }
+            ExprKind::Match {
+                expr: scrutinee,
+                arms,
+            } => {
+                self.write("match ");
+                self.head(scrutinee);
+                let no_struct = std::mem::take(&mut self.no_struct);
+                self.braced(arms, |p, arm| {
+                    p.pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        p.write(" if ");
+                        p.expr(guard, LAMBDA);
+                    }
+                    p.write(" => ");
+                    p.expr(&arm.body, LAMBDA);
+                    p.write(",");
+                });
+                self.no_struct = no_struct;
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::Tuple(elems) => {
+                let no_struct = std::mem::take(&mut self.no_struct);
+                self.write("(");
+                self.list(elems, |p, elem| p.expr(elem, LAMBDA));
+                if elems.len() == 1 {
+                    self.write(",");
+                }
+                self.write(")");
+                self.no_struct = no_struct;
+            }
+            ExprKind::Array(elems) => {
+                self.write("[");
+                self.list(elems, |p, elem| p.expr(elem, LAMBDA));
+                self.write("]");
+            }
+            ExprKind::StructLit { name, fields } => {
+                self.write(&name.value);
+                if fields.is_empty() {
+                    self.write(" {}");
+                    return;
+                }
+                self.write(" { ");
+                self.list(fields, |p, (field, value)| {
+                    p.write(&format!("{}: ", field.value));
+                    p.expr(value, LAMBDA);
+                });
+                self.write(" }");
+            }
+            ExprKind::Lambda {
+                params,
+                return_type,
+                body,
+            } => {
+                self.write("|");
+                self.list(params, |p, param| p.param(param));
+                self.write("|");
+                self.return_type(return_type.as_ref());
+                self.write(" ");
+                self.expr(body, LAMBDA);
+            }
+            ExprKind::Await(operand) => {
+                self.write("await ");
+                self.expr(operand, POSTFIX);
+            }
+            ExprKind::Try(operand) => {
+                self.expr(operand, POSTFIX);
+                self.write("?");
+            }
+            ExprKind::Cast { expr: operand, ty } => {
+                self.expr(operand, POSTFIX);
+                self.write(&format!(" as {}", ty));
+            }
+            ExprKind::Is { expr: operand, ty } => {
+                self.expr(operand, POSTFIX);
+                self.write(&format!(" is {}", ty));
+            }
+        }
+    }
+
+    /// Write the base of a `.field` or `.method()`; `(1).abs()` keeps its
+    /// parentheses because `1.` would lex as the start of a float
+    fn receiver(&mut self, base: &Expr) {
+        if matches!(base.kind, ExprKind::Integer(_)) {
+            self.write("(");
+            self.expr_kind(base);
+            self.write(")");
+        } else {
+            self.expr(base, POSTFIX);
+        }
+    }
+
+    // === Patterns ===
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard => self.write("_"),
+            Pattern::Ident(name) => self.write(name),
+            Pattern::Literal(lit) => self.write(&literal(lit)),
+            Pattern::Tuple(patterns) => {
+                self.write("(");
+                self.list(patterns, |p, pattern| p.pattern(pattern));
+                if patterns.len() == 1 {
+                    self.write(",");
+                }
+                self.write(")");
+            }
+            Pattern::Variant { name, patterns } => {
+                self.write(&format!("{}(", name));
+                self.list(patterns, |p, pattern| p.pattern(pattern));
+                self.write(")");
+            }
+            Pattern::Struct { name, fields } => {
+                self.write(&format!("{} {{ ", name));
+                self.list(fields, |p, (field, pattern)| match pattern {
+                    Pattern::Ident(binding) if *binding == field.value => p.write(binding),
+                    _ => {
+                        p.write(&format!("{}: ", field.value));
+                        p.pattern(pattern);
+                    }
+                });
+                self.write(" }");
+            }
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let op = if *inclusive { "..=" } else { ".." };
+                self.write(&format!("{}{}{}", literal(start), op, literal(end)));
+            }
+            Pattern::Or(alternatives) => {
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        self.write(" | ");
+                    }
+                    self.pattern(alternative);
+                }
+            }
+            Pattern::Binding { name, pattern } => {
+                self.write(&format!("{} @ ", name.value));
+                // `@` binds tighter than `|`
+                if matches!(**pattern, Pattern::Or(_)) {
+                    self.write("(");
+                    self.pattern(pattern);
+                    self.write(")");
+                } else {
+                    self.pattern(pattern);
+                }
+            }
+        }
+    }
+}
+
+/// How tightly `expr` holds together as an operand
+fn binding_power(expr: &Expr) -> u8 {
+    match &expr.kind {
+        ExprKind::Lambda { .. } => LAMBDA,
+        ExprKind::Binary { op, .. } => op.precedence(),
+        // The parser gives a range whatever operand precedes `..`, so ranges
+        // are parenthesized inside every other operator
+        ExprKind::Range { .. } => RANGE,
+        ExprKind::Unary { .. } | ExprKind::Await(_) => UNARY,
+        ExprKind::Call { .. }
+        | ExprKind::MethodCall { .. }
+        | ExprKind::Field { .. }
+        | ExprKind::Index { .. }
+        | ExprKind::Try(_)
+        | ExprKind::Cast { .. }
+        | ExprKind::Is { .. } => POSTFIX,
+        _ => PRIMARY,
+    }
+}
+
+/// Whether the first token of `expr` is `if`, `match` or `{`
+fn starts_with_block(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::If { .. } | ExprKind::Match { .. } | ExprKind::Block(_) => true,
+        ExprKind::Binary { op, left, .. } => {
+            let prec = op.precedence();
+            let left_min = if op.is_left_associative() {
+                prec
+            } else {
+                prec + 1
+            };
+            binding_power(left) >= left_min && starts_with_block(left)
+        }
+        ExprKind::Call { func: base, .. }
+        | ExprKind::MethodCall { receiver: base, .. }
+        | ExprKind::Field { expr: base, .. }
+        | ExprKind::Index { expr: base, .. }
+        | ExprKind::Try(base)
+        | ExprKind::Cast { expr: base, .. }
+        | ExprKind::Is { expr: base, .. } => {
+            binding_power(base) >= POSTFIX && starts_with_block(base)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the formatted `expr` begins with `-`
+fn starts_with_minus(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Unary { op, .. } => *op == UnOp::Neg,
+        ExprKind::Integer(n) => *n < 0,
+        ExprKind::Float(x) => x.is_sign_negative(),
+        _ => false,
+    }
+}
+
+fn literal(lit: &Literal) -> String {
+    match lit {
+        Literal::Integer(n) => n.to_string(),
+        Literal::Float(x) => float_literal(*x),
+        Literal::String(s) => string_literal(s),
+        Literal::Char(c) => char_literal(*c),
+        Literal::Bool(b) => b.to_string(),
+    }
+}
+
+/// Floats in positional notation, with a fractional part so that they lex
+/// back as floats
+pub(crate) fn float_literal(x: f64) -> String {
+    let text = x.to_string();
+    if text.contains(['.', 'i', 'N']) {
+        text
+    } else {
+        format!("{}.0", text)
+    }
+}
+
+/// A quoted string, escaped so that the lexer reads back the same text
+pub(crate) fn string_literal(s: &str) -> String {
+    let mut out = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            _ if ch.is_ascii_control() => out.push_str(&format!("\\x{:02X}", ch as u8)),
+            _ => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A quoted char, escaped so that the lexer reads back the same char
+pub(crate) fn char_literal(c: char) -> String {
+    match c {
+        '\n' => "'\\n'".to_string(),
+        '\t' => "'\\t'".to_string(),
+        '\r' => "'\\r'".to_string(),
+        '\\' => "'\\\\'".to_string(),
+        '\'' => "'\\''".to_string(),
+        _ if c.is_ascii_control() => format!("'\\x{:02X}'", c as u8),
+        _ => format!("'{}'", c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_source;
+
+    fn format(source: &str) -> String {
+        format_module(&parse_source(source).unwrap())
+    }
+
+    fn parse_stripped(source: &str) -> Module {
+        let mut module = parse_source(source).unwrap();
+        module.strip_spans();
+        module
+    }
+
+    const SAMPLE: &str = r#"
+import std::io
+import std::collections::{Map,Set}
+struct Point{x:i32,pub y:i32}
+#[inline]
+pub fn classify<T:Display+Clone>(p:Point,items:Vec<T>,n:i32)->Option<str>{
+let mut total=0
+for i in 0..n{ total+=i*(i-1); }
+while total>100&&!done(total){total-=(1+2)*3;}
+let s=Point{x:1,y:-(2)}
+if p.x==s.x{return None;}else{ total = -total; }
+let f=|a,b:i32|a+b
+let r=match total{0=>"zero\n",n@1..=9 if n%2==0=>"small",_=>{ let k = f(1,2); "big" }}
+if let Some(v)=lookup(total)&&v>0{ println("{}",v); }
+(1).abs()
+Some(r)
+}
+"#;
+
+    #[test]
+    fn test_format_sample_function() {
+        let expected = r#"import std::io;
+import std::collections::{Map, Set};
+
+struct Point {
+    x: i32,
+    pub y: i32,
+}
+
+#[inline]
+pub fn classify<T: Display + Clone>(p: Point, items: Vec<T>, n: i32) -> Option<str> {
+    let mut total = 0;
+    for i in 0..n {
+        total += i * (i - 1);
+    }
+    while total > 100 and not done(total) {
+        total -= (1 + 2) * 3;
+    }
+    let s = Point { x: 1, y: -2 };
+    if p.x == s.x {
+        return None;
+    } else {
+        total = -total;
+    }
+    let f = |a, b: i32| a + b;
+    let r = match total {
+        0 => "zero\n",
+        n @ 1..=9 if n % 2 == 0 => "small",
+        _ => {
+            let k = f(1, 2);
+            "big"
+        },
+    };
+    if let Some(v) = lookup(total) and v > 0 {
+        println("{}", v);
+    }
+    (1).abs();
+    Some(r)
+}
+"#;
+        assert_eq!(format(SAMPLE), expected);
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        let once = format(SAMPLE);
+        assert_eq!(format(&once), once);
+        assert_eq!(parse_stripped(&once), parse_stripped(SAMPLE));
+    }
+
+    #[test]
+    fn test_format_keeps_required_parens() {
+        let source = "fn f() { let a = (x + y) * -(z - 1); let b = (a..b).len(); let c = (1, ); if (P { x: 1 }).x > 0 { } }";
+        let formatted = format(source);
+        assert!(formatted.contains("let a = (x + y) * -(z - 1);"));
+        assert!(formatted.contains("let b = (a..b).len();"));
+        assert!(formatted.contains("let c = (1,);"));
+        assert!(formatted.contains("if (P { x: 1 }).x > 0 {}"));
+        assert_eq!(parse_stripped(&formatted), parse_stripped(source));
+    }
+
+    #[test]
+    fn test_format_turbofish() {
+        let source = "fn f() { let v = Vec::<i32>::new(); let n = num::parse::<i32>(s); }";
+        let formatted = format(source);
+        assert!(formatted.contains("let v = Vec::<i32>::new();"));
+        assert!(formatted.contains("let n = num::parse::<i32>(s);"));
+        assert_eq!(parse_stripped(&formatted), parse_stripped(source));
+    }
+
+    #[test]
+    fn test_format_escapes_control_characters() {
+        let source = r#"fn f() { let s = "\x01\tA\x41"; let c = '\x00'; }"#;
+        let formatted = format(source);
+        assert!(formatted.contains(r#"let s = "\x01\tAA";"#));
+        assert!(formatted.contains(r"let c = '\x00';"));
+        assert_eq!(parse_stripped(&formatted), parse_stripped(source));
+    }
+
+    #[test]
+    fn test_format_byte_literals() {
+        let source = r#"fn f() { let b = b'\''; let s = b"\x00\xFFa\"b"; }"#;
+        let formatted = format(source);
+        assert!(formatted.contains(r"let b = b'\'';"));
+        assert!(formatted.contains(r#"let s = b"\x00\xffa\"b";"#));
+        assert_eq!(parse_stripped(&formatted), parse_stripped(source));
+    }
+
+    #[test]
+    fn test_format_moves_where_bounds_inline() {
+        let formatted = format("fn show<T: Display, U>(t: T, u: U) where T: Debug, U: Clone {}");
+        assert_eq!(
+            formatted,
+            "fn show<T: Display + Debug, U: Clone>(t: T, u: U) {}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_items() {
+        let source = "pub enum Shape{Circle(f64),Rect{w:f64,h:f64},Empty} trait Area:Shape{fn area(s:Shape)->f64;} impl Area for Shape{fn area(self)->f64{0.0} fn zero(){}} type Id=i32; const MAX:i32=1<<4; mod m{fn a(){}}";
+        let expected = "pub enum Shape {
+    Circle(f64),
+    Rect { w: f64, h: f64 },
+    Empty,
+}
+
+trait Area: Shape {
+    fn area(s: Shape) -> f64;
+}
+
+impl Area for Shape {
+    fn area(self) -> f64 {
+        0.0
+    }
+
+    fn zero() {}
+}
+
+type Id = i32;
+
+const MAX: i32 = 1 << 4;
+
+mod m {
+    fn a() {}
+}
+";
+        assert_eq!(format(source), expected);
+        assert_eq!(format(expected), expected);
+    }
+
+    #[test]
+    fn test_format_lambda_return_type() {
+        let source = "fn f() { let inc = |x:i32|->i32{x+1}; let mul = |a,b|a*b; }";
+        let formatted = format(source);
+        assert!(formatted.contains("let inc = |x: i32| -> i32 {\n        x + 1\n    };"));
+        assert!(formatted.contains("let mul = |a, b| a * b;"));
+        assert_eq!(parse_stripped(&formatted), parse_stripped(source));
+    }
+
+    #[test]
+    fn test_format_keeps_doc_comments() {
+        let source = "/// Adds one\n///\n/// Saturates at `i32::MAX`.\nfn inc(x:i32)->i32{x+1}\n/** A point */ struct P{x:i32}\nimpl P{\n/// The x coordinate\nfn x(&self)->i32{self.x}}";
+        let expected = "/// Adds one
+///
+/// Saturates at `i32::MAX`.
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+/// A point
+struct P {
+    x: i32,
+}
+
+impl P {
+    /// The x coordinate
+    fn x(&self) -> i32 {
+        self.x
+    }
+}
+";
+        let formatted = format(source);
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted), formatted);
+        assert_eq!(parse_stripped(&formatted), parse_stripped(source));
+    }
+
+    #[test]
+    fn test_format_receivers() {
+        let source = "trait T{fn a(&self)->i32;fn b(self,k:i32);} impl P{fn a(&self)->i32{0} fn b(own self,k:i32){}}";
+        let expected = "trait T {
+    fn a(&self) -> i32;
+    fn b(self, k: i32);
+}
+
+impl P {
+    fn a(&self) -> i32 {
+        0
+    }
+
+    fn b(own self, k: i32) {}
+}
+";
+        assert_eq!(format(source), expected);
+    }
+}
@@ -1,7 +1,7 @@
 // Lexer - Tokenizer for Fruti language
 // Converts source code into a stream of tokens
 
-use crate::error::{Error, ErrorKind, Result};
+use crate::error::{Error, ErrorKind, Location, Result};
 use crate::span::Span;
 use crate::token::{Token, TokenKind};
 use std::str::Chars;
@@ -10,44 +10,92 @@ pub struct Lexer<'a> {
     source: &'a str,
     chars: std::iter::Peekable<Chars<'a>>,
     position: usize,
+    line: usize,   // 1-based line of `position`
+    column: usize, // 1-based column of `position`, in chars
     current_char: Option<char>,
     last_token: Option<TokenKind>,
     pending_semicolon: Option<()>,
+    finished: bool, // Set once Eof or an error has been yielded by the iterator
+    comments: Vec<Span>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(source: &'a str) -> Self {
         let mut chars = source.chars().peekable();
         let current_char = chars.next();
-        Self {
+        let mut lexer = Self {
             source,
             chars,
             position: 0,
+            line: 1,
+            column: 1,
             current_char,
             last_token: None,
             pending_semicolon: None,
+            finished: false,
+            comments: Vec::new(),
+        };
+
+        // A `#!` interpreter line is only allowed as the very first bytes,
+        // and is kept as a comment so the formatter knows not to drop it
+        if source.starts_with("#!") {
+            while lexer.current_char.is_some() && lexer.current_char != Some('\n') {
+                lexer.advance();
+            }
+            lexer.comments.push(Span::new(0, lexer.position));
         }
+        lexer
     }
 
     /// Get all tokens from source
     pub fn tokenize(&mut self) -> Result<Vec<Token>> {
-        let mut tokens = Vec::new();
-        loop {
-            let token = self.next_token()?;
-            let is_eof = matches!(token.value, TokenKind::Eof);
-            tokens.push(token);
-            if is_eof {
-                break;
-            }
-        }
-        Ok(tokens)
+        self.by_ref().collect()
+    }
+
+    /// Spans of the comments skipped so far. Doc comments are tokens, so
+    /// they aren't included.
+    pub fn comments(&self) -> &[Span] {
+        &self.comments
     }
 
     /// Get the next token
     pub fn next_token(&mut self) -> Result<Token> {
+        let origin = (self.position, self.line, self.column);
+        self.lex_token().map_err(|e| {
+            let location = self.locate(e.span, origin);
+            e.with_location(location)
+        })
+    }
+
+    /// Line/column range of `span`, counted forward from a known
+    /// `(offset, line, column)` at or before its start
+    fn locate(&self, span: Span, origin: (usize, usize, usize)) -> Location {
+        let step = |(offset, line, column): (usize, usize, usize), to: usize| {
+            let to = to.clamp(offset, self.source.len());
+            self.source[offset..to]
+                .chars()
+                .fold((to, line, column), |(_, line, column), ch| {
+                    if ch == '\n' {
+                        (to, line + 1, 1)
+                    } else {
+                        (to, line, column + 1)
+                    }
+                })
+        };
+        let start = step(origin, span.start);
+        let end = step(start, span.end);
+        Location {
+            start_line: start.1,
+            start_col: start.2,
+            end_line: end.1,
+            end_col: end.2,
+        }
+    }
+
+    fn lex_token(&mut self) -> Result<Token> {
         // Remember position before skipping whitespace for newline detection
         let before_skip = self.position;
-        self.skip_whitespace_and_comments();
+        self.skip_whitespace_and_comments()?;
         let had_newline = self.source[before_skip..self.position].contains('\n');
 
         let start = self.position;
@@ -81,6 +129,13 @@ impl<'a> Lexer<'a> {
                 self.pending_semicolon = None;
 
                 let kind = match ch {
+                    // Doc comments are kept for tooling; other comments were skipped
+                    '/' if self.at_doc_comment() => self.lex_doc_comment()?,
+
+                    // Byte and byte string literals
+                    'b' if self.peek() == Some('\'') => self.lex_byte()?,
+                    'b' if self.peek() == Some('"') => self.lex_byte_string()?,
+
                     // Identifiers and keywords
                     'a'..='z' | 'A'..='Z' | '_' => self.lex_identifier(),
 
@@ -123,6 +178,10 @@ impl<'a> Lexer<'a> {
 
                     ',' => self.simple_token(TokenKind::Comma),
                     ';' => self.simple_token(TokenKind::Semicolon),
+                    '@' => self.simple_token(TokenKind::At),
+
+                    // Attributes: only `#[` starts a token, a bare `#` is still an error
+                    '#' if self.peek() == Some('[') => self.simple_token(TokenKind::Hash),
 
                     _ => {
                         self.advance();
@@ -135,7 +194,10 @@ impl<'a> Lexer<'a> {
                 };
 
                 let end = self.position;
-                self.last_token = Some(kind.clone());
+                // Doc comments sit between tokens without ending a statement
+                if !matches!(kind, TokenKind::DocComment(_)) {
+                    self.last_token = Some(kind.clone());
+                }
                 Ok(Token::new(kind, Span::new(start, end)))
             }
         }
@@ -145,6 +207,12 @@ impl<'a> Lexer<'a> {
     fn advance(&mut self) -> Option<char> {
         if let Some(ch) = self.current_char {
             self.position += ch.len_utf8();
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
             self.current_char = self.chars.next();
             Some(ch)
         } else {
@@ -193,8 +261,11 @@ impl<'a> Lexer<'a> {
                 | TokenKind::Float(_)
                 | TokenKind::String(_)
                 | TokenKind::Char(_)
+                | TokenKind::Byte(_)
+                | TokenKind::ByteString(_)
                 | TokenKind::True
-                | TokenKind::False => true,
+                | TokenKind::False
+                | TokenKind::SelfLower => true,
 
                 // Keywords that end statements
                 TokenKind::Return | TokenKind::Break | TokenKind::Continue => true,
@@ -202,6 +273,9 @@ impl<'a> Lexer<'a> {
                 // Closing delimiters
                 TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => true,
 
+                // Error propagation ends an expression: let n = parse(s)?
+                TokenKind::Question => true,
+
                 // Everything else doesn't trigger ASI
                 _ => false,
             },
@@ -209,36 +283,91 @@ impl<'a> Lexer<'a> {
     }
 
     /// Skip whitespace and comments
-    fn skip_whitespace_and_comments(&mut self) {
+    fn skip_whitespace_and_comments(&mut self) -> Result<()> {
         while let Some(ch) = self.current_char {
             match ch {
                 ' ' | '\t' | '\r' | '\n' => {
                     self.advance();
                 }
+                '/' if self.at_doc_comment() => break,
                 '/' if self.peek() == Some('/') => {
                     // Line comment
+                    let start = self.position;
                     self.advance(); // '/'
                     self.advance(); // '/'
                     while self.current_char.is_some() && self.current_char != Some('\n') {
                         self.advance();
                     }
+                    self.comments.push(Span::new(start, self.position));
                 }
                 '/' if self.peek() == Some('*') => {
-                    // Block comment
+                    let start = self.position;
+                    self.skip_block_comment()?;
+                    self.comments.push(Span::new(start, self.position));
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip a block comment. These nest, so `/* a /* b */ c */` is one comment.
+    fn skip_block_comment(&mut self) -> Result<()> {
+        let start = self.position;
+        self.advance(); // '/'
+        self.advance(); // '*'
+        let mut depth = 1;
+        while depth > 0 {
+            match (self.current_char, self.peek()) {
+                (None, _) => {
+                    return Err(Error::new(
+                        ErrorKind::UnterminatedComment,
+                        Span::new(start, self.position),
+                        "Unterminated block comment",
+                    ));
+                }
+                (Some('*'), Some('/')) => {
+                    self.advance(); // '*'
+                    self.advance(); // '/'
+                    depth -= 1;
+                }
+                (Some('/'), Some('*')) => {
                     self.advance(); // '/'
                     self.advance(); // '*'
-                    while self.current_char.is_some() {
-                        if self.current_char == Some('*') && self.peek() == Some('/') {
-                            self.advance(); // '*'
-                            self.advance(); // '/'
-                            break;
-                        }
-                        self.advance();
-                    }
+                    depth += 1;
+                }
+                _ => {
+                    self.advance();
                 }
-                _ => break,
             }
         }
+        Ok(())
+    }
+
+    /// Whether a doc comment starts here: `///` or `/**`, but not the plain
+    /// comments `////...`, `/**/` and `/***...`
+    fn at_doc_comment(&self) -> bool {
+        let rest = &self.source[self.position..];
+        (rest.starts_with("///") && !rest.starts_with("////"))
+            || (rest.starts_with("/**") && !rest.starts_with("/**/") && !rest.starts_with("/***"))
+    }
+
+    /// Lex a doc comment into its text: a `///` line loses the marker and
+    /// one following space, a `/** */` block is trimmed
+    fn lex_doc_comment(&mut self) -> Result<TokenKind> {
+        let start = self.position;
+        if self.source[start..].starts_with("/**") {
+            self.skip_block_comment()?;
+            let text = &self.source[start + 3..self.position - 2];
+            return Ok(TokenKind::DocComment(text.trim().to_string()));
+        }
+
+        while self.current_char.is_some() && self.current_char != Some('\n') {
+            self.advance();
+        }
+        let text = &self.source[start + 3..self.position];
+        let text = text.strip_prefix(' ').unwrap_or(text);
+        Ok(TokenKind::DocComment(text.trim_end().to_string()))
     }
 
     /// Create simple single-character token
@@ -268,30 +397,47 @@ impl<'a> Lexer<'a> {
         let start = self.position;
 
         // Collect digits
-        while let Some(ch) = self.current_char {
-            if ch.is_ascii_digit() {
-                self.advance();
-            } else {
-                break;
-            }
-        }
+        self.lex_digits(start)?;
+
+        let mut is_float = false;
 
         // Check for decimal point
         if self.current_char == Some('.') && self.peek().is_some_and(|c| c.is_ascii_digit()) {
             self.advance(); // '.'
+            is_float = true;
 
             // Collect fractional digits
-            while let Some(ch) = self.current_char {
-                if ch.is_ascii_digit() {
-                    self.advance();
-                } else {
-                    break;
-                }
+            let fraction_start = self.position;
+            self.lex_digits(fraction_start)?;
+        }
+
+        // Check for exponent: e10, E-3, e+7
+        if matches!(self.current_char, Some('e' | 'E')) {
+            self.advance();
+            is_float = true;
+
+            if matches!(self.current_char, Some('+' | '-')) {
+                self.advance();
+            }
+
+            if !self.current_char.is_some_and(|c| c.is_ascii_digit()) {
+                return Err(Error::new(
+                    ErrorKind::InvalidNumber,
+                    Span::new(start, self.position),
+                    format!(
+                        "Invalid float: {}; exponent needs at least one digit",
+                        &self.source[start..self.position]
+                    ),
+                ));
             }
+            let exponent_start = self.position;
+            self.lex_digits(exponent_start)?;
+        }
 
+        if is_float {
             // Parse as float
             let num_str = &self.source[start..self.position];
-            match num_str.parse::<f64>() {
+            match num_str.replace('_', "").parse::<f64>() {
                 Ok(n) => Ok(TokenKind::Float(n)),
                 Err(_) => Err(Error::new(
                     ErrorKind::InvalidNumber,
@@ -302,7 +448,7 @@ impl<'a> Lexer<'a> {
         } else {
             // Parse as integer
             let num_str = &self.source[start..self.position];
-            match num_str.parse::<i64>() {
+            match num_str.replace('_', "").parse::<i64>() {
                 Ok(n) => Ok(TokenKind::Integer(n)),
                 Err(_) => Err(Error::new(
                     ErrorKind::InvalidNumber,
@@ -313,6 +459,32 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Collect a run of digits starting at `start`, allowing single `_`
+    /// separators between digits (`1_000_000`)
+    fn lex_digits(&mut self, start: usize) -> Result<()> {
+        while let Some(ch) = self.current_char {
+            if ch.is_ascii_digit() || ch == '_' {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        let digits = &self.source[start..self.position];
+        if digits.ends_with('_') || digits.contains("__") {
+            return Err(Error::new(
+                ErrorKind::InvalidNumber,
+                Span::new(start, self.position),
+                format!(
+                    "Invalid number: {}; `_` separators must sit between digits",
+                    digits
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Lex string literal
     fn lex_string(&mut self) -> Result<TokenKind> {
         let start = self.position;
@@ -353,6 +525,10 @@ impl<'a> Lexer<'a> {
                             string.push('{');
                             self.advance();
                         }
+                        Some('x') => {
+                            self.advance();
+                            string.push(self.lex_ascii_escape(start)?);
+                        }
                         _ => {
                             return Err(Error::new(
                                 ErrorKind::InvalidChar,
@@ -391,12 +567,15 @@ impl<'a> Lexer<'a> {
         let ch = match self.current_char {
             Some('\\') => {
                 self.advance();
-                match self.current_char {
+                let escape = self.current_char;
+                self.advance();
+                match escape {
                     Some('n') => '\n',
                     Some('t') => '\t',
                     Some('r') => '\r',
                     Some('\\') => '\\',
                     Some('\'') => '\'',
+                    Some('x') => self.lex_ascii_escape(start)?,
                     _ => {
                         return Err(Error::new(
                             ErrorKind::InvalidChar,
@@ -406,7 +585,10 @@ impl<'a> Lexer<'a> {
                     }
                 }
             }
-            Some(ch) => ch,
+            Some(ch) => {
+                self.advance();
+                ch
+            }
             None => {
                 return Err(Error::new(
                     ErrorKind::UnterminatedChar,
@@ -416,8 +598,6 @@ impl<'a> Lexer<'a> {
             }
         };
 
-        self.advance();
-
         if self.current_char != Some('\'') {
             return Err(Error::new(
                 ErrorKind::UnterminatedChar,
@@ -430,6 +610,134 @@ impl<'a> Lexer<'a> {
         Ok(TokenKind::Char(ch))
     }
 
+    /// Lex byte literal: b'A'
+    fn lex_byte(&mut self) -> Result<TokenKind> {
+        let start = self.position;
+        self.advance(); // 'b'
+        self.advance(); // Opening '\''
+
+        let byte = match self.current_char {
+            Some('\'') | Some('\n') | None => {
+                return Err(Error::new(
+                    ErrorKind::UnterminatedChar,
+                    Span::new(start, self.position),
+                    "Unterminated byte literal",
+                ));
+            }
+            Some(_) => self.lex_byte_char(start)?,
+        };
+
+        if self.current_char != Some('\'') {
+            return Err(Error::new(
+                ErrorKind::UnterminatedChar,
+                Span::new(start, self.position),
+                "Unterminated byte literal",
+            ));
+        }
+
+        self.advance(); // Closing '\''
+        Ok(TokenKind::Byte(byte))
+    }
+
+    /// Lex byte string literal: b"bytes"
+    fn lex_byte_string(&mut self) -> Result<TokenKind> {
+        let start = self.position;
+        self.advance(); // 'b'
+        self.advance(); // Opening '"'
+
+        let mut bytes = Vec::new();
+
+        while let Some(ch) = self.current_char {
+            match ch {
+                '"' => {
+                    self.advance(); // Closing '"'
+                    return Ok(TokenKind::ByteString(bytes));
+                }
+                '\n' | '\r' => break,
+                _ => bytes.push(self.lex_byte_char(start)?),
+            }
+        }
+
+        Err(Error::new(
+            ErrorKind::UnterminatedString,
+            Span::new(start, self.position),
+            "Unterminated byte string literal",
+        ))
+    }
+
+    /// Lex one ASCII character or escape of a byte literal, including `\xNN`
+    fn lex_byte_char(&mut self, start: usize) -> Result<u8> {
+        let ch = self.current_char.unwrap_or_default();
+        self.advance();
+
+        if ch != '\\' {
+            if !ch.is_ascii() {
+                return Err(Error::new(
+                    ErrorKind::InvalidChar,
+                    Span::new(start, self.position),
+                    format!(
+                        "Non-ASCII character '{}' in byte literal; use a \\x escape",
+                        ch
+                    ),
+                ));
+            }
+            return Ok(ch as u8);
+        }
+
+        let escape = self.current_char;
+        self.advance();
+        let byte = match escape {
+            Some('n') => b'\n',
+            Some('t') => b'\t',
+            Some('r') => b'\r',
+            Some('0') => b'\0',
+            Some('\\') => b'\\',
+            Some('\'') => b'\'',
+            Some('"') => b'"',
+            Some('x') => self.lex_hex_escape(start)?,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidChar,
+                    Span::new(start, self.position),
+                    "Invalid escape sequence in byte literal",
+                ));
+            }
+        };
+        Ok(byte)
+    }
+
+    /// Lex the two hex digits of a `\xNN` escape, just past the `x`
+    fn lex_hex_escape(&mut self, start: usize) -> Result<u8> {
+        let digits: String = (0..2).filter_map(|_| self.advance()).collect();
+        u8::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidChar,
+                    Span::new(start, self.position),
+                    "Invalid \\x escape; expected two hex digits",
+                )
+            })
+    }
+
+    /// Lex a `\xNN` escape in a string or char literal. Larger values would
+    /// be a lone byte of a multi-byte UTF-8 sequence, so only ASCII is allowed.
+    fn lex_ascii_escape(&mut self, start: usize) -> Result<char> {
+        let byte = self.lex_hex_escape(start)?;
+        if !byte.is_ascii() {
+            return Err(Error::new(
+                ErrorKind::InvalidChar,
+                Span::new(start, self.position),
+                format!(
+                    "\\x{:02X} is out of range; \\x escapes in text go up to \\x7F",
+                    byte
+                ),
+            ));
+        }
+        Ok(byte as char)
+    }
+
     // Operator lexing methods
 
     fn lex_plus(&mut self) -> TokenKind {
@@ -553,6 +861,102 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// Yields tokens up to and including `Eof`, then `None`. A lexer error is
+/// yielded once as `Err` and also ends the stream.
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.next_token();
+        self.finished = match &result {
+            Ok(token) => matches!(token.value, TokenKind::Eof),
+            Err(_) => true,
+        };
+        Some(result)
+    }
+}
+
+/// Render tokens back to approximate source, for eyeballing what the lexer
+/// produced. Semicolons inserted by ASI are zero-width and come out as line
+/// breaks, which re-insert them; explicit ones keep their `;`. Lexing the
+/// output again gives the same token kinds.
+pub fn tokens_to_source(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut prev: Option<&TokenKind> = None;
+
+    for token in tokens {
+        let kind = &token.value;
+        let inserted = matches!(kind, TokenKind::Semicolon) && token.span.start == token.span.end;
+        if inserted {
+            out.push('\n');
+            prev = None;
+            continue;
+        }
+
+        match kind {
+            TokenKind::Eof => break,
+            TokenKind::RightBrace => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+
+        match prev {
+            None => out.push_str(&"    ".repeat(depth)),
+            Some(prev) if needs_space(prev, kind) => out.push(' '),
+            Some(_) => {}
+        }
+        out.push_str(&match kind {
+            TokenKind::Float(x) => crate::formatter::float_literal(*x),
+            TokenKind::String(s) => crate::formatter::string_literal(s),
+            TokenKind::Char(c) => crate::formatter::char_literal(*c),
+            _ => kind.to_string(),
+        });
+
+        // Breaking after these can't trigger ASI, and a line doc comment
+        // runs to the end of its line
+        if matches!(
+            kind,
+            TokenKind::LeftBrace | TokenKind::Semicolon | TokenKind::DocComment(_)
+        ) {
+            if matches!(kind, TokenKind::LeftBrace) {
+                depth += 1;
+            }
+            out.push('\n');
+            prev = None;
+        } else {
+            prev = Some(kind);
+        }
+    }
+
+    out
+}
+
+/// Whether `next` should be separated from `prev` by a space. Tokens are only
+/// joined where the joined text can't lex differently.
+fn needs_space(prev: &TokenKind, next: &TokenKind) -> bool {
+    use TokenKind::*;
+    match (prev, next) {
+        // `t.0.1` would lex `0.1` as a float, `: :` as `::`, and so on
+        (Integer(_), Dot)
+        | (Colon, Colon | ColonColon)
+        | (Dot, Dot | DotDot | DotDotEqual)
+        | (DotDot, Dot)
+        | (Bang, Equal | EqualEqual) => true,
+        (LeftParen | LeftBracket | Dot | ColonColon | Hash | Bang | Tilde, _) => false,
+        (
+            _,
+            RightParen | RightBracket | Comma | Dot | ColonColon | Colon | Question | Semicolon,
+        ) => false,
+        // Calls and indexing
+        (Ident(_) | SelfLower | RightParen | RightBracket, LeftParen | LeftBracket) => false,
+        _ => true,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -613,6 +1017,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_digit_separators() {
+        let tokens = lex("1_000 3_000.5 0.000_1").unwrap();
+        assert_eq!(
+            &tokens[..3],
+            [
+                TokenKind::Integer(1000),
+                TokenKind::Float(3000.5),
+                TokenKind::Float(0.0001)
+            ]
+        );
+
+        for bad in ["100_", "1__0", "1_.5", "2.5_"] {
+            let err = lex(bad).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::InvalidNumber, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_error_location() {
+        let err = lex("fn main() {\n    let x = 1;\n    let y = x $ 2;\n}\n").unwrap_err();
+        assert_eq!(
+            err.location,
+            Some(Location {
+                start_line: 3,
+                start_col: 15,
+                end_line: 3,
+                end_col: 16,
+            })
+        );
+
+        // Columns count chars, not bytes
+        let err = lex("let a = 1\nlet b = \"é\nx").unwrap_err();
+        let location = err.location.unwrap();
+        assert_eq!((location.start_line, location.start_col), (2, 9));
+        assert_eq!((location.end_line, location.end_col), (2, 11));
+    }
+
+    #[test]
+    fn test_scientific_notation() {
+        let tokens = lex("1e10 2.5e-3 6.022E23 1e+2").unwrap();
+        assert_eq!(
+            &tokens[..4],
+            [
+                TokenKind::Float(1e10),
+                TokenKind::Float(2.5e-3),
+                TokenKind::Float(6.022e23),
+                TokenKind::Float(100.0)
+            ]
+        );
+
+        for bad in ["1e", "1e+", "2.5E-"] {
+            let err = lex(bad).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::InvalidNumber, "{}", bad);
+        }
+    }
+
     #[test]
     fn test_strings() {
         let tokens = lex(r#""hello" "world\n" "test""#).unwrap();
@@ -628,6 +1089,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hex_escapes() {
+        let tokens = lex(r#""\x41\x62c" '\x7F'"#).unwrap();
+        assert_eq!(tokens[0], TokenKind::String("Abc".to_string()));
+        assert_eq!(tokens[1], TokenKind::Char('\x7F'));
+
+        for source in [r#""\xZZ""#, r#""\x4""#, r#"'\x4'"#, r#""\xFF""#] {
+            assert_eq!(
+                lex(source).unwrap_err().kind,
+                ErrorKind::InvalidChar,
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_byte_literals() {
+        let tokens = lex(r#"b'A' b'\n' b"\x00\xFF" b"hi""#).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                TokenKind::Byte(65),
+                TokenKind::Byte(b'\n'),
+                TokenKind::ByteString(vec![0x00, 0xFF]),
+                TokenKind::ByteString(b"hi".to_vec()),
+                TokenKind::Semicolon, // Auto-inserted at EOF
+                TokenKind::Eof,
+            ]
+        );
+
+        // A `b` not followed by a quote is still an identifier
+        assert_eq!(
+            lex("b").unwrap().first(),
+            Some(&TokenKind::Ident("b".to_string()))
+        );
+
+        for source in ["b'é'", "b\"naïve\"", r#"b"\xG0""#, r#"b'\x4'"#] {
+            assert_eq!(
+                lex(source).unwrap_err().kind,
+                ErrorKind::InvalidChar,
+                "{}",
+                source
+            );
+        }
+    }
+
     #[test]
     fn test_operators() {
         let tokens = lex("+ - * / == != <= >= && ||").unwrap();
@@ -674,6 +1182,106 @@ mod tests {
         let tokens = lex(source).unwrap();
         assert_eq!(tokens[0], TokenKind::Let);
         assert_eq!(tokens[1], TokenKind::Ident("x".to_string()));
+
+        let mut lexer = Lexer::new(source);
+        lexer.tokenize().unwrap();
+        let comments: Vec<&str> = lexer
+            .comments()
+            .iter()
+            .map(|s| &source[s.start..s.end])
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                "// This is a comment",
+                "// inline comment",
+                "/* block comment */"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let source = "// plain\n/// Adds one.\n//// also plain\n/** Block\n    doc */ /**/ fn";
+        assert_eq!(
+            lex(source).unwrap(),
+            vec![
+                TokenKind::DocComment("Adds one.".to_string()),
+                TokenKind::DocComment("Block\n    doc".to_string()),
+                TokenKind::Fn,
+                TokenKind::Eof,
+            ]
+        );
+
+        // Only the plain comments were skipped
+        let mut lexer = Lexer::new(source);
+        lexer.tokenize().unwrap();
+        let comments: Vec<&str> = lexer
+            .comments()
+            .iter()
+            .map(|s| &source[s.start..s.end])
+            .collect();
+        assert_eq!(comments, ["// plain", "//// also plain", "/**/"]);
+
+        // A trailing doc comment doesn't stop ASI at the end of its line
+        assert_eq!(
+            lex("x /// note\ny").unwrap(),
+            vec![
+                TokenKind::Ident("x".to_string()),
+                TokenKind::DocComment("note".to_string()),
+                TokenKind::Semicolon,
+                TokenKind::Ident("y".to_string()),
+                TokenKind::Semicolon,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shebang_line() {
+        let tokens = lex("#!/usr/bin/env fruti\nfn main(){}").unwrap();
+        assert_eq!(tokens[0], TokenKind::Fn);
+        assert_eq!(tokens[1], TokenKind::Ident("main".to_string()));
+
+        // Only at the very start of the file
+        let err = lex("\n#!/usr/bin/env fruti\nfn main(){}").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnexpectedCharacter);
+        let err = lex("fn main(){}\n#!oops").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnexpectedCharacter);
+    }
+
+    #[test]
+    fn test_nested_block_comments() {
+        let source = "/* outer /* inner */ still comment */ let";
+        assert_eq!(lex(source).unwrap(), vec![TokenKind::Let, TokenKind::Eof]);
+
+        let err = lex("let x = 1 /* open /* nested */ never closed").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnterminatedComment);
+        assert_eq!(err.message, "Unterminated block comment");
+    }
+
+    #[test]
+    fn test_tokens_to_source_relexes_to_the_same_tokens() {
+        let source = "fn area(p: Point) -> f64 {
+    let s = \"a\\\"b\\n\"; let t = p.pair.0
+    if !done { return 1.0 * t }
+    p.w[0] as f64
+}
+";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let rendered = tokens_to_source(&tokens);
+        assert_eq!(
+            rendered,
+            "fn area(p: Point) -> f64 {
+    let s = \"a\\\"b\\n\";
+    let t = p.pair.0
+    if !done {
+        return 1.0 * t }
+    p.w[0] as f64
+}
+"
+        );
+        assert_eq!(lex(&rendered).unwrap(), lex(source).unwrap());
     }
 
     #[test]
@@ -766,4 +1374,24 @@ let y = 100;
             ]
         );
     }
+
+    #[test]
+    fn test_iterator_matches_tokenize() {
+        let source = "fn main() {\n    let x = 5\n}";
+        let iterated: Vec<TokenKind> = Lexer::new(source).map(|t| t.unwrap().value).collect();
+
+        assert_eq!(iterated, lex(source).unwrap());
+        assert_eq!(iterated.last(), Some(&TokenKind::Eof));
+
+        let mut lexer = Lexer::new("x");
+        assert_eq!(lexer.by_ref().count(), 3); // x, ASI semicolon, Eof
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_iterator_stops_after_error() {
+        let mut lexer = Lexer::new("\"unterminated");
+        assert!(matches!(lexer.next(), Some(Err(_))));
+        assert!(lexer.next().is_none());
+    }
 }
@@ -3,19 +3,27 @@
 
 pub mod ast;
 pub mod codegen;
+pub mod const_eval;
+pub mod desugar;
 pub mod error;
+pub mod formatter;
 pub mod lexer;
+pub mod lint;
+pub mod optimize;
 pub mod parser;
 pub mod semantic;
+pub mod source;
 pub mod span;
 pub mod token;
 
 // Re-exports for convenience
 pub use ast::*;
-pub use codegen::CodeGen;
-pub use error::{Error, Result};
+pub use codegen::{CodeGen, OutputFormat};
+pub use error::{Error, Location, Palette, Result};
 pub use lexer::Lexer;
-pub use parser::Parser;
+pub use lint::Linter;
+pub use parser::{parse_source, Parser};
 pub use semantic::TypeChecker;
+pub use source::Source;
 pub use span::{Span, Spanned};
 pub use token::{Token, TokenKind};
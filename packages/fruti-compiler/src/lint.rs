@@ -0,0 +1,836 @@
+// Linter - Fruti Compiler
+//
+// Style checks over a parsed module. Every rule warns by default; users can
+// allow a rule to silence it or deny it to make its findings errors.
+
+use crate::ast::*;
+use crate::span::{Span, Spanned};
+use std::collections::{HashMap, HashSet};
+
+/// A lint rule users can refer to by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lint {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Every rule the linter knows, in the order `fruti lints` lists them
+pub const LINTS: &[Lint] = &[
+    Lint {
+        name: "non_snake_case",
+        description: "functions, variables, and parameters should be snake_case",
+    },
+    Lint {
+        name: "non_pascal_case",
+        description: "types, traits, and enums should be PascalCase",
+    },
+    Lint {
+        name: "non_screaming_case",
+        description: "constants should be SCREAMING_SNAKE_CASE",
+    },
+    Lint {
+        name: "redundant_return",
+        description: "a final `return x;` that could be a trailing expression",
+    },
+    Lint {
+        name: "implicit_return",
+        description: "a trailing expression where `--return-style explicit` wants `return`",
+    },
+    Lint {
+        name: "unused_variable",
+        description: "a `let` binding that is never used; prefix it with `_` if intended",
+    },
+    Lint {
+        name: "unused_function",
+        description: "a private function other than `main` that is never called",
+    },
+];
+
+/// Look up a rule by name
+pub fn find_lint(name: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|lint| lint.name == name)
+}
+
+/// How a rule's findings are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Level {
+    /// Not reported at all
+    Allow,
+    /// Reported without failing the command
+    #[default]
+    Warn,
+    /// Reported as an error that fails the command
+    Deny,
+}
+
+/// A single lint finding
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub span: Span,
+    pub message: String,
+}
+
+/// Preferred way to return a value from the end of a function body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReturnStyle {
+    /// `x` as the trailing expression; flags a final `return x;` (redundant_return)
+    #[default]
+    Trailing,
+    /// `return x;` as the final statement; flags a trailing `x` (implicit_return)
+    Explicit,
+}
+
+/// Naming convention a casing rule enforces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    /// Functions, variables, and parameters: `parse_line`
+    Snake,
+    /// Types, traits, and enums: `LineReader`
+    Pascal,
+    /// Constants: `MAX_LINES`
+    ScreamingSnake,
+}
+
+impl Case {
+    fn rule(self) -> &'static str {
+        match self {
+            Case::Snake => "non_snake_case",
+            Case::Pascal => "non_pascal_case",
+            Case::ScreamingSnake => "non_screaming_case",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Case::Snake => "snake_case",
+            Case::Pascal => "PascalCase",
+            Case::ScreamingSnake => "SCREAMING_SNAKE_CASE",
+        }
+    }
+
+    fn matches(self, name: &str) -> bool {
+        let name = name.trim_start_matches('_');
+        match self {
+            Case::Snake => !name.chars().any(char::is_uppercase) && !name.contains("__"),
+            Case::Pascal => {
+                name.is_empty() || (name.starts_with(char::is_uppercase) && !name.contains('_'))
+            }
+            Case::ScreamingSnake => !name.chars().any(char::is_lowercase) && !name.contains("__"),
+        }
+    }
+
+    /// Rewrite a name in this case, keeping any leading underscores
+    fn convert(self, name: &str) -> String {
+        let trimmed = name.trim_start_matches('_');
+        let prefix = &name[..name.len() - trimmed.len()];
+        let words = split_words(trimmed);
+
+        let converted = match self {
+            Case::Snake => words.join("_"),
+            Case::ScreamingSnake => words.join("_").to_uppercase(),
+            Case::Pascal => words
+                .iter()
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                        .unwrap_or_default()
+                })
+                .collect::<String>(),
+        };
+
+        format!("{}{}", prefix, converted)
+    }
+}
+
+/// Split an identifier into lowercase words at underscores and lower-to-upper
+/// case changes: `parseHTTPLine` -> `parse`, `httpline`
+fn split_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev: Option<char> = None;
+
+    for ch in name.chars() {
+        let boundary = ch == '_'
+            || (ch.is_uppercase() && prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()));
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        if ch != '_' {
+            current.extend(ch.to_lowercase());
+        }
+        prev = Some(ch);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+/// Runs style rules over a module
+#[derive(Debug, Default)]
+pub struct Linter {
+    return_style: ReturnStyle,
+    /// Rules set to something other than the default `Warn`
+    levels: HashMap<String, Level>,
+    findings: Vec<Finding>,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_return_style(mut self, style: ReturnStyle) -> Self {
+        self.return_style = style;
+        self
+    }
+
+    /// Suppress the named rules (e.g. `non_snake_case`)
+    pub fn with_allowed<I, S>(self, rules: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with_level(rules, Level::Allow)
+    }
+
+    /// Report the named rules at `level`
+    pub fn with_level<I, S>(mut self, rules: I, level: Level) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.levels
+            .extend(rules.into_iter().map(|rule| (rule.into(), level)));
+        self
+    }
+
+    /// The level the named rule is reported at
+    pub fn level(&self, rule: &str) -> Level {
+        self.levels.get(rule).copied().unwrap_or_default()
+    }
+
+    /// Lint a module, returning its findings in source order
+    pub fn lint_module(&mut self, module: &Module) -> Vec<Finding> {
+        self.findings.clear();
+
+        for item in &module.items {
+            match item {
+                Item::Function(func) => self.lint_function(func),
+                Item::Impl(imp) => imp.methods.iter().for_each(|m| self.lint_function(m)),
+                Item::Struct(s) => self.check_case(Case::Pascal, "Struct", &s.name),
+                Item::Enum(e) => self.check_case(Case::Pascal, "Enum", &e.name),
+                Item::TypeAlias(t) => self.check_case(Case::Pascal, "Type", &t.name),
+                Item::Trait(t) => {
+                    self.check_case(Case::Pascal, "Trait", &t.name);
+                    for method in &t.methods {
+                        self.check_case(Case::Snake, "Method", &method.name);
+                    }
+                }
+                Item::Const(c) => self.check_case(Case::ScreamingSnake, "Constant", &c.name),
+                Item::Import(_) | Item::Mod(_) => {}
+            }
+        }
+        self.check_usage(module);
+
+        let mut findings = std::mem::take(&mut self.findings);
+        findings.retain(|f| self.level(f.rule) != Level::Allow);
+        findings.sort_by_key(|f| f.span.start);
+        findings
+    }
+
+    fn check_case(&mut self, case: Case, what: &str, name: &Spanned<String>) {
+        if case.matches(&name.value) {
+            return;
+        }
+
+        self.findings.push(Finding {
+            rule: case.rule(),
+            span: name.span,
+            message: format!(
+                "{} '{}' should be {}, e.g. '{}'",
+                what,
+                name.value,
+                case.description(),
+                case.convert(&name.value)
+            ),
+        });
+    }
+
+    /// Flag `let` bindings that are never used, and private functions that
+    /// nothing else refers to
+    fn check_usage(&mut self, module: &Module) {
+        // Names each body refers to without binding them, keyed by the
+        // top-level function it belongs to, if any
+        let mut references: Vec<(Option<&str>, HashSet<&str>)> = Vec::new();
+
+        for item in &module.items {
+            match item {
+                Item::Function(func) => {
+                    let scopes = Scopes::of_function(func);
+                    self.report_unused(&scopes.unused);
+                    references.push((Some(&func.name.value), scopes.free));
+                }
+                Item::Impl(imp) => {
+                    for method in &imp.methods {
+                        let scopes = Scopes::of_function(method);
+                        self.report_unused(&scopes.unused);
+                        references.push((None, scopes.free));
+                    }
+                }
+                Item::Const(c) => {
+                    let mut scopes = Scopes::default();
+                    scopes.expr(&c.value);
+                    references.push((None, scopes.free));
+                }
+                Item::Mod(m) => references.push((None, Scopes::of_items(&m.items))),
+                _ => {}
+            }
+        }
+
+        for item in &module.items {
+            let Item::Function(func) = item else {
+                continue;
+            };
+            let name = func.name.value.as_str();
+            if func.is_pub || name == "main" || name.starts_with('_') {
+                continue;
+            }
+            // Calls from its own body don't count, so recursion alone isn't a use
+            let called = references
+                .iter()
+                .any(|(owner, names)| *owner != Some(name) && names.contains(name));
+            if !called {
+                self.findings.push(Finding {
+                    rule: "unused_function",
+                    span: func.name.span,
+                    message: format!("Function '{}' is never called", name),
+                });
+            }
+        }
+    }
+
+    fn report_unused(&mut self, unused: &[&Spanned<String>]) {
+        for name in unused {
+            self.findings.push(Finding {
+                rule: "unused_variable",
+                span: name.span,
+                message: format!(
+                    "Variable '{}' is never used; prefix it with an underscore if intended",
+                    name.value
+                ),
+            });
+        }
+    }
+
+    /// Check the names of variables bound anywhere in a block
+    fn check_block_names(&mut self, block: &Block) {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let { name, value, .. } => {
+                    self.check_case(Case::Snake, "Variable", name);
+                    if let Some(value) = value {
+                        self.check_expr_names(value);
+                    }
+                }
+                Stmt::Expr(expr) | Stmt::Return(Some(expr)) => self.check_expr_names(expr),
+                Stmt::Return(None) | Stmt::Break(_) | Stmt::Continue(_) => {}
+                Stmt::While { condition, body } => {
+                    self.check_expr_names(condition);
+                    self.check_block_names(body);
+                }
+                Stmt::For { var, iter, body } => {
+                    self.check_case(Case::Snake, "Variable", var);
+                    self.check_expr_names(iter);
+                    self.check_block_names(body);
+                }
+                Stmt::Loop { body } => self.check_block_names(body),
+            }
+        }
+        if let Some(expr) = &block.expr {
+            self.check_expr_names(expr);
+        }
+    }
+
+    /// Descend into the blocks and lambdas nested in an expression
+    fn check_expr_names(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::If {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                self.check_expr_names(condition);
+                self.check_block_names(then_block);
+                if let Some(block) = else_block {
+                    self.check_block_names(block);
+                }
+            }
+            ExprKind::Match { expr, arms } => {
+                self.check_expr_names(expr);
+                for arm in arms {
+                    self.check_expr_names(&arm.body);
+                }
+            }
+            ExprKind::Block(block) => self.check_block_names(block),
+            ExprKind::Lambda { params, body, .. } => {
+                for param in params {
+                    self.check_case(Case::Snake, "Parameter", &param.name);
+                }
+                self.check_expr_names(body);
+            }
+            _ => {}
+        }
+    }
+
+    fn lint_function(&mut self, func: &Function) {
+        self.check_case(Case::Snake, "Function", &func.name);
+        for param in &func.params {
+            self.check_case(Case::Snake, "Parameter", &param.name);
+        }
+        self.check_block_names(&func.body);
+
+        let body = &func.body;
+
+        match self.return_style {
+            ReturnStyle::Trailing => {
+                // Only the final statement counts; early returns are the point of `return`
+                if let (Some(Stmt::Return(Some(value))), None) = (body.stmts.last(), &body.expr) {
+                    self.findings.push(Finding {
+                        rule: "redundant_return",
+                        span: value.span,
+                        message: format!(
+                            "`return` at the end of '{}' can be written as a trailing expression",
+                            func.name.value
+                        ),
+                    });
+                }
+            }
+            ReturnStyle::Explicit => {
+                if let Some(value) = &body.expr {
+                    if func.return_type.is_some() {
+                        self.findings.push(Finding {
+                            rule: "implicit_return",
+                            span: value.span,
+                            message: format!(
+                                "Trailing expression of '{}' can be written as `return`",
+                                func.name.value
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A name in scope while walking a body
+struct Local<'a> {
+    name: &'a str,
+    /// The `let` that introduced it; other bindings are never reported
+    binding: Option<&'a Spanned<String>>,
+    used: bool,
+}
+
+/// Resolves the names used in a body against the bindings in scope, finding
+/// the `let` bindings that are never used and the names that refer to items
+#[derive(Default)]
+struct Scopes<'a> {
+    locals: Vec<Local<'a>>,
+    unused: Vec<&'a Spanned<String>>,
+    /// Names used but not bound locally: functions, constants, and so on
+    free: HashSet<&'a str>,
+}
+
+impl<'a> Scopes<'a> {
+    fn of_function(func: &'a Function) -> Self {
+        let mut scopes = Scopes::default();
+        if func.receiver.is_some() {
+            scopes.bind("self", None);
+        }
+        for param in &func.params {
+            scopes.bind(&param.name.value, None);
+        }
+        scopes.block(&func.body);
+        scopes
+    }
+
+    /// The free names of every body in `items`, including nested modules
+    fn of_items(items: &'a [Item]) -> HashSet<&'a str> {
+        let mut free = HashSet::new();
+        for item in items {
+            match item {
+                Item::Function(func) => free.extend(Scopes::of_function(func).free),
+                Item::Impl(imp) => {
+                    for method in &imp.methods {
+                        free.extend(Scopes::of_function(method).free);
+                    }
+                }
+                Item::Const(c) => {
+                    let mut scopes = Scopes::default();
+                    scopes.expr(&c.value);
+                    free.extend(scopes.free);
+                }
+                Item::Mod(m) => free.extend(Scopes::of_items(&m.items)),
+                _ => {}
+            }
+        }
+        free
+    }
+
+    fn bind(&mut self, name: &'a str, binding: Option<&'a Spanned<String>>) {
+        self.locals.push(Local {
+            name,
+            binding,
+            used: false,
+        });
+    }
+
+    fn reference(&mut self, name: &'a str) {
+        match self
+            .locals
+            .iter_mut()
+            .rev()
+            .find(|local| local.name == name)
+        {
+            Some(local) => local.used = true,
+            None => {
+                self.free.insert(name);
+            }
+        }
+    }
+
+    /// Leave every scope entered since the stack was `depth` deep
+    fn exit(&mut self, depth: usize) {
+        for local in self.locals.drain(depth..) {
+            if let (Some(binding), false) = (local.binding, local.used) {
+                if !local.name.starts_with('_') {
+                    self.unused.push(binding);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, block: &'a Block) {
+        let depth = self.locals.len();
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let { name, value, .. } => {
+                    // The value is evaluated before the name is in scope
+                    if let Some(value) = value {
+                        self.expr(value);
+                    }
+                    self.bind(&name.value, Some(name));
+                }
+                Stmt::Expr(expr) | Stmt::Return(Some(expr)) => self.expr(expr),
+                Stmt::Return(None) | Stmt::Break(_) | Stmt::Continue(_) => {}
+                Stmt::While { condition, body } => {
+                    self.expr(condition);
+                    self.block(body);
+                }
+                Stmt::For { var, iter, body } => {
+                    self.expr(iter);
+                    let depth = self.locals.len();
+                    self.bind(&var.value, None);
+                    self.block(body);
+                    self.exit(depth);
+                }
+                Stmt::Loop { body } => self.block(body),
+            }
+        }
+        if let Some(expr) = &block.expr {
+            self.expr(expr);
+        }
+        self.exit(depth);
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::Integer(_)
+            | ExprKind::Float(_)
+            | ExprKind::Char(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Byte(_)
+            | ExprKind::ByteString(_) => {}
+            ExprKind::String(text) => {
+                // Interpolations like "Sum: {sum}" and "{p.x}" use the leading name
+                for (start, _) in text.match_indices('{') {
+                    let rest = &text[start + 1..];
+                    let len = rest
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    if len > 0 && !rest.starts_with(|c: char| c.is_ascii_digit()) {
+                        self.reference(&rest[..len]);
+                    }
+                }
+            }
+            ExprKind::Ident(name) => self.reference(name),
+            ExprKind::Path { path, .. } => self.reference(&path[0].value),
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::Field { expr, .. }
+            | ExprKind::Cast { expr, .. }
+            | ExprKind::Is { expr, .. } => self.expr(expr),
+            ExprKind::Call { func, args, .. } => {
+                self.expr(func);
+                args.iter().for_each(|arg| self.expr(arg));
+            }
+            ExprKind::MethodCall { receiver, args, .. } => {
+                self.expr(receiver);
+                args.iter().for_each(|arg| self.expr(arg));
+            }
+            ExprKind::Index { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::Range { start, end, .. } => {
+                for bound in [start, end].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            ExprKind::If {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                // `if let` bindings are in scope for the then-block only
+                let depth = self.locals.len();
+                self.expr(condition);
+                self.block(then_block);
+                self.exit(depth);
+                if let Some(block) = else_block {
+                    self.block(block);
+                }
+            }
+            ExprKind::Let { pattern, expr } => {
+                self.expr(expr);
+                self.bind_pattern(pattern);
+            }
+            ExprKind::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    let depth = self.locals.len();
+                    self.bind_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                    self.exit(depth);
+                }
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::Tuple(exprs) | ExprKind::Array(exprs) => {
+                exprs.iter().for_each(|e| self.expr(e))
+            }
+            ExprKind::StructLit { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            ExprKind::Lambda { params, body, .. } => {
+                let depth = self.locals.len();
+                for param in params {
+                    self.bind(&param.name.value, None);
+                }
+                self.expr(body);
+                self.exit(depth);
+            }
+        }
+    }
+
+    /// Bring a pattern's names into scope. A bare name may be a unit variant
+    /// rather than a binding; shadowing with it only hides outer names.
+    fn bind_pattern(&mut self, pattern: &'a Pattern) {
+        match pattern {
+            Pattern::Ident(name) => self.bind(name, None),
+            Pattern::Binding { name, pattern } => {
+                self.bind(&name.value, None);
+                self.bind_pattern(pattern);
+            }
+            Pattern::Tuple(patterns)
+            | Pattern::Variant { patterns, .. }
+            | Pattern::Or(patterns) => patterns.iter().for_each(|p| self.bind_pattern(p)),
+            Pattern::Struct { fields, .. } => fields.iter().for_each(|(_, p)| self.bind_pattern(p)),
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Unused code is allowed here so that the style samples can stay small;
+    /// see `usage` for those rules
+    fn lint(source: &str, style: ReturnStyle) -> Vec<Finding> {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let module = Parser::new(tokens).parse_module().unwrap();
+        Linter::new()
+            .with_return_style(style)
+            .with_allowed(["unused_variable", "unused_function"])
+            .lint_module(&module)
+    }
+
+    /// Findings of the unused-code rules alone
+    fn usage(source: &str) -> Vec<Finding> {
+        let module = crate::parser::parse_source(source).unwrap();
+        Linter::new()
+            .with_allowed(["non_snake_case", "redundant_return"])
+            .lint_module(&module)
+    }
+
+    #[test]
+    fn test_final_return_is_redundant() {
+        let source = "fn id(x: i32) -> i32 {\n    return x;\n}";
+        let findings = lint(source, ReturnStyle::Trailing);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "redundant_return");
+        assert_eq!(&source[findings[0].span.start..findings[0].span.end], "x");
+    }
+
+    #[test]
+    fn test_early_return_is_not_redundant() {
+        let source = "fn f(x: i32) -> i32 {\n    if x > 0 {\n        return 1;\n    }\n    let y = 2;\n    y\n}";
+        assert!(lint(source, ReturnStyle::Trailing).is_empty());
+    }
+
+    fn rules(source: &str) -> Vec<&'static str> {
+        lint(source, ReturnStyle::Trailing)
+            .iter()
+            .map(|f| f.rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_snake_case_rule() {
+        let source = "fn parseLine(lineNo: i32) {\n    let Total = lineNo;\n}";
+        let findings = lint(source, ReturnStyle::Trailing);
+
+        assert_eq!(findings.len(), 3);
+        assert!(findings.iter().all(|f| f.rule == "non_snake_case"));
+        assert_eq!(
+            &source[findings[0].span.start..findings[0].span.end],
+            "parseLine"
+        );
+        assert!(findings[0].message.contains("'parse_line'"));
+        assert!(rules("fn parse_line(line_no: i32) {\n    let _total = line_no;\n}").is_empty());
+    }
+
+    #[test]
+    fn test_pascal_case_rule() {
+        let findings = lint(
+            "struct line_reader { x: i32 }\ntrait Read_all {}",
+            ReturnStyle::Trailing,
+        );
+
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|f| f.rule == "non_pascal_case"));
+        assert!(findings[0].message.contains("'LineReader'"));
+        assert!(findings[1].message.contains("'ReadAll'"));
+        assert!(rules("struct LineReader { x: i32 }\nenum IOError { Closed }").is_empty());
+    }
+
+    #[test]
+    fn test_screaming_case_rule() {
+        let findings = lint("const maxLines: i32 = 10;", ReturnStyle::Trailing);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "non_screaming_case");
+        assert!(findings[0].message.contains("'MAX_LINES'"));
+        assert!(rules("const MAX_LINES: i32 = 10;").is_empty());
+    }
+
+    #[test]
+    fn test_allowed_rules_are_suppressed() {
+        let tokens = Lexer::new("fn parseLine() {}\nconst max: i32 = 1;")
+            .tokenize()
+            .unwrap();
+        let module = Parser::new(tokens).parse_module().unwrap();
+        let findings = Linter::new()
+            .with_allowed(["non_snake_case", "unused_function"])
+            .lint_module(&module);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "non_screaming_case");
+    }
+
+    #[test]
+    fn test_denied_rules_keep_reporting() {
+        let linter = Linter::new().with_level(["non_snake_case"], Level::Deny);
+        assert_eq!(linter.level("non_snake_case"), Level::Deny);
+        assert_eq!(linter.level("non_pascal_case"), Level::Warn);
+        assert!(find_lint("non_snake_case").is_some());
+        assert!(find_lint("unknown_rule").is_none());
+    }
+
+    #[test]
+    fn test_explicit_style_flags_trailing_expression() {
+        let source = "fn id(x: i32) -> i32 {\n    x\n}";
+        let findings = lint(source, ReturnStyle::Explicit);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "implicit_return");
+        assert!(lint(source, ReturnStyle::Trailing).is_empty());
+    }
+
+    #[test]
+    fn test_unused_variable() {
+        let source = "fn main() {\n    let used = 1;\n    let unused = used + 1;\n    let shown = 2;\n    println(\"{shown}\");\n}";
+        let findings = usage(source);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "unused_variable");
+        assert_eq!(
+            &source[findings[0].span.start..findings[0].span.end],
+            "unused"
+        );
+        assert!(usage("fn main() {\n    let _unused = 1;\n}").is_empty());
+    }
+
+    #[test]
+    fn test_shadowed_names_are_not_uses() {
+        // The closure parameter and match binding hide the outer `x`
+        let source = "fn main() {
+    let x = 1;
+    let f = |x: i32| x;
+    let y = match f(2) { x => x };
+    y
+}";
+        let findings = usage(source);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].message,
+            "Variable 'x' is never used; prefix it with an underscore if intended"
+        );
+        assert!(usage(
+            "fn main() {\n    let x = 1;\n    if let Some(y) = f(x) {\n        g(y);\n    }\n}"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_unused_function() {
+        let source = "fn main() {\n    helper();\n}\nfn helper() {}\nfn unused() {\n    unused();\n}\npub fn api() {}";
+        let findings = usage(source);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "unused_function");
+        assert_eq!(
+            &source[findings[0].span.start..findings[0].span.end],
+            "unused"
+        );
+    }
+}
@@ -4,9 +4,18 @@
 // The self-hosting compiler (written in Fruti) is a future goal.
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use fruti_compiler::{CodeGen, Lexer, Parser as FrutiParser, TypeChecker};
+use clap::{Parser, Subcommand, ValueEnum};
+use fruti_compiler::desugar;
+use fruti_compiler::formatter::format_module;
+use fruti_compiler::lexer;
+use fruti_compiler::lint::{self, Finding, Level, ReturnStyle};
+use fruti_compiler::optimize;
+use fruti_compiler::{
+    parse_source, CodeGen, Item, Lexer, Linter, Module, OutputFormat, Palette,
+    Parser as FrutiParser, TypeChecker,
+};
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
@@ -16,20 +25,28 @@ use std::path::{Path, PathBuf};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// When to color diagnostics
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    color: ColorChoice,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Build a Fruti project
     Build {
-        /// Input source file
-        #[arg(value_name = "FILE")]
-        input: PathBuf,
+        /// Input source files
+        #[arg(value_name = "FILE", required = true)]
+        inputs: Vec<PathBuf>,
 
-        /// Output file path
+        /// Output file path (an output directory when building several files)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
+        /// Stop at the first file that fails to build
+        #[arg(long)]
+        fail_fast: bool,
+
         /// Optimization level (0-3)
         #[arg(short = 'O', long, default_value = "0")]
         opt_level: u8,
@@ -41,6 +58,11 @@ enum Commands {
         /// Target platform
         #[arg(long, default_value = "native")]
         target: String,
+
+        /// Outputs to write (comma-separated); IR is written to --output by
+        /// default, and the others next to it or the input
+        #[arg(long, value_enum, value_delimiter = ',')]
+        emit: Vec<EmitKind>,
     },
 
     /// Run a Fruti program
@@ -59,6 +81,14 @@ enum Commands {
         /// Input source file
         #[arg(value_name = "FILE")]
         input: PathBuf,
+
+        /// Lint rules to silence (e.g. non_snake_case)
+        #[arg(long, value_name = "RULE", value_delimiter = ',')]
+        allow: Vec<String>,
+
+        /// Lint rules whose findings fail the check
+        #[arg(long, value_name = "RULE", value_delimiter = ',')]
+        deny: Vec<String>,
     },
 
     /// Format Fruti source code
@@ -77,8 +107,23 @@ enum Commands {
         /// Input source file or directory
         #[arg(value_name = "PATH")]
         path: PathBuf,
+
+        /// Preferred way to return from the end of a function
+        #[arg(long, value_enum, default_value = "trailing")]
+        return_style: ReturnStyleArg,
+
+        /// Lint rules to silence (e.g. non_snake_case)
+        #[arg(long, value_name = "RULE", value_delimiter = ',')]
+        allow: Vec<String>,
+
+        /// Lint rules whose findings fail the lint
+        #[arg(long, value_name = "RULE", value_delimiter = ',')]
+        deny: Vec<String>,
     },
 
+    /// List the lint rules and what they check
+    Lints,
+
     /// Run tests
     Test {
         /// Input source file or directory
@@ -92,12 +137,100 @@ enum Commands {
         #[arg(value_name = "NAME")]
         name: String,
 
-        /// Project type (binary, library)
-        #[arg(long, default_value = "binary")]
-        project_type: String,
+        /// Project type
+        #[arg(long, value_enum, default_value = "binary")]
+        project_type: ProjectType,
+    },
+
+    /// Parse a file repeatedly and report parser throughput
+    #[command(hide = true)]
+    BenchParse {
+        /// Input source file
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+
+        /// Number of times to parse the file
+        #[arg(short = 'n', long, default_value = "100")]
+        iterations: u32,
     },
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EmitKind {
+    /// Textual LLVM IR (`.ll`), the default
+    LlvmIr,
+    /// Target assembly (`.s`), via `llc`
+    Asm,
+    /// Object file (`.o`), via `llc`
+    Obj,
+    /// Makefile-style `.d` file listing the source files the build reads
+    DepInfo,
+}
+
+impl EmitKind {
+    /// The code generator output this names, if any
+    fn format(self) -> Option<OutputFormat> {
+        match self {
+            EmitKind::LlvmIr => Some(OutputFormat::LlvmIr),
+            EmitKind::Asm => Some(OutputFormat::Asm),
+            EmitKind::Obj => Some(OutputFormat::Obj),
+            EmitKind::DepInfo => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ProjectType {
+    /// A program with `src/main.fruti`
+    Binary,
+    /// A library with `src/lib.fruti`
+    Library,
+}
+
+impl ProjectType {
+    fn name(self) -> &'static str {
+        match self {
+            ProjectType::Binary => "binary",
+            ProjectType::Library => "library",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorChoice {
+    /// Color when stdout is a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn palette(self) -> Palette {
+        Palette::new(match self {
+            ColorChoice::Auto => std::io::stdout().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        })
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReturnStyleArg {
+    /// End functions with a trailing expression
+    Trailing,
+    /// End functions with an explicit `return`
+    Explicit,
+}
+
+impl From<ReturnStyleArg> for ReturnStyle {
+    fn from(arg: ReturnStyleArg) -> Self {
+        match arg {
+            ReturnStyleArg::Trailing => ReturnStyle::Trailing,
+            ReturnStyleArg::Explicit => ReturnStyle::Explicit,
+        }
+    }
+}
+
 fn main() -> Result<()> {
     // Initialize logging
     tracing_subscriber::fmt()
@@ -108,47 +241,58 @@ fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
+    let palette = cli.color.palette();
 
     match cli.command {
         Commands::Build {
-            input,
+            inputs,
             output,
+            fail_fast,
             opt_level,
             release,
             target,
+            emit,
         } => {
-            compile_file(&input, output.as_deref(), opt_level, release, &target)?;
+            let options = BuildOptions {
+                opt_level,
+                release,
+                target,
+                emit,
+                palette,
+            };
+            build_files(&inputs, output.as_deref(), fail_fast, &options)?;
         }
 
         Commands::Run { input, args } => {
-            println!("Running {:?}...", input);
-            if !args.is_empty() {
-                println!("  Args: {:?}", args);
-            }
-
-            // TODO: Compile and execute
-            println!("\n[TODO] Run command in progress (Phase 1: building lexer first)");
+            let code = run_file(&input, &args, palette)?;
+            std::process::exit(code);
         }
 
-        Commands::Check { input } => {
-            check_file(&input)?;
+        Commands::Check { input, allow, deny } => {
+            let linter = configure_linter(Linter::new(), allow, deny)?;
+            check_file(&input, palette, linter)?;
         }
 
         Commands::Fmt { path, check } => {
-            println!("Formatting {:?}...", path);
-            if check {
-                println!("  Mode: Check only");
-            }
-
-            // TODO: Implement formatter
-            println!("\n[TODO] Formatter planned for Phase 2");
+            format_path(&path, check)?;
         }
 
-        Commands::Lint { path } => {
-            println!("Linting {:?}...", path);
+        Commands::Lint {
+            path,
+            return_style,
+            allow,
+            deny,
+        } => {
+            let linter = Linter::new().with_return_style(return_style.into());
+            let linter = configure_linter(linter, allow, deny)?;
+            lint_path(&path, linter, palette)?;
+        }
 
-            // TODO: Implement linter
-            println!("\n[TODO] Linter planned for Phase 2");
+        Commands::Lints => {
+            let width = lint::LINTS.iter().map(|l| l.name.len()).max().unwrap_or(0);
+            for rule in lint::LINTS {
+                println!("{:width$}  {}", rule.name, rule.description, width = width);
+            }
         }
 
         Commands::Test { path } => {
@@ -162,23 +306,83 @@ fn main() -> Result<()> {
         }
 
         Commands::New { name, project_type } => {
-            println!("Creating new {} project: {}", project_type, name);
+            new_project(Path::new(&name), project_type)?;
+        }
 
-            // TODO: Implement project scaffolding
-            println!("\n[TODO] Project creation planned for Phase 2");
+        Commands::BenchParse { input, iterations } => {
+            bench_parse(&input, iterations)?;
         }
     }
 
     Ok(())
 }
 
-fn compile_file(
-    input: &PathBuf,
-    output: Option<&Path>,
+/// Settings shared by every file in a `build` invocation
+struct BuildOptions {
     opt_level: u8,
     release: bool,
-    target: &str,
+    target: String,
+    emit: Vec<EmitKind>,
+    palette: Palette,
+}
+
+/// Build each input in turn, continuing past failures unless `fail_fast` is set
+fn build_files(
+    inputs: &[PathBuf],
+    output: Option<&Path>,
+    fail_fast: bool,
+    options: &BuildOptions,
 ) -> Result<()> {
+    // A single input keeps its original error
+    if let [input] = inputs {
+        return compile_file(input, output, options);
+    }
+
+    let mut failed = Vec::new();
+    for input in inputs {
+        // With several inputs, `--output` names a directory for per-file artifacts
+        let file_output =
+            output.map(|dir| dir.join(input.file_stem().unwrap_or_else(|| input.as_os_str())));
+
+        match compile_file(input, file_output.as_deref(), options) {
+            Ok(()) => println!("\n[OK] Built {:?}\n", input),
+            Err(e) => {
+                eprintln!(
+                    "\n{} Failed to build {:?}: {:#}\n",
+                    options.palette.error("[ERROR]"),
+                    input,
+                    e
+                );
+                failed.push(input);
+                if fail_fast {
+                    break;
+                }
+            }
+        }
+    }
+
+    println!(
+        "[BUILD] {} of {} file(s) built successfully",
+        inputs.len() - failed.len(),
+        inputs.len()
+    );
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!("{} file(s) failed to build: {:?}", failed.len(), failed)
+    }
+}
+
+fn compile_file(input: &Path, output: Option<&Path>, options: &BuildOptions) -> Result<()> {
+    let BuildOptions {
+        opt_level,
+        release,
+        ref target,
+        ref emit,
+        palette,
+    } = *options;
+
     println!("[BUILD] Building {:?}...", input);
     println!("  Target: {}", target);
     println!("  Optimization: {}", if release { 3 } else { opt_level });
@@ -203,13 +407,15 @@ fn compile_file(
         for (i, token) in tokens.iter().enumerate() {
             println!("    {:3}: {:?}", i, token.value);
         }
+        println!("\n  As source (a line break marks each inserted semicolon):");
+        print!("{}", lexer::tokens_to_source(&tokens));
     }
 
     println!("\n[Phase 2] Parsing");
 
     // Parse
     let mut parser = FrutiParser::new(tokens);
-    let ast = parser
+    let mut ast = parser
         .parse_module()
         .with_context(|| format!("Failed to parse file: {:?}", input))?;
 
@@ -230,11 +436,15 @@ fn compile_file(
             println!("  [OK] Type checking passed");
         }
         Err(e) => {
-            println!("  [ERROR] Type checking failed: {}", e);
+            println!("  {} Type checking failed: {}", palette.error("[ERROR]"), e);
             return Err(e.into());
         }
     }
 
+    // Lower `?` and other sugar into core forms
+    desugar::desugar_module(&mut ast);
+    optimize::optimize_module(&mut ast, if release { 3 } else { opt_level });
+
     println!("\n[Phase 4] LLVM IR Code Generation");
 
     // Generate LLVM IR
@@ -251,13 +461,31 @@ fn compile_file(
         .with_context(|| format!("Failed to generate IR for file: {:?}", input))?;
 
     println!("  [OK] Generated LLVM IR ({} bytes)", ir.len());
+    for warning in codegen.warnings() {
+        println!(
+            "  {} {} at {}",
+            palette.warning("[WARN]"),
+            warning.value,
+            warning.span
+        );
+    }
+    report_skipped(&codegen, palette);
 
-    // Write IR to file if output specified
-    if let Some(out) = output {
-        let ir_path = out.with_extension("ll");
-        fs::write(&ir_path, &ir)
-            .with_context(|| format!("Failed to write IR file: {:?}", ir_path))?;
-        println!("  [OK] Written IR to {:?}", ir_path);
+    // Without --emit, IR is written if there is somewhere to put it
+    let mut formats: Vec<OutputFormat> = emit.iter().filter_map(|kind| kind.format()).collect();
+    if formats.is_empty() && output.is_some() {
+        formats.push(OutputFormat::LlvmIr);
+    }
+    let target = output.map_or_else(|| input.with_extension(""), Path::to_path_buf);
+    for format in formats {
+        let path = target.with_extension(format.extension());
+        codegen.emit(&ir, format, &path)?;
+        let name = match format {
+            OutputFormat::LlvmIr => "IR",
+            OutputFormat::Asm => "assembly",
+            OutputFormat::Obj => "object file",
+        };
+        println!("  [OK] Written {} to {:?}", name, path);
     }
 
     // Display IR if verbose
@@ -266,7 +494,11 @@ fn compile_file(
         println!("{}", ir);
     }
 
-    println!("\n[TODO] Object file generation and linking");
+    if emit.contains(&EmitKind::DepInfo) {
+        write_dep_info(input, &ast, output)?;
+    }
+
+    println!("\n[TODO] Linking");
     println!("  Current status: Lexer [OK] | Parser [OK] | Semantic [OK] | Codegen [OK] | Linking [TODO]");
 
     if let Some(out) = output {
@@ -276,7 +508,7 @@ fn compile_file(
     Ok(())
 }
 
-fn check_file(input: &PathBuf) -> Result<()> {
+fn check_file(input: &PathBuf, palette: Palette, mut linter: Linter) -> Result<()> {
     println!("[CHECK] Checking {:?}...", input);
 
     // Read source file
@@ -303,30 +535,37 @@ fn check_file(input: &PathBuf) -> Result<()> {
             tokens
         }
         Err(e) => {
-            eprintln!("  [ERROR] Lexical error: {}", e);
+            eprintln!(
+                "  {} Lexical error\n{}",
+                palette.error("[ERROR]"),
+                e.render_with(&source, palette)
+            );
             return Err(e.into());
         }
     };
 
     // Parse
+    // Report every broken item, not just the first
     let mut parser = FrutiParser::new(tokens);
-    let ast = match parser.parse_module() {
-        Ok(ast) => {
-            println!("  [OK] Parsing passed ({} items)", ast.items.len());
+    let (ast, errors) = parser.parse_module_recover();
+    if let Some(first) = errors.first() {
+        for e in &errors {
+            eprintln!(
+                "  {} Parse error\n{}",
+                palette.error("[ERROR]"),
+                e.render_with(&source, palette)
+            );
+        }
+        return Err(first.clone().into());
+    }
 
-            // Show AST in verbose mode
-            if std::env::var("FRUTI_VERBOSE").is_ok() {
-                println!("\n  AST:");
-                println!("{:#?}", ast);
-            }
+    println!("  [OK] Parsing passed ({} items)", ast.items.len());
 
-            ast
-        }
-        Err(e) => {
-            eprintln!("  [ERROR] Parse error: {}", e);
-            return Err(e.into());
-        }
-    };
+    // Show AST in verbose mode
+    if std::env::var("FRUTI_VERBOSE").is_ok() {
+        println!("\n  AST:");
+        println!("{:#?}", ast);
+    }
 
     // Type check
     let mut type_checker = TypeChecker::new();
@@ -335,11 +574,21 @@ fn check_file(input: &PathBuf) -> Result<()> {
             println!("  [OK] Type checking passed");
         }
         Err(e) => {
-            eprintln!("  [ERROR] Type checking failed: {}", e);
+            eprintln!(
+                "  {} Type checking failed\n{}",
+                palette.error("[ERROR]"),
+                e.render_with(&source, palette)
+            );
             return Err(e.into());
         }
     }
 
+    let findings = linter.lint_module(&ast);
+    let denied = report_findings(&findings, &linter, palette);
+    if denied > 0 {
+        anyhow::bail!("{} denied lint finding(s)", denied);
+    }
+
     // Generate IR (but don't write to file)
     let mut codegen = CodeGen::new(
         input
@@ -352,6 +601,15 @@ fn check_file(input: &PathBuf) -> Result<()> {
     match codegen.generate_module(&ast) {
         Ok(ir) => {
             println!("  [OK] IR generation passed ({} bytes)", ir.len());
+            for warning in codegen.warnings() {
+                println!(
+                    "  {} {} at {}",
+                    palette.warning("[WARN]"),
+                    warning.value,
+                    warning.span
+                );
+            }
+            report_skipped(&codegen, palette);
 
             // Display IR if verbose
             if std::env::var("FRUTI_VERBOSE").is_ok() {
@@ -360,7 +618,7 @@ fn check_file(input: &PathBuf) -> Result<()> {
             }
         }
         Err(e) => {
-            eprintln!("  [ERROR] IR generation failed: {}", e);
+            eprintln!("  {} IR generation failed: {}", palette.error("[ERROR]"), e);
             return Err(e.into());
         }
     }
@@ -369,3 +627,436 @@ fn check_file(input: &PathBuf) -> Result<()> {
 
     Ok(())
 }
+
+/// Compile a file to a temporary executable and run it with `args`,
+/// returning its exit code. Output from the compiler goes to stderr so that
+/// stdout is the program's alone. The executable links against the C
+/// library, which provides `puts` and `printf`.
+fn run_file(input: &Path, args: &[String], palette: Palette) -> Result<i32> {
+    let source =
+        fs::read_to_string(input).with_context(|| format!("Failed to read file: {:?}", input))?;
+
+    let mut ast = match parse_source(&source) {
+        Ok(ast) => ast,
+        Err(e) => {
+            eprintln!(
+                "{} Parsing failed\n{}",
+                palette.error("[ERROR]"),
+                e.render_with(&source, palette)
+            );
+            return Err(e.into());
+        }
+    };
+    if let Err(e) = TypeChecker::new().check_module(&ast) {
+        eprintln!(
+            "{} Type checking failed\n{}",
+            palette.error("[ERROR]"),
+            e.render_with(&source, palette)
+        );
+        return Err(e.into());
+    }
+    desugar::desugar_module(&mut ast);
+    optimize::optimize_module(&mut ast, 0);
+
+    let name = input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("module")
+        .to_string();
+    let mut codegen = CodeGen::new(name.clone());
+    let ir = codegen
+        .generate_module(&ast)
+        .with_context(|| format!("Failed to generate IR for file: {:?}", input))?;
+    // Skipped function bodies are emitted as stubs, so running would give
+    // wrong results rather than fail
+    if !codegen.skipped().is_empty() {
+        eprintln!(
+            "{} Code generation failed; not yet supported:",
+            palette.error("[ERROR]")
+        );
+        for construct in codegen.skipped() {
+            eprintln!("    - {} at {}", construct.value, construct.span);
+        }
+        anyhow::bail!(
+            "Cannot run {:?}: some constructs are not yet codegen'd",
+            input
+        );
+    }
+
+    let dir = std::env::temp_dir().join(format!("fruti-run-{}", std::process::id()));
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let result = link_and_run(&codegen, &ir, &dir.join(name), args);
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+/// Emit `ir` as an object file, link it into `exe` with the system C
+/// compiler, and run it
+fn link_and_run(codegen: &CodeGen, ir: &str, exe: &Path, args: &[String]) -> Result<i32> {
+    let object = exe.with_extension(OutputFormat::Obj.extension());
+    codegen.emit(ir, OutputFormat::Obj, &object)?;
+
+    let output = std::process::Command::new("cc")
+        .arg(&object)
+        .arg("-o")
+        .arg(exe)
+        .output()
+        .context("Failed to run cc; is a C toolchain installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Linking failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+
+    let status = std::process::Command::new(exe)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run {:?}", exe))?;
+    // A program killed by a signal has no exit code
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Summarize constructs that were left out of the generated IR
+fn report_skipped(codegen: &CodeGen, palette: Palette) {
+    let skipped = codegen.skipped();
+    if skipped.is_empty() {
+        return;
+    }
+
+    let noun = if skipped.len() == 1 {
+        "construct"
+    } else {
+        "constructs"
+    };
+    println!(
+        "  {} {} {} not yet codegen'd",
+        palette.warning("[WARN]"),
+        skipped.len(),
+        noun
+    );
+    if std::env::var("FRUTI_VERBOSE").is_ok() {
+        for construct in skipped {
+            println!("    - {} at {}", construct.value, construct.span);
+        }
+    }
+}
+
+/// Files to list as dependencies of a build: the input plus, transitively,
+/// each existing `<name>.fruti` in the input's directory where `name` is the
+/// first segment of an `import`. The build itself only compiles the input.
+fn source_dependencies(input: &Path, ast: &Module) -> Result<Vec<PathBuf>> {
+    let dir = input.parent().unwrap_or_else(|| Path::new(""));
+    let mut deps = vec![input.to_path_buf()];
+    let mut pending = vec![ast.clone()];
+
+    while let Some(module) = pending.pop() {
+        for item in &module.items {
+            let Item::Import(import) = item else {
+                continue;
+            };
+            let Some(first) = import.path.first() else {
+                continue;
+            };
+
+            let path = dir.join(format!("{}.fruti", first.value));
+            if deps.contains(&path) || !path.is_file() {
+                continue;
+            }
+
+            let source = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read file: {:?}", path))?;
+            let imported = parse_source(&source)
+                .with_context(|| format!("Failed to parse file: {:?}", path))?;
+
+            deps.push(path);
+            pending.push(imported);
+        }
+    }
+
+    Ok(deps)
+}
+
+/// Write a Makefile-style `.d` file next to the output (or the input)
+fn write_dep_info(input: &Path, ast: &Module, output: Option<&Path>) -> Result<()> {
+    let deps = source_dependencies(input, ast)?;
+    let target = output.map_or_else(|| input.with_extension(""), Path::to_path_buf);
+    let dep_path = target.with_extension("d");
+
+    let dep_list: Vec<String> = deps.iter().map(|d| d.display().to_string()).collect();
+    let mut contents = format!("{}: {}\n", target.display(), dep_list.join(" "));
+    // Empty rules keep make from failing when a source file is deleted
+    for dep in &dep_list {
+        contents.push_str(&format!("\n{}:\n", dep));
+    }
+
+    fs::write(&dep_path, contents)
+        .with_context(|| format!("Failed to write dep-info file: {:?}", dep_path))?;
+    println!("  [OK] Written dep-info to {:?}", dep_path);
+
+    Ok(())
+}
+
+/// Set the level of each rule named by `--allow` and `--deny`
+fn configure_linter(linter: Linter, allow: Vec<String>, deny: Vec<String>) -> Result<Linter> {
+    for rule in allow.iter().chain(&deny) {
+        if lint::find_lint(rule).is_none() {
+            anyhow::bail!("Unknown lint '{}'; run `fruti lints` to list them", rule);
+        }
+    }
+
+    Ok(linter
+        .with_level(allow, Level::Allow)
+        .with_level(deny, Level::Deny))
+}
+
+/// Print lint findings at their configured level, returning how many were denied
+fn report_findings(findings: &[Finding], linter: &Linter, palette: Palette) -> usize {
+    let mut denied = 0;
+    for finding in findings {
+        let tag = match linter.level(finding.rule) {
+            Level::Deny => {
+                denied += 1;
+                palette.error("[ERROR]")
+            }
+            _ => palette.warning("[WARN]"),
+        };
+        println!(
+            "  {} {}: {} at {}",
+            tag, finding.rule, finding.message, finding.span
+        );
+    }
+    denied
+}
+
+/// Lint a file, or every `.fruti` file directly inside a directory
+fn lint_path(path: &Path, mut linter: Linter, palette: Palette) -> Result<()> {
+    let files = if path.is_dir() {
+        let mut files = fs::read_dir(path)
+            .with_context(|| format!("Failed to read directory: {:?}", path))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "fruti"))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut total = 0;
+    let mut denied = 0;
+    for file in &files {
+        println!("[LINT] Linting {:?}...", file);
+
+        let source =
+            fs::read_to_string(file).with_context(|| format!("Failed to read file: {:?}", file))?;
+        let ast =
+            parse_source(&source).with_context(|| format!("Failed to parse file: {:?}", file))?;
+
+        let findings = linter.lint_module(&ast);
+        denied += report_findings(&findings, &linter, palette);
+        total += findings.len();
+    }
+
+    if denied > 0 {
+        anyhow::bail!("Lint failed: {} of {} finding(s) denied", denied, total);
+    }
+    println!("\n[OK] Lint finished with {} finding(s)", total);
+
+    Ok(())
+}
+
+/// Format a file, or every `.fruti` file under a directory. With `check`,
+/// report the files that would change and fail instead of writing them.
+fn format_path(path: &Path, check: bool) -> Result<()> {
+    let files = if path.is_dir() {
+        fruti_files(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut changed = 0;
+    for file in &files {
+        let source =
+            fs::read_to_string(file).with_context(|| format!("Failed to read file: {:?}", file))?;
+        let mut lexer = Lexer::new(&source);
+        let tokens = lexer
+            .tokenize()
+            .with_context(|| format!("Failed to tokenize file: {:?}", file))?;
+
+        // Only doc comments on items are kept in the AST, so formatting
+        // would delete any others
+        let mut parser = FrutiParser::new(tokens);
+        let ast = parser
+            .parse_module()
+            .with_context(|| format!("Failed to parse file: {:?}", file))?;
+        if !lexer.comments().is_empty() || parser.has_unattached_docs() {
+            println!(
+                "[SKIP] {:?} has comments, which the formatter would drop",
+                file
+            );
+            continue;
+        }
+        let formatted = format_module(&ast);
+        if formatted == source {
+            continue;
+        }
+
+        changed += 1;
+        if check {
+            println!("[DIFF] {:?}", file);
+            print_line_diff(&source, &formatted);
+        } else {
+            fs::write(file, &formatted)
+                .with_context(|| format!("Failed to write file: {:?}", file))?;
+            println!("[OK] Formatted {:?}", file);
+        }
+    }
+
+    if check && changed > 0 {
+        anyhow::bail!("{} of {} file(s) need formatting", changed, files.len());
+    }
+    println!(
+        "\n[OK] {} file(s) checked, {} reformatted",
+        files.len(),
+        changed
+    );
+
+    Ok(())
+}
+
+/// Every `.fruti` file under `dir`, recursively, in path order
+fn fruti_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("Failed to read directory: {:?}", dir))?
+    {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(fruti_files(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "fruti") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Print the lines that differ between `old` and `new`, each run under a
+/// header giving its first line number in `old`
+fn print_line_diff(old: &str, new: &str) {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence table over the lines after each position
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut in_hunk = false;
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            in_hunk = false;
+            continue;
+        }
+        if !in_hunk {
+            println!("  @@ line {} @@", i + 1);
+            in_hunk = true;
+        }
+        if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            println!("  -{}", old[i]);
+            i += 1;
+        } else {
+            println!("  +{}", new[j]);
+            j += 1;
+        }
+    }
+}
+
+/// Create a project directory with a manifest and a starter source file
+fn new_project(dir: &Path, project_type: ProjectType) -> Result<()> {
+    let name = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .with_context(|| format!("Invalid project name: {:?}", dir))?;
+    println!("Creating new {} project: {}", project_type.name(), name);
+
+    if dir.exists() {
+        let mut entries =
+            fs::read_dir(dir).with_context(|| format!("Failed to read directory: {:?}", dir))?;
+        if entries.next().is_some() {
+            anyhow::bail!(
+                "Refusing to create project in non-empty directory {:?}",
+                dir
+            );
+        }
+    }
+
+    let src = dir.join("src");
+    fs::create_dir_all(&src).with_context(|| format!("Failed to create directory: {:?}", src))?;
+
+    let manifest = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\ntype = \"{}\"\n",
+        name,
+        project_type.name()
+    );
+    let (file, source) = match project_type {
+        ProjectType::Binary => (
+            "main.fruti",
+            "fn main() {\n    println(\"Hello, World!\")\n}\n",
+        ),
+        ProjectType::Library => (
+            "lib.fruti",
+            "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n",
+        ),
+    };
+
+    for (path, contents) in [
+        (dir.join("fruti.toml"), manifest.as_str()),
+        (src.join(file), source),
+    ] {
+        fs::write(&path, contents).with_context(|| format!("Failed to write file: {:?}", path))?;
+        println!("  [OK] Created {:?}", path);
+    }
+
+    Ok(())
+}
+
+/// Lex and parse a file `iterations` times and report tokens/sec and nodes/sec
+fn bench_parse(input: &Path, iterations: u32) -> Result<()> {
+    let source =
+        fs::read_to_string(input).with_context(|| format!("Failed to read file: {:?}", input))?;
+    let tokens = Lexer::new(&source)
+        .tokenize()
+        .with_context(|| format!("Failed to tokenize file: {:?}", input))?
+        .len();
+    let nodes = parse_source(&source)
+        .with_context(|| format!("Failed to parse file: {:?}", input))?
+        .node_count();
+
+    let start = std::time::Instant::now();
+    for _ in 0..iterations {
+        std::hint::black_box(parse_source(std::hint::black_box(&source))?);
+    }
+    let secs = start.elapsed().as_secs_f64().max(f64::EPSILON);
+
+    let total = f64::from(iterations);
+    println!(
+        "[BENCH] Parsed {:?} {} times in {:.3}s ({} tokens, {} nodes per parse)",
+        input, iterations, secs, tokens, nodes
+    );
+    println!("  {:.0} tokens/sec", tokens as f64 * total / secs);
+    println!("  {:.0} nodes/sec", nodes as f64 * total / secs);
+
+    Ok(())
+}
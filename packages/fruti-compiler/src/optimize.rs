@@ -0,0 +1,334 @@
+// Optimizer - Fruti Compiler
+//
+// AST rewrites that run after desugaring, gated on the optimization level.
+//
+// At `-O1` and above, an `if` whose condition folds to a constant bool is
+// replaced by the branch that runs:
+//
+//     if true { a } else { b }   =>  { a }
+//     if false { a } else { b }  =>  { b }
+//     if false { a }             =>  {}
+//
+// Conditions are folded by `const_eval`, which only accepts pure
+// expressions, so dropping the condition and the other branch never drops
+// a side effect. Module constants fold to their values unless a local
+// binding of the same name is in scope.
+
+use crate::ast::*;
+use crate::const_eval::{eval_const, ConstEnv};
+use crate::span::Span;
+use std::collections::HashMap;
+
+/// Optimize every function body in a module in place
+pub fn optimize_module(module: &mut Module, opt_level: u8) {
+    if opt_level == 0 {
+        return;
+    }
+    Optimizer::default().items(&mut module.items);
+}
+
+#[derive(Default, Clone)]
+struct Optimizer {
+    consts: HashMap<String, Literal>,
+    locals: Vec<String>, // Bindings in scope, which hide constants of the same name
+}
+
+impl ConstEnv for Optimizer {
+    fn const_value(&self, name: &str) -> Option<Literal> {
+        if self.locals.iter().any(|local| local == name) {
+            return None;
+        }
+        self.consts.get(name).cloned()
+    }
+}
+
+impl Optimizer {
+    fn items(&mut self, items: &mut [Item]) {
+        // Constants that fold are visible to every body at this level
+        for item in items.iter() {
+            if let Item::Const(c) = item {
+                if let Ok(value) = eval_const(&c.value, self) {
+                    self.consts.insert(c.name.value.clone(), value);
+                }
+            }
+        }
+
+        for item in items {
+            match item {
+                Item::Function(func) => self.function(func),
+                Item::Impl(imp) => imp.methods.iter_mut().for_each(|m| self.function(m)),
+                Item::Mod(m) => self.clone().items(&mut m.items),
+                _ => {}
+            }
+        }
+    }
+
+    fn function(&mut self, func: &mut Function) {
+        let scope = self.locals.len();
+        self.locals
+            .extend(func.params.iter().map(|p| p.name.value.clone()));
+        self.block(&mut func.body);
+        self.locals.truncate(scope);
+    }
+
+    fn block(&mut self, block: &mut Block) {
+        let scope = self.locals.len();
+        for stmt in &mut block.stmts {
+            match stmt {
+                Stmt::Let { name, value, .. } => {
+                    if let Some(value) = value {
+                        self.expr(value);
+                    }
+                    self.locals.push(name.value.clone());
+                }
+                Stmt::Expr(expr) => self.expr(expr),
+                Stmt::Return(value) => {
+                    if let Some(value) = value {
+                        self.expr(value);
+                    }
+                }
+                Stmt::Break(_) | Stmt::Continue(_) => {}
+                Stmt::While { condition, body } => {
+                    self.expr(condition);
+                    self.block(body);
+                }
+                Stmt::For { var, iter, body } => {
+                    self.expr(iter);
+                    self.locals.push(var.value.clone());
+                    self.block(body);
+                    self.locals.pop();
+                }
+                Stmt::Loop { body } => self.block(body),
+            }
+        }
+        if let Some(expr) = &mut block.expr {
+            self.expr(expr);
+        }
+        self.locals.truncate(scope);
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match &mut expr.kind {
+            ExprKind::Integer(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::Char(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Byte(_)
+            | ExprKind::ByteString(_)
+            | ExprKind::Ident(_)
+            | ExprKind::Path { .. } => {}
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { expr, .. }
+            | ExprKind::Await(expr)
+            | ExprKind::Try(expr)
+            | ExprKind::Let { expr, .. }
+            | ExprKind::Field { expr, .. }
+            | ExprKind::Cast { expr, .. }
+            | ExprKind::Is { expr, .. } => self.expr(expr),
+            ExprKind::Call { func, args, .. } => {
+                self.expr(func);
+                args.iter_mut().for_each(|arg| self.expr(arg));
+            }
+            ExprKind::MethodCall { receiver, args, .. } => {
+                self.expr(receiver);
+                args.iter_mut().for_each(|arg| self.expr(arg));
+            }
+            ExprKind::Index { expr, index } => {
+                self.expr(expr);
+                self.expr(index);
+            }
+            ExprKind::Range { start, end, .. } => {
+                for bound in [start, end].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            ExprKind::If {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                self.expr(condition);
+                self.block(then_block);
+                if let Some(block) = else_block {
+                    self.block(block);
+                }
+
+                if let Ok(Literal::Bool(taken)) = eval_const(condition, self) {
+                    let branch = if taken {
+                        Some(std::mem::replace(then_block, empty_block(expr.span)))
+                    } else {
+                        else_block.take()
+                    };
+                    expr.kind = ExprKind::Block(branch.unwrap_or_else(|| empty_block(expr.span)));
+                }
+            }
+            ExprKind::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    let scope = self.locals.len();
+                    bindings(&arm.pattern, &mut self.locals);
+                    if let Some(guard) = &mut arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&mut arm.body);
+                    self.locals.truncate(scope);
+                }
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::Tuple(exprs) | ExprKind::Array(exprs) => {
+                exprs.iter_mut().for_each(|e| self.expr(e))
+            }
+            ExprKind::StructLit { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            ExprKind::Lambda { params, body, .. } => {
+                let scope = self.locals.len();
+                self.locals
+                    .extend(params.iter().map(|p| p.name.value.clone()));
+                self.expr(body);
+                self.locals.truncate(scope);
+            }
+        }
+    }
+}
+
+/// Every name a pattern could bind. A bare name may be a unit variant rather
+/// than a binding; treating it as one only means fewer folds.
+fn bindings(pattern: &Pattern, names: &mut Vec<String>) {
+    match pattern {
+        Pattern::Ident(name) => names.push(name.clone()),
+        Pattern::Binding { name, pattern } => {
+            names.push(name.value.clone());
+            bindings(pattern, names);
+        }
+        Pattern::Tuple(patterns) | Pattern::Variant { patterns, .. } | Pattern::Or(patterns) => {
+            patterns.iter().for_each(|p| bindings(p, names))
+        }
+        Pattern::Struct { fields, .. } => fields.iter().for_each(|(_, p)| bindings(p, names)),
+        Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range { .. } => {}
+    }
+}
+
+fn empty_block(span: Span) -> Block {
+    Block {
+        stmts: Vec::new(),
+        expr: None,
+        span,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_source;
+
+    /// The body of `main` after optimizing at `opt_level`
+    fn optimized(source: &str, opt_level: u8) -> Block {
+        let mut module = parse_source(source).unwrap();
+        optimize_module(&mut module, opt_level);
+        module.strip_spans();
+        let Some(Item::Function(main)) = module
+            .items
+            .into_iter()
+            .find(|item| matches!(item, Item::Function(f) if f.name.value == "main"))
+        else {
+            panic!("no main");
+        };
+        main.body
+    }
+
+    /// The single block a folded `let x = if ...` was replaced by
+    fn folded_let(body: &Block) -> &Block {
+        let Stmt::Let {
+            value:
+                Some(Expr {
+                    kind: ExprKind::Block(block),
+                    ..
+                }),
+            ..
+        } = &body.stmts[0]
+        else {
+            panic!("`if` was not folded: {:?}", body.stmts[0]);
+        };
+        block
+    }
+
+    #[test]
+    fn test_if_true_folds_to_then_branch() {
+        let body = optimized("fn main() { let x = if true { 1 } else { 2 }; }", 1);
+        let block = folded_let(&body);
+        assert!(block.stmts.is_empty());
+        assert_eq!(block.expr.as_ref().unwrap().kind, ExprKind::Integer(1));
+    }
+
+    #[test]
+    fn test_if_false_folds_to_else_branch_or_unit() {
+        let body = optimized("fn main() { let x = if not true { 1 } else { 2 }; }", 1);
+        assert_eq!(
+            folded_let(&body).expr.as_ref().unwrap().kind,
+            ExprKind::Integer(2)
+        );
+
+        let body = optimized("fn main() { let x = if 1 > 2 { 1 }; }", 1);
+        let block = folded_let(&body);
+        assert!(block.stmts.is_empty() && block.expr.is_none());
+    }
+
+    #[test]
+    fn test_constants_fold_unless_shadowed() {
+        let source = "const DEBUG: bool = false
+fn main() {
+    let x = if DEBUG { 1 } else { 2 };
+    let y = |DEBUG: bool| if DEBUG { 1 } else { 2 };
+}";
+        let body = optimized(source, 2);
+        assert_eq!(
+            folded_let(&body).expr.as_ref().unwrap().kind,
+            ExprKind::Integer(2)
+        );
+        let Stmt::Let {
+            value: Some(lambda),
+            ..
+        } = &body.stmts[1]
+        else {
+            panic!("expected let");
+        };
+        let ExprKind::Lambda { body, .. } = &lambda.kind else {
+            panic!("expected lambda");
+        };
+        assert!(matches!(body.kind, ExprKind::If { .. }));
+    }
+
+    #[test]
+    fn test_runtime_conditions_and_opt_level_zero_are_untouched() {
+        let body = optimized("fn main() { let x = if f() { 1 } else { 2 }; }", 3);
+        assert!(matches!(
+            &body.stmts[0],
+            Stmt::Let {
+                value: Some(Expr {
+                    kind: ExprKind::If { .. },
+                    ..
+                }),
+                ..
+            }
+        ));
+
+        let body = optimized("fn main() { let x = if true { 1 } else { 2 }; }", 0);
+        assert!(matches!(
+            &body.stmts[0],
+            Stmt::Let {
+                value: Some(Expr {
+                    kind: ExprKind::If { .. },
+                    ..
+                }),
+                ..
+            }
+        ));
+    }
+}
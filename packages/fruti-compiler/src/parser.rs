@@ -5,17 +5,71 @@
 
 use crate::ast::*;
 use crate::error::{Error, ErrorKind, Result};
+use crate::lexer::Lexer;
 use crate::span::{Span, Spanned};
 use crate::token::{Token, TokenKind};
+use std::collections::HashMap;
+
+/// Tokens that can begin a top-level item (after attributes and `pub`)
+const ITEM_STARTS: &[TokenKind] = &[
+    TokenKind::Fn,
+    TokenKind::Async,
+    TokenKind::Struct,
+    TokenKind::Enum,
+    TokenKind::Trait,
+    TokenKind::Impl,
+    TokenKind::Type,
+    TokenKind::Const,
+    TokenKind::Import,
+    TokenKind::Mod,
+];
+
+/// Lex and parse a whole source file, without any of the CLI's per-phase
+/// reporting
+pub fn parse_source(source: &str) -> Result<Module> {
+    let tokens = Lexer::new(source).tokenize()?;
+    Parser::new(tokens).parse_module()
+}
 
 pub struct Parser {
     tokens: Vec<Token>,
     pos: usize,
+    no_struct_literal: bool, // Set while parsing `if`/`while`/`match` heads
+    docs: HashMap<usize, String>, // Doc comment text, keyed by the token it precedes
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, pos: 0 }
+        // Doc comments can appear anywhere, so they are set aside rather
+        // than parsed; items pick up the ones written right before them
+        let mut docs = HashMap::new();
+        let mut lines: Vec<String> = Vec::new();
+        let mut kept = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            match token.value {
+                TokenKind::DocComment(text) => lines.push(text),
+                _ => {
+                    if !lines.is_empty() {
+                        docs.insert(kept.len(), lines.join("\n"));
+                        lines.clear();
+                    }
+                    kept.push(token);
+                }
+            }
+        }
+
+        Parser {
+            tokens: kept,
+            pos: 0,
+            no_struct_literal: false,
+            docs,
+        }
+    }
+
+    /// Whether any doc comments were left without an item to attach to, such
+    /// as ones on struct fields or inside bodies. Meaningful after parsing.
+    pub fn has_unattached_docs(&self) -> bool {
+        !self.docs.is_empty()
     }
 
     /// Parse a complete module
@@ -36,25 +90,114 @@ impl Parser {
         Ok(Module { items })
     }
 
+    /// Parse a module, recovering from errors in individual items so that
+    /// every broken item is reported rather than only the first. Items that
+    /// fail to parse are left out of the returned module.
+    pub fn parse_module_recover(&mut self) -> (Module, Vec<Error>) {
+        let mut items = Vec::new();
+        let mut errors = Vec::new();
+
+        while !self.is_at_end() {
+            while self.eat(&TokenKind::Semicolon) {}
+            if self.is_at_end() {
+                break;
+            }
+
+            let start = self.pos;
+            match self.parse_item() {
+                Ok(item) => items.push(item),
+                Err(e) => {
+                    errors.push(e);
+                    if self.pos == start {
+                        self.advance();
+                    }
+                    self.synchronize(start);
+                }
+            }
+        }
+
+        (Module { items }, errors)
+    }
+
+    /// Skip tokens until the start of the next top-level item, after the
+    /// broken one that began at `item_start`. Braces are counted from there,
+    /// so the rest of its body is skipped whole, even if it contains item
+    /// keywords such as a local `const`.
+    fn synchronize(&mut self, item_start: usize) {
+        let mut depth = 0usize;
+        for token in &self.tokens[item_start..self.pos] {
+            match token.value {
+                TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        while !self.is_at_end() {
+            match self.peek().value {
+                TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightBrace => depth = depth.saturating_sub(1),
+                _ if depth == 0 && self.at_item_start() => return,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Whether the current token can begin a top-level item
+    fn at_item_start(&self) -> bool {
+        match self.peek().value {
+            // `fn(i32) -> i32` is a type, not an item
+            TokenKind::Fn => matches!(
+                self.tokens.get(self.pos + 1).map(|t| &t.value),
+                Some(TokenKind::Ident(_))
+            ),
+            TokenKind::Struct
+            | TokenKind::Enum
+            | TokenKind::Trait
+            | TokenKind::Impl
+            | TokenKind::Type
+            | TokenKind::Const
+            | TokenKind::Import
+            | TokenKind::Mod
+            | TokenKind::Pub
+            | TokenKind::Async
+            | TokenKind::Hash => true,
+            _ => false,
+        }
+    }
+
     /// Parse a top-level item
     fn parse_item(&mut self) -> Result<Item> {
+        let doc = self.docs.remove(&self.pos);
+        let attributes = self.parse_attributes()?;
         let is_pub = self.eat(&TokenKind::Pub);
 
+        if let Some(attr) = attributes.first() {
+            if !matches!(self.peek().value, TokenKind::Fn | TokenKind::Async) {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedToken,
+                    attr.name.span,
+                    "Attributes are only supported on functions",
+                ));
+            }
+        }
+
         match self.peek().value {
-            TokenKind::Fn => {
-                let func = self.parse_function(is_pub)?;
+            TokenKind::Fn | TokenKind::Async => {
+                let func = self.parse_function(doc, attributes, is_pub)?;
                 Ok(Item::Function(func))
             }
             TokenKind::Struct => {
-                let struc = self.parse_struct(is_pub)?;
+                let struc = self.parse_struct(doc, is_pub)?;
                 Ok(Item::Struct(struc))
             }
             TokenKind::Enum => {
-                let enm = self.parse_enum(is_pub)?;
+                let enm = self.parse_enum(doc, is_pub)?;
                 Ok(Item::Enum(enm))
             }
             TokenKind::Trait => {
-                let trt = self.parse_trait(is_pub)?;
+                let trt = self.parse_trait(doc, is_pub)?;
                 Ok(Item::Trait(trt))
             }
             TokenKind::Impl => {
@@ -62,37 +205,118 @@ impl Parser {
                 Ok(Item::Impl(imp))
             }
             TokenKind::Type => {
-                let alias = self.parse_type_alias(is_pub)?;
+                let alias = self.parse_type_alias(doc, is_pub)?;
                 Ok(Item::TypeAlias(alias))
             }
             TokenKind::Const => {
-                let cnst = self.parse_const(is_pub)?;
+                let cnst = self.parse_const(doc, is_pub)?;
                 Ok(Item::Const(cnst))
             }
             TokenKind::Import => {
                 let imp = self.parse_import()?;
                 Ok(Item::Import(imp))
             }
+            TokenKind::Mod => {
+                let module = self.parse_mod(doc, is_pub)?;
+                Ok(Item::Mod(module))
+            }
             _ => {
-                let tok = self.peek();
-                Err(Error::new(
-                    ErrorKind::UnexpectedToken,
-                    tok.span,
-                    format!("Expected item, found {:?}", tok.value),
-                ))
+                self.expect_any(ITEM_STARTS)?;
+                unreachable!("every item starter has a parse arm")
+            }
+        }
+    }
+
+    /// Parse attributes: #[name] or #[name(arg, ...)]
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>> {
+        let mut attributes = Vec::new();
+
+        while self.eat(&TokenKind::Hash) {
+            self.expect(&TokenKind::LeftBracket)?;
+            let name = self.expect_ident()?;
+
+            let mut args = Vec::new();
+            if self.eat(&TokenKind::LeftParen) {
+                while !matches!(self.peek().value, TokenKind::RightParen) {
+                    args.push(self.parse_attribute_arg()?);
+                    if !self.eat(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&TokenKind::RightParen)?;
+            }
+
+            self.expect(&TokenKind::RightBracket)?;
+
+            // An attribute on its own line gets a semicolon from ASI
+            self.eat(&TokenKind::Semicolon);
+
+            attributes.push(Attribute { name, args });
+        }
+
+        Ok(attributes)
+    }
+
+    /// Parse a single attribute argument, keeping its tokens as source text
+    fn parse_attribute_arg(&mut self) -> Result<Spanned<String>> {
+        let start = self.peek().span;
+        let mut end = start;
+        let mut parts = Vec::new();
+        let mut depth = 0usize;
+
+        loop {
+            let tok = self.peek();
+            match tok.value {
+                TokenKind::Comma | TokenKind::RightParen if depth == 0 => break,
+                TokenKind::Eof => {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedToken,
+                        tok.span,
+                        "Unterminated attribute argument",
+                    ))
+                }
+                TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen => depth -= 1,
+                _ => {}
             }
+            parts.push(tok.value.to_string());
+            end = self.advance().span;
         }
+
+        Ok(Spanned::new(parts.join(" "), start.merge(end)))
     }
 
     /// Parse function definition
-    fn parse_function(&mut self, is_pub: bool) -> Result<Function> {
+    fn parse_function(
+        &mut self,
+        doc: Option<String>,
+        attributes: Vec<Attribute>,
+        is_pub: bool,
+    ) -> Result<Function> {
         let is_async = self.eat(&TokenKind::Async);
         self.expect(&TokenKind::Fn)?;
 
         let name = self.expect_ident()?;
-        self.expect(&TokenKind::LeftParen)?;
 
-        let params = self.parse_param_list()?;
+        let mut generics = Vec::new();
+        let mut bounds = Vec::new();
+        if self.eat(&TokenKind::Less) {
+            while !matches!(self.peek().value, TokenKind::Greater) {
+                let param = self.expect_ident()?;
+                generics.push(param.clone());
+                if self.eat(&TokenKind::Colon) {
+                    let traits = self.parse_trait_list()?;
+                    bounds.push(TraitBound { param, traits });
+                }
+                if !self.eat(&TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.expect(&TokenKind::Greater)?;
+        }
+
+        self.expect(&TokenKind::LeftParen)?;
+        let (receiver, params) = self.parse_method_params()?;
         self.expect(&TokenKind::RightParen)?;
 
         let return_type = if self.eat(&TokenKind::Arrow) {
@@ -101,10 +325,28 @@ impl Parser {
             None
         };
 
+        // `where` constraints run up to the opening brace of the body
+        if self.eat(&TokenKind::Where) {
+            while !matches!(self.peek().value, TokenKind::LeftBrace) {
+                let param = self.expect_ident()?;
+                self.expect(&TokenKind::Colon)?;
+                let traits = self.parse_trait_list()?;
+                bounds.push(TraitBound { param, traits });
+                if !self.eat(&TokenKind::Comma) {
+                    break;
+                }
+            }
+        }
+
         let body = self.parse_block()?;
 
         Ok(Function {
+            doc,
+            attributes,
             name,
+            generics,
+            bounds,
+            receiver,
             params,
             return_type,
             body,
@@ -113,6 +355,41 @@ impl Parser {
         })
     }
 
+    /// Parse a method receiver: `self`, `&self`, or `own self`
+    fn parse_receiver(&mut self) -> Result<Option<SelfKind>> {
+        let next = self.tokens.get(self.pos + 1).map(|t| &t.value);
+        let kind = match (&self.peek().value, next) {
+            (TokenKind::SelfLower, _) => SelfKind::Value,
+            (TokenKind::Amp, Some(TokenKind::SelfLower)) => SelfKind::Ref,
+            (TokenKind::Own, Some(TokenKind::SelfLower)) => SelfKind::Own,
+            _ => return Ok(None),
+        };
+        if kind != SelfKind::Value {
+            self.advance();
+        }
+        self.expect(&TokenKind::SelfLower)?;
+        Ok(Some(kind))
+    }
+
+    /// Parse the parameters of a function or trait method. A leading `self`
+    /// makes it a method; other parameters may follow.
+    fn parse_method_params(&mut self) -> Result<(Option<SelfKind>, Vec<Param>)> {
+        let receiver = self.parse_receiver()?;
+        if receiver.is_some() && !matches!(self.peek().value, TokenKind::RightParen) {
+            self.expect(&TokenKind::Comma)?;
+        }
+        Ok((receiver, self.parse_param_list()?))
+    }
+
+    /// Parse `+`-joined trait names: Display + Clone
+    fn parse_trait_list(&mut self) -> Result<Vec<Spanned<String>>> {
+        let mut traits = vec![self.expect_ident()?];
+        while self.eat(&TokenKind::Plus) {
+            traits.push(self.expect_ident()?);
+        }
+        Ok(traits)
+    }
+
     /// Parse parameter list
     fn parse_param_list(&mut self) -> Result<Vec<Param>> {
         let mut params = Vec::new();
@@ -125,8 +402,13 @@ impl Parser {
             let name = self.expect_ident()?;
             self.expect(&TokenKind::Colon)?;
             let ty = self.parse_type()?;
+            let default = if self.eat(&TokenKind::Equal) {
+                Some(self.parse_expr()?)
+            } else {
+                None
+            };
 
-            params.push(Param { name, ty });
+            params.push(Param { name, ty, default });
 
             if !self.eat(&TokenKind::Comma) {
                 break;
@@ -149,6 +431,11 @@ impl Parser {
                 let inner = Box::new(self.parse_type()?);
                 Ok(Type::Own(inner))
             }
+            TokenKind::Impl => {
+                self.advance();
+                let bounds = self.parse_trait_list()?;
+                Ok(Type::ImplTrait(bounds))
+            }
             TokenKind::LeftParen => {
                 self.advance();
                 let mut types = Vec::new();
@@ -165,16 +452,49 @@ impl Parser {
                 self.expect(&TokenKind::RightParen)?;
                 Ok(Type::Tuple(types))
             }
+            TokenKind::Fn => {
+                self.advance();
+                self.expect(&TokenKind::LeftParen)?;
+                let mut params = Vec::new();
+
+                if !matches!(self.peek().value, TokenKind::RightParen) {
+                    loop {
+                        params.push(self.parse_type()?);
+                        if !self.eat(&TokenKind::Comma) {
+                            break;
+                        }
+                    }
+                }
+                self.expect(&TokenKind::RightParen)?;
+
+                // A missing return type is unit, spelled as the empty tuple
+                let return_type = if self.eat(&TokenKind::Arrow) {
+                    self.parse_type()?
+                } else {
+                    Type::Tuple(Vec::new())
+                };
+
+                Ok(Type::Function {
+                    params,
+                    return_type: Box::new(return_type),
+                })
+            }
             TokenKind::LeftBracket => {
                 self.advance();
                 let elem_type = Box::new(self.parse_type()?);
                 let size = if self.eat(&TokenKind::Semicolon) {
-                    if let TokenKind::Integer(n) = self.peek().value {
-                        self.advance();
-                        Some(n as usize)
-                    } else {
-                        None
-                    }
+                    Some(Box::new(self.parse_expr()?))
+                } else if !matches!(self.peek().value, TokenKind::RightBracket) {
+                    let tok = self.peek();
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedToken,
+                        tok.span,
+                        format!(
+                            "Expected `;` or `]` after array element type `{}`, found {}",
+                            elem_type,
+                            tok.value.description()
+                        ),
+                    ));
                 } else {
                     None
                 };
@@ -183,7 +503,13 @@ impl Parser {
             }
             TokenKind::Ident(_) => {
                 let name = self.expect_ident()?;
-                Ok(Type::Simple(name))
+
+                if self.eat(&TokenKind::Less) {
+                    let args = self.parse_type_args(&name.value)?;
+                    Ok(Type::Generic { name, args })
+                } else {
+                    Ok(Type::Simple(name))
+                }
             }
             _ => {
                 let tok = self.peek();
@@ -196,8 +522,56 @@ impl Parser {
         }
     }
 
+    /// Parse type arguments up to and including the closing `>`, just past
+    /// the opening `<`. `owner` names what they belong to in errors.
+    fn parse_type_args(&mut self, owner: &str) -> Result<Vec<Type>> {
+        let mut args = Vec::new();
+        loop {
+            let arg = self.parse_type()?;
+            let more = self.eat(&TokenKind::Comma);
+            let closed = matches!(
+                self.peek().value,
+                TokenKind::Greater | TokenKind::GreaterGreater
+            );
+            if !more && !closed {
+                let tok = self.peek();
+                return Err(Error::new(
+                    ErrorKind::UnexpectedToken,
+                    tok.span,
+                    format!(
+                        "Expected `,` or `>` after type argument `{}` of `{}`, found {}",
+                        arg,
+                        owner,
+                        tok.value.description()
+                    ),
+                ));
+            }
+            args.push(arg);
+            if !more {
+                break;
+            }
+        }
+        self.expect_generic_close()?;
+        Ok(args)
+    }
+
+    /// Expect the `>` closing a generic argument list
+    fn expect_generic_close(&mut self) -> Result<()> {
+        // Nested generics like Option<Option<i32>> lex their closing `>>` as a
+        // single shift token, so split it and leave the second `>` in place
+        if let TokenKind::GreaterGreater = self.peek().value {
+            let span = self.peek().span;
+            self.tokens[self.pos] =
+                Token::new(TokenKind::Greater, Span::new(span.start + 1, span.end));
+            return Ok(());
+        }
+
+        self.expect(&TokenKind::Greater)?;
+        Ok(())
+    }
+
     /// Parse struct definition
-    fn parse_struct(&mut self, is_pub: bool) -> Result<Struct> {
+    fn parse_struct(&mut self, doc: Option<String>, is_pub: bool) -> Result<Struct> {
         self.expect(&TokenKind::Struct)?;
         let name = self.expect_ident()?;
         self.expect(&TokenKind::LeftBrace)?;
@@ -223,6 +597,7 @@ impl Parser {
         self.expect(&TokenKind::RightBrace)?;
 
         Ok(Struct {
+            doc,
             name,
             fields,
             is_pub,
@@ -230,7 +605,7 @@ impl Parser {
     }
 
     /// Parse enum definition
-    fn parse_enum(&mut self, is_pub: bool) -> Result<Enum> {
+    fn parse_enum(&mut self, doc: Option<String>, is_pub: bool) -> Result<Enum> {
         self.expect(&TokenKind::Enum)?;
         let name = self.expect_ident()?;
         self.expect(&TokenKind::LeftBrace)?;
@@ -276,11 +651,33 @@ impl Parser {
                 _ => VariantData::Unit,
             };
 
+            let discriminant = if matches!(self.peek().value, TokenKind::Equal) {
+                if data != VariantData::Unit {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedToken,
+                        self.peek().span,
+                        format!(
+                            "Only unit variants can have a discriminant, but '{}' carries data",
+                            variant_name.value
+                        ),
+                    ));
+                }
+                self.advance();
+                Some(self.parse_expr()?)
+            } else {
+                None
+            };
+
             variants.push(Variant {
                 name: variant_name,
                 data,
+                discriminant,
             });
 
+            // A last variant without a comma ends its line
+            if self.at_inserted_semicolon_before_close() {
+                self.advance();
+            }
             if !self.eat(&TokenKind::Comma) {
                 break;
             }
@@ -289,6 +686,7 @@ impl Parser {
         self.expect(&TokenKind::RightBrace)?;
 
         Ok(Enum {
+            doc,
             name,
             variants,
             is_pub,
@@ -296,9 +694,14 @@ impl Parser {
     }
 
     /// Parse trait definition (simplified)
-    fn parse_trait(&mut self, is_pub: bool) -> Result<Trait> {
+    fn parse_trait(&mut self, doc: Option<String>, is_pub: bool) -> Result<Trait> {
         self.expect(&TokenKind::Trait)?;
         let name = self.expect_ident()?;
+        let supertraits = if self.eat(&TokenKind::Colon) {
+            self.parse_trait_list()?
+        } else {
+            Vec::new()
+        };
         self.expect(&TokenKind::LeftBrace)?;
 
         let mut methods = Vec::new();
@@ -306,7 +709,7 @@ impl Parser {
             self.expect(&TokenKind::Fn)?;
             let method_name = self.expect_ident()?;
             self.expect(&TokenKind::LeftParen)?;
-            let params = self.parse_param_list()?;
+            let (receiver, params) = self.parse_method_params()?;
             self.expect(&TokenKind::RightParen)?;
 
             let return_type = if self.eat(&TokenKind::Arrow) {
@@ -319,6 +722,7 @@ impl Parser {
 
             methods.push(TraitMethod {
                 name: method_name,
+                receiver,
                 params,
                 return_type,
             });
@@ -327,7 +731,9 @@ impl Parser {
         self.expect(&TokenKind::RightBrace)?;
 
         Ok(Trait {
+            doc,
             name,
+            supertraits,
             methods,
             is_pub,
         })
@@ -350,8 +756,16 @@ impl Parser {
         self.expect(&TokenKind::LeftBrace)?;
 
         let mut methods = Vec::new();
-        while !matches!(self.peek().value, TokenKind::RightBrace) {
-            let method = self.parse_function(false)?;
+        loop {
+            // Methods on separate lines are followed by ASI semicolons
+            while self.eat(&TokenKind::Semicolon) {}
+            if matches!(self.peek().value, TokenKind::RightBrace) {
+                break;
+            }
+
+            let doc = self.docs.remove(&self.pos);
+            let attributes = self.parse_attributes()?;
+            let method = self.parse_function(doc, attributes, false)?;
             methods.push(method);
         }
 
@@ -365,18 +779,23 @@ impl Parser {
     }
 
     /// Parse type alias
-    fn parse_type_alias(&mut self, is_pub: bool) -> Result<TypeAlias> {
+    fn parse_type_alias(&mut self, doc: Option<String>, is_pub: bool) -> Result<TypeAlias> {
         self.expect(&TokenKind::Type)?;
         let name = self.expect_ident()?;
         self.expect(&TokenKind::Equal)?;
         let ty = self.parse_type()?;
         self.expect(&TokenKind::Semicolon)?;
 
-        Ok(TypeAlias { name, ty, is_pub })
+        Ok(TypeAlias {
+            doc,
+            name,
+            ty,
+            is_pub,
+        })
     }
 
     /// Parse constant
-    fn parse_const(&mut self, is_pub: bool) -> Result<Const> {
+    fn parse_const(&mut self, doc: Option<String>, is_pub: bool) -> Result<Const> {
         self.expect(&TokenKind::Const)?;
         let name = self.expect_ident()?;
         self.expect(&TokenKind::Colon)?;
@@ -386,6 +805,7 @@ impl Parser {
         self.expect(&TokenKind::Semicolon)?;
 
         Ok(Const {
+            doc,
             name,
             ty,
             value,
@@ -398,20 +818,87 @@ impl Parser {
         self.expect(&TokenKind::Import)?;
 
         let mut path = Vec::new();
+        let mut glob = false;
+        let mut group = Vec::new();
         loop {
             path.push(self.expect_ident()?);
             if !self.eat(&TokenKind::ColonColon) {
                 break;
             }
+            if self.eat(&TokenKind::Star) {
+                glob = true;
+                break;
+            }
+            if self.eat(&TokenKind::LeftBrace) {
+                while !matches!(self.peek().value, TokenKind::RightBrace) {
+                    group.push(self.expect_ident()?);
+                    if !self.eat(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&TokenKind::RightBrace)?;
+                break;
+            }
         }
 
-        self.expect(&TokenKind::Semicolon)?;
+        // Globs and groups bring in many names, so there is nothing to rename
+        let alias = if !glob && group.is_empty() && self.eat(&TokenKind::As) {
+            Some(self.expect_ident()?)
+        } else {
+            None
+        };
+
+        // ASI doesn't fire after `*`, so a glob at the end of a line is
+        // terminated by whatever item comes next, or by the end of its module
+        let glob_ends_here = glob
+            && (self.is_at_end()
+                || self.at_item_start()
+                || matches!(self.peek().value, TokenKind::RightBrace));
+        if !glob_ends_here {
+            self.expect(&TokenKind::Semicolon)?;
+        }
+
+        Ok(Import {
+            path,
+            alias,
+            glob,
+            group,
+        })
+    }
+
+    /// Parse an inline module: mod name { items }
+    fn parse_mod(&mut self, doc: Option<String>, is_pub: bool) -> Result<Mod> {
+        self.expect(&TokenKind::Mod)?;
+        let name = self.expect_ident()?;
+        self.expect(&TokenKind::LeftBrace)?;
 
-        Ok(Import { path })
+        let mut items = Vec::new();
+        loop {
+            while self.eat(&TokenKind::Semicolon) {}
+            if matches!(self.peek().value, TokenKind::RightBrace | TokenKind::Eof) {
+                break;
+            }
+            items.push(self.parse_item()?);
+        }
+        self.expect(&TokenKind::RightBrace)?;
+
+        Ok(Mod {
+            doc,
+            name,
+            items,
+            is_pub,
+        })
     }
 
     /// Parse a block
     fn parse_block(&mut self) -> Result<Block> {
+        let saved = std::mem::replace(&mut self.no_struct_literal, false);
+        let result = self.parse_block_inner();
+        self.no_struct_literal = saved;
+        result
+    }
+
+    fn parse_block_inner(&mut self) -> Result<Block> {
         let start = self.expect(&TokenKind::LeftBrace)?.span;
         let mut stmts = Vec::new();
         let mut expr = None;
@@ -439,16 +926,16 @@ impl Parser {
                     // Not a statement - restore position and parse as trailing expression
                     self.pos = saved_pos;
                     expr = Some(Box::new(self.parse_expr()?));
+                    if self.at_inserted_semicolon_before_close() {
+                        self.advance();
+                    }
                     break;
                 }
             }
         }
 
         let end = self.expect(&TokenKind::RightBrace)?.span;
-        let span = Span {
-            start: start.start,
-            end: end.end,
-        };
+        let span = span_between(start, end);
 
         Ok(Block { stmts, expr, span })
     }
@@ -489,18 +976,18 @@ impl Parser {
                 Ok(Some(Stmt::Return(value)))
             }
             TokenKind::Break => {
-                self.advance();
+                let span = self.advance().span;
                 self.expect(&TokenKind::Semicolon)?;
-                Ok(Some(Stmt::Break))
+                Ok(Some(Stmt::Break(span)))
             }
             TokenKind::Continue => {
-                self.advance();
+                let span = self.advance().span;
                 self.expect(&TokenKind::Semicolon)?;
-                Ok(Some(Stmt::Continue))
+                Ok(Some(Stmt::Continue(span)))
             }
             TokenKind::While => {
                 self.advance();
-                let condition = self.parse_expr()?;
+                let condition = self.parse_expr_no_struct()?;
                 let body = self.parse_block()?;
                 Ok(Some(Stmt::While { condition, body }))
             }
@@ -508,7 +995,7 @@ impl Parser {
                 self.advance();
                 let var = self.expect_ident()?;
                 self.expect(&TokenKind::In)?;
-                let iter = self.parse_expr()?;
+                let iter = self.parse_expr_no_struct()?;
                 let body = self.parse_block()?;
                 Ok(Some(Stmt::For { var, iter, body }))
             }
@@ -527,8 +1014,13 @@ impl Parser {
                 // Try parsing as expression statement
                 let expr = self.parse_expr()?;
 
-                // Control flow expressions don't need semicolons when used as statements
-                if starts_with_control || self.eat(&TokenKind::Semicolon) {
+                // Control flow expressions don't need semicolons when used as statements.
+                // An expression on the last line of a block is its value, even though
+                // ASI put a semicolon after it.
+                if starts_with_control
+                    || (!self.at_inserted_semicolon_before_close()
+                        && self.eat(&TokenKind::Semicolon))
+                {
                     Ok(Some(Stmt::Expr(expr)))
                 } else {
                     // It's a trailing expression, not a statement
@@ -543,6 +1035,15 @@ impl Parser {
         self.parse_binary_expr(0)
     }
 
+    /// Parse an expression followed by a block, e.g. an `if` condition, where
+    /// `x {` must start the block rather than a struct literal
+    fn parse_expr_no_struct(&mut self) -> Result<Expr> {
+        let saved = std::mem::replace(&mut self.no_struct_literal, true);
+        let result = self.parse_expr();
+        self.no_struct_literal = saved;
+        result
+    }
+
     /// Parse binary expression with precedence climbing
     fn parse_binary_expr(&mut self, min_prec: u8) -> Result<Expr> {
         let mut left = self.parse_unary_expr()?;
@@ -569,10 +1070,7 @@ impl Parser {
                     Some(Box::new(self.parse_unary_expr()?))
                 };
 
-                let span = Span {
-                    start: left.span.start,
-                    end: end.as_ref().map(|e| e.span.end).unwrap_or(left.span.end),
-                };
+                let span = span_between(left.span, end.as_ref().map_or(left.span, |e| e.span));
 
                 left = Expr {
                     kind: ExprKind::Range {
@@ -600,10 +1098,7 @@ impl Parser {
 
             let right = self.parse_binary_expr(next_min_prec)?;
 
-            let span = Span {
-                start: left.span.start,
-                end: right.span.end,
-            };
+            let span = span_between(left.span, right.span);
 
             left = Expr {
                 kind: ExprKind::Binary {
@@ -654,10 +1149,7 @@ impl Parser {
             TokenKind::Minus => {
                 let start = self.advance().span;
                 let expr = Box::new(self.parse_unary_expr()?);
-                let span = Span {
-                    start: start.start,
-                    end: expr.span.end,
-                };
+                let span = span_between(start, expr.span);
                 Ok(Expr {
                     kind: ExprKind::Unary {
                         op: UnOp::Neg,
@@ -669,10 +1161,7 @@ impl Parser {
             TokenKind::Not | TokenKind::Bang => {
                 let start = self.advance().span;
                 let expr = Box::new(self.parse_unary_expr()?);
-                let span = Span {
-                    start: start.start,
-                    end: expr.span.end,
-                };
+                let span = span_between(start, expr.span);
                 Ok(Expr {
                     kind: ExprKind::Unary {
                         op: UnOp::Not,
@@ -684,10 +1173,7 @@ impl Parser {
             TokenKind::Tilde => {
                 let start = self.advance().span;
                 let expr = Box::new(self.parse_unary_expr()?);
-                let span = Span {
-                    start: start.start,
-                    end: expr.span.end,
-                };
+                let span = span_between(start, expr.span);
                 Ok(Expr {
                     kind: ExprKind::Unary {
                         op: UnOp::BitNot,
@@ -712,13 +1198,71 @@ impl Parser {
                     let end = self.expect(&TokenKind::RightParen)?.span;
 
                     expr = Expr {
-                        span: Span {
-                            start: expr.span.start,
-                            end: end.end,
+                        span: span_between(expr.span, end),
+                        kind: ExprKind::Call {
+                            func: Box::new(expr),
+                            args,
+                            type_args: Vec::new(),
                         },
+                    };
+                }
+                // Turbofish: parse::<i32>(s). Plain `<` after an expression
+                // is a comparison, so the type arguments need the `::`.
+                TokenKind::ColonColon
+                    if matches!(
+                        self.tokens.get(self.pos + 1).map(|t| &t.value),
+                        Some(TokenKind::Less)
+                    ) =>
+                {
+                    self.advance(); // '::'
+                    self.advance(); // '<'
+                    let owner = match &expr.kind {
+                        ExprKind::Ident(name) => name.clone(),
+                        _ => "call".to_string(),
+                    };
+                    let type_args = self.parse_type_args(&owner)?;
+
+                    // Arguments of a type, as in Vec::<i32>::new, stay on
+                    // the path to the item named after them
+                    if matches!(self.peek().value, TokenKind::ColonColon)
+                        && matches!(
+                            self.tokens.get(self.pos + 1).map(|t| &t.value),
+                            Some(TokenKind::Ident(_))
+                        )
+                    {
+                        let mut path = match expr.kind {
+                            ExprKind::Ident(name) => vec![Spanned::new(name, expr.span)],
+                            ExprKind::Path {
+                                path,
+                                type_args: outer,
+                            } if outer.is_empty() => path,
+                            _ => {
+                                return Err(Error::new(
+                                    ErrorKind::UnexpectedToken,
+                                    self.peek().span,
+                                    "Type arguments can only be given once in a path",
+                                ))
+                            }
+                        };
+                        self.advance(); // '::'
+                        path.push(self.expect_ident()?);
+                        expr = Expr {
+                            span: span_between(expr.span, path[path.len() - 1].span),
+                            kind: ExprKind::Path { path, type_args },
+                        };
+                        continue;
+                    }
+
+                    self.expect(&TokenKind::LeftParen)?;
+                    let args = self.parse_expr_list()?;
+                    let end = self.expect(&TokenKind::RightParen)?.span;
+
+                    expr = Expr {
+                        span: span_between(expr.span, end),
                         kind: ExprKind::Call {
                             func: Box::new(expr),
                             args,
+                            type_args,
                         },
                     };
                 }
@@ -728,10 +1272,7 @@ impl Parser {
                     let end = self.expect(&TokenKind::RightBracket)?.span;
 
                     expr = Expr {
-                        span: Span {
-                            start: expr.span.start,
-                            end: end.end,
-                        },
+                        span: span_between(expr.span, end),
                         kind: ExprKind::Index {
                             expr: Box::new(expr),
                             index,
@@ -749,10 +1290,7 @@ impl Parser {
                         let end = self.expect(&TokenKind::RightParen)?.span;
 
                         expr = Expr {
-                            span: Span {
-                                start: expr.span.start,
-                                end: end.end,
-                            },
+                            span: span_between(expr.span, end),
                             kind: ExprKind::MethodCall {
                                 receiver: Box::new(expr),
                                 method: field,
@@ -761,10 +1299,7 @@ impl Parser {
                         };
                     } else {
                         expr = Expr {
-                            span: Span {
-                                start: expr.span.start,
-                                end: field.span.end,
-                            },
+                            span: span_between(expr.span, field.span),
                             kind: ExprKind::Field {
                                 expr: Box::new(expr),
                                 field,
@@ -775,10 +1310,7 @@ impl Parser {
                 TokenKind::Question => {
                     let end = self.advance().span;
                     expr = Expr {
-                        span: Span {
-                            start: expr.span.start,
-                            end: end.end,
-                        },
+                        span: span_between(expr.span, end),
                         kind: ExprKind::Try(Box::new(expr)),
                     };
                 }
@@ -836,6 +1368,16 @@ impl Parser {
                 let span = self.advance().span;
                 Ok(Expr::new(ExprKind::Char(c), span))
             }
+            TokenKind::Byte(b) => {
+                let b = *b;
+                let span = self.advance().span;
+                Ok(Expr::new(ExprKind::Byte(b), span))
+            }
+            TokenKind::ByteString(bytes) => {
+                let bytes = bytes.clone();
+                let span = self.advance().span;
+                Ok(Expr::new(ExprKind::ByteString(bytes), span))
+            }
             TokenKind::True => {
                 let span = self.advance().span;
                 Ok(Expr::bool(true, span))
@@ -844,20 +1386,45 @@ impl Parser {
                 let span = self.advance().span;
                 Ok(Expr::bool(false, span))
             }
+            TokenKind::SelfLower => {
+                let span = self.advance().span;
+                Ok(Expr::ident("self", span))
+            }
             TokenKind::Ident(_) => {
                 let ident = self.expect_ident()?;
 
+                // Path: Color::Red, shapes::Shape::Circle. A `::` followed by
+                // `<` is a turbofish, left to the postfix parser.
+                let mut path = vec![ident];
+                while matches!(self.peek().value, TokenKind::ColonColon)
+                    && matches!(
+                        self.tokens.get(self.pos + 1).map(|t| &t.value),
+                        Some(TokenKind::Ident(_))
+                    )
+                {
+                    self.advance();
+                    path.push(self.expect_ident()?);
+                }
+                if path.len() > 1 {
+                    let span = span_between(path[0].span, path[path.len() - 1].span);
+                    return Ok(Expr {
+                        span,
+                        kind: ExprKind::Path {
+                            path,
+                            type_args: Vec::new(),
+                        },
+                    });
+                }
+                let ident = path.remove(0);
+
                 // Check for struct literal
-                if matches!(self.peek().value, TokenKind::LeftBrace) {
+                if !self.no_struct_literal && matches!(self.peek().value, TokenKind::LeftBrace) {
                     self.advance();
                     let fields = self.parse_struct_lit_fields()?;
                     let end = self.expect(&TokenKind::RightBrace)?.span;
 
                     Ok(Expr {
-                        span: Span {
-                            start: ident.span.start,
-                            end: end.end,
-                        },
+                        span: span_between(ident.span, end),
                         kind: ExprKind::StructLit {
                             name: ident,
                             fields,
@@ -870,57 +1437,18 @@ impl Parser {
             }
             TokenKind::LeftParen => {
                 let start = self.advance().span;
-
-                // Empty tuple or single expression
-                if matches!(self.peek().value, TokenKind::RightParen) {
-                    let end = self.advance().span;
-                    return Ok(Expr {
-                        span: Span {
-                            start: start.start,
-                            end: end.end,
-                        },
-                        kind: ExprKind::Tuple(vec![]),
-                    });
-                }
-
-                let first_expr = self.parse_expr()?;
-
-                // Check for tuple
-                if self.eat(&TokenKind::Comma) {
-                    let mut exprs = vec![first_expr];
-
-                    if !matches!(self.peek().value, TokenKind::RightParen) {
-                        loop {
-                            exprs.push(self.parse_expr()?);
-                            if !self.eat(&TokenKind::Comma) {
-                                break;
-                            }
-                        }
-                    }
-
-                    let end = self.expect(&TokenKind::RightParen)?.span;
-                    Ok(Expr {
-                        span: Span {
-                            start: start.start,
-                            end: end.end,
-                        },
-                        kind: ExprKind::Tuple(exprs),
-                    })
-                } else {
-                    self.expect(&TokenKind::RightParen)?;
-                    Ok(first_expr)
-                }
-            }
-            TokenKind::LeftBracket => {
-                let start = self.advance().span;
-                let exprs = self.parse_expr_list()?;
-                let end = self.expect(&TokenKind::RightBracket)?.span;
+                let saved = std::mem::replace(&mut self.no_struct_literal, false);
+                let result = self.parse_paren_expr(start);
+                self.no_struct_literal = saved;
+                result
+            }
+            TokenKind::LeftBracket => {
+                let start = self.advance().span;
+                let exprs = self.parse_expr_list()?;
+                let end = self.expect(&TokenKind::RightBracket)?.span;
 
                 Ok(Expr {
-                    span: Span {
-                        start: start.start,
-                        end: end.end,
-                    },
+                    span: span_between(start, end),
                     kind: ExprKind::Array(exprs),
                 })
             }
@@ -934,7 +1462,7 @@ impl Parser {
             }
             TokenKind::If => {
                 self.advance();
-                let condition = Box::new(self.parse_expr()?);
+                let condition = Box::new(self.parse_expr_no_struct()?);
                 let then_block = self.parse_block()?;
                 let else_block = if self.eat(&TokenKind::Else) {
                     Some(self.parse_block()?)
@@ -951,13 +1479,35 @@ impl Parser {
                     },
                 })
             }
+            TokenKind::Let => {
+                // `let pattern = expr` inside an `if` condition; the scrutinee binds
+                // tighter than `&&` so that conditions can be chained after it
+                let start = self.advance().span;
+                let pattern = self.parse_pattern()?;
+                self.expect(&TokenKind::Equal)?;
+                let expr = self.parse_binary_expr(BinOp::And.precedence() + 1)?;
+
+                Ok(Expr {
+                    span: span_between(start, expr.span),
+                    kind: ExprKind::Let {
+                        pattern,
+                        expr: Box::new(expr),
+                    },
+                })
+            }
             TokenKind::Match => {
                 self.advance();
-                let expr = Box::new(self.parse_expr()?);
+                let expr = Box::new(self.parse_expr_no_struct()?);
                 self.expect(&TokenKind::LeftBrace)?;
 
                 let mut arms = Vec::new();
-                while !matches!(self.peek().value, TokenKind::RightBrace) {
+                loop {
+                    // Arms on separate lines pick up semicolons from ASI
+                    while self.eat(&TokenKind::Semicolon) {}
+                    if matches!(self.peek().value, TokenKind::RightBrace) {
+                        break;
+                    }
+
                     let pattern = self.parse_pattern()?;
                     let guard = if self.eat(&TokenKind::If) {
                         Some(self.parse_expr()?)
@@ -974,6 +1524,7 @@ impl Parser {
                     });
 
                     if !self.eat(&TokenKind::Comma) {
+                        while self.eat(&TokenKind::Semicolon) {}
                         break;
                     }
                 }
@@ -989,10 +1540,7 @@ impl Parser {
                 let start = self.advance().span;
                 let expr = Box::new(self.parse_postfix_expr()?);
                 Ok(Expr {
-                    span: Span {
-                        start: start.start,
-                        end: expr.span.end,
-                    },
+                    span: span_between(start, expr.span),
                     kind: ExprKind::Await(expr),
                 })
             }
@@ -1009,7 +1557,11 @@ impl Parser {
                         } else {
                             Type::Infer
                         };
-                        params.push(Param { name, ty });
+                        params.push(Param {
+                            name,
+                            ty,
+                            default: None,
+                        });
 
                         if !self.eat(&TokenKind::Comma) {
                             break;
@@ -1019,6 +1571,12 @@ impl Parser {
 
                 self.expect(&TokenKind::Pipe)?;
 
+                let return_type = if self.eat(&TokenKind::Arrow) {
+                    Some(self.parse_type()?)
+                } else {
+                    None
+                };
+
                 let body = if matches!(self.peek().value, TokenKind::LeftBrace) {
                     let block = self.parse_block()?;
                     Box::new(Expr {
@@ -1030,11 +1588,12 @@ impl Parser {
                 };
 
                 Ok(Expr {
-                    span: Span {
-                        start: start.start,
-                        end: body.span.end,
+                    span: span_between(start, body.span),
+                    kind: ExprKind::Lambda {
+                        params,
+                        return_type,
+                        body,
                     },
-                    kind: ExprKind::Lambda { params, body },
                 })
             }
             _ => Err(Error::new(
@@ -1045,8 +1604,78 @@ impl Parser {
         }
     }
 
-    /// Parse pattern (simplified for MVP)
+    /// Parse a parenthesized expression or tuple after the opening `(`
+    fn parse_paren_expr(&mut self, start: Span) -> Result<Expr> {
+        // Empty tuple or single expression
+        if matches!(self.peek().value, TokenKind::RightParen) {
+            let end = self.advance().span;
+            return Ok(Expr {
+                span: span_between(start, end),
+                kind: ExprKind::Tuple(vec![]),
+            });
+        }
+
+        let first_expr = self.parse_expr()?;
+
+        // Check for tuple
+        if self.eat(&TokenKind::Comma) {
+            let mut exprs = vec![first_expr];
+
+            if !matches!(self.peek().value, TokenKind::RightParen) {
+                loop {
+                    exprs.push(self.parse_expr()?);
+                    if !self.eat(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+            }
+
+            let end = self.expect(&TokenKind::RightParen)?.span;
+            Ok(Expr {
+                span: span_between(start, end),
+                kind: ExprKind::Tuple(exprs),
+            })
+        } else {
+            self.expect(&TokenKind::RightParen)?;
+            Ok(first_expr)
+        }
+    }
+
+    /// Parse a pattern with any `|`-separated alternatives: 1 | 2 | 3.
+    /// Patterns never start an expression, so a `|` here is always an
+    /// alternative and never opens a lambda.
     fn parse_pattern(&mut self) -> Result<Pattern> {
+        // A leading `|` lets long alternatives line up
+        self.eat(&TokenKind::Pipe);
+
+        let mut alternatives = vec![self.parse_single_pattern()?];
+        loop {
+            // An alternative starting the next line follows an ASI semicolon
+            let tok = self.peek();
+            if matches!(tok.value, TokenKind::Semicolon)
+                && tok.span.is_empty()
+                && matches!(
+                    self.tokens.get(self.pos + 1).map(|t| &t.value),
+                    Some(TokenKind::Pipe)
+                )
+            {
+                self.advance();
+            }
+            if !self.eat(&TokenKind::Pipe) {
+                break;
+            }
+            alternatives.push(self.parse_single_pattern()?);
+        }
+
+        if alternatives.len() == 1 {
+            Ok(alternatives.pop().unwrap())
+        } else {
+            Ok(Pattern::Or(alternatives))
+        }
+    }
+
+    /// Parse a pattern without top-level alternatives (simplified for MVP)
+    fn parse_single_pattern(&mut self) -> Result<Pattern> {
         match self.peek().value {
             TokenKind::Ident(ref s) if s == "_" => {
                 self.advance();
@@ -1055,6 +1684,16 @@ impl Parser {
             TokenKind::Ident(_) => {
                 let ident = self.expect_ident()?;
 
+                // Binding pattern: n @ 1..=9. Alternatives need parentheses:
+                // n @ (1 | 2)
+                if self.eat(&TokenKind::At) {
+                    let pattern = self.parse_single_pattern()?;
+                    return Ok(Pattern::Binding {
+                        name: ident,
+                        pattern: Box::new(pattern),
+                    });
+                }
+
                 // Check for variant pattern
                 if matches!(self.peek().value, TokenKind::LeftParen) {
                     self.advance();
@@ -1075,23 +1714,98 @@ impl Parser {
                         name: ident.value,
                         patterns,
                     })
+                } else if self.eat(&TokenKind::LeftBrace) {
+                    // Struct or struct variant pattern: Point { x, y: 0 }
+                    let mut fields = Vec::new();
+                    while !matches!(self.peek().value, TokenKind::RightBrace) {
+                        let field = self.expect_ident()?;
+                        let pattern = if self.eat(&TokenKind::Colon) {
+                            self.parse_pattern()?
+                        } else {
+                            Pattern::Ident(field.value.clone())
+                        };
+                        fields.push((field, pattern));
+                        if !self.eat(&TokenKind::Comma) {
+                            break;
+                        }
+                    }
+                    // A last field on its own line gets a semicolon from ASI
+                    if self.at_inserted_semicolon_before_close() {
+                        self.advance();
+                    }
+                    self.expect(&TokenKind::RightBrace)?;
+
+                    Ok(Pattern::Struct {
+                        name: ident.value,
+                        fields,
+                    })
                 } else {
                     Ok(Pattern::Ident(ident.value))
                 }
             }
             TokenKind::Integer(n) => {
                 self.advance();
-                Ok(Pattern::Literal(Literal::Integer(n)))
+
+                // Range pattern: 1..=9, 0..10
+                let inclusive = match self.peek().value {
+                    TokenKind::DotDotEqual => true,
+                    TokenKind::DotDot => false,
+                    _ => return Ok(Pattern::Literal(Literal::Integer(n))),
+                };
+                self.advance();
+
+                let tok = self.advance().clone();
+                let TokenKind::Integer(end) = tok.value else {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedToken,
+                        tok.span,
+                        format!(
+                            "Expected integer to end range pattern, found {:?}",
+                            tok.value
+                        ),
+                    ));
+                };
+
+                Ok(Pattern::Range {
+                    start: Literal::Integer(n),
+                    end: Literal::Integer(end),
+                    inclusive,
+                })
             }
             TokenKind::String(ref s) => {
                 let s = s.clone();
                 self.advance();
                 Ok(Pattern::Literal(Literal::String(s)))
             }
+            TokenKind::Char(c) => {
+                self.advance();
+                Ok(Pattern::Literal(Literal::Char(c)))
+            }
             TokenKind::True => {
                 self.advance();
                 Ok(Pattern::Literal(Literal::Bool(true)))
             }
+            TokenKind::LeftParen => {
+                // Tuple pattern: (a, b). A single pattern without a trailing
+                // comma is just parenthesized.
+                self.advance();
+                let mut patterns = Vec::new();
+                let mut trailing_comma = false;
+                while !matches!(self.peek().value, TokenKind::RightParen) {
+                    patterns.push(self.parse_pattern()?);
+                    trailing_comma = self.eat(&TokenKind::Comma);
+                    if !trailing_comma {
+                        break;
+                    }
+                }
+                self.expect(&TokenKind::RightParen)?;
+
+                if patterns.len() == 1 && !trailing_comma {
+                    Ok(patterns.pop().unwrap())
+                } else {
+                    Ok(Pattern::Tuple(patterns))
+                }
+            }
             TokenKind::False => {
                 self.advance();
                 Ok(Pattern::Literal(Literal::Bool(false)))
@@ -1157,6 +1871,22 @@ impl Parser {
         &self.tokens[self.pos]
     }
 
+    /// Whether the current token is a semicolon inserted by ASI (zero-width)
+    /// directly before a closing `}`. The expression before it is the
+    /// block's value, so a body ending in `x` on its own line means the same
+    /// as `{ x }` and `redundant_return` can suggest dropping `return`. A
+    /// unit function ignores its body's value, so ending one in a call of
+    /// any type is still fine.
+    fn at_inserted_semicolon_before_close(&self) -> bool {
+        let tok = self.peek();
+        matches!(tok.value, TokenKind::Semicolon)
+            && tok.span.is_empty()
+            && matches!(
+                self.tokens.get(self.pos + 1).map(|t| &t.value),
+                Some(TokenKind::RightBrace)
+            )
+    }
+
     fn advance(&mut self) -> &Token {
         let tok = &self.tokens[self.pos];
         if !matches!(tok.value, TokenKind::Eof) {
@@ -1191,12 +1921,41 @@ impl Parser {
         }
     }
 
+    /// Consume the next token if it is any of `kinds`, otherwise report
+    /// all of them as alternatives
+    fn expect_any(&mut self, kinds: &[TokenKind]) -> Result<&Token> {
+        let tok = self.peek();
+        if kinds
+            .iter()
+            .any(|kind| std::mem::discriminant(&tok.value) == std::mem::discriminant(kind))
+        {
+            return Ok(self.advance());
+        }
+
+        let expected: Vec<String> = kinds.iter().map(TokenKind::description).collect();
+        Err(Error::new(
+            ErrorKind::UnexpectedToken,
+            tok.span,
+            format!(
+                "Expected one of {}, found {}",
+                expected.join(", "),
+                tok.value.description()
+            ),
+        ))
+    }
+
     fn expect_ident(&mut self) -> Result<Spanned<String>> {
         let tok = self.peek();
         if let TokenKind::Ident(name) = &tok.value {
             let name = name.clone();
             let span = self.advance().span;
             Ok(Spanned { value: name, span })
+        } else if tok.value.is_keyword() {
+            Err(Error::new(
+                ErrorKind::UnexpectedToken,
+                tok.span,
+                format!("Cannot use keyword '{}' as an identifier", tok.value),
+            ))
         } else {
             Err(Error::new(
                 ErrorKind::UnexpectedToken,
@@ -1206,3 +1965,777 @@ impl Parser {
         }
     }
 }
+
+/// The span from the start of `first` to the end of `last`. Parsing moves
+/// forward, so `last` never ends before `first` starts.
+fn span_between(first: Span, last: Span) -> Span {
+    debug_assert!(
+        first.start <= last.end,
+        "malformed span: {} ends before {} starts",
+        last,
+        first
+    );
+    Span::new(first.start, last.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse(source: &str) -> Module {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::new(tokens).parse_module().unwrap()
+    }
+
+    /// Extract the value of the first `let` in the first function
+    fn first_let_value(module: &Module) -> &Expr {
+        let Item::Function(func) = &module.items[0] else {
+            panic!("expected function");
+        };
+        match &func.body.stmts[0] {
+            Stmt::Let {
+                value: Some(value), ..
+            } => value,
+            other => panic!("expected let, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lambda_return_type() {
+        let module = parse("fn main() { let f = |x: i32| -> i32 { x + 1 }; }");
+
+        match &first_let_value(&module).kind {
+            ExprKind::Lambda {
+                params,
+                return_type: Some(Type::Simple(ret)),
+                body,
+            } => {
+                assert_eq!(params.len(), 1);
+                assert_eq!(ret.value, "i32");
+                assert!(matches!(body.kind, ExprKind::Block(_)));
+            }
+            other => panic!("expected annotated lambda, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_recover_reports_every_broken_item() {
+        let source = "fn a() {\n    let = 1;\n}\nfn b() {\n    let y = (2;\n}\nfn c() {\n}\n";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let (module, errors) = Parser::new(tokens).parse_module_recover();
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].span.start < errors[1].span.start);
+        assert_eq!(module.items.len(), 1);
+        assert!(matches!(&module.items[0], Item::Function(f) if f.name.value == "c"));
+
+        // Item keywords later in a broken body don't restart parsing there
+        let source = "fn a() {\n    let x = ;\n    const Y: i32 = 1\n}\nfn b() {\n    let y = (2;\n    if y { struct S {} }\n}\nfn c() {\n}\n";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let (module, errors) = Parser::new(tokens).parse_module_recover();
+
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert_eq!(module.items.len(), 1);
+        assert!(matches!(&module.items[0], Item::Function(f) if f.name.value == "c"));
+    }
+
+    /// Regression guard against accidentally quadratic parsing. The input is
+    /// 2,000 small functions, roughly 80,000 tokens; a linear parser handles
+    /// it in well under a second even in debug builds.
+    #[test]
+    fn test_parse_large_program() {
+        let source: String = (0..2000)
+            .map(|i| {
+                format!(
+                    "fn f{i}(a: i32, b: i32) -> i32 {{\n    let c = a * {i} + b;\n    if c > 10 {{\n        return c - 1;\n    }}\n    c\n}}\n"
+                )
+            })
+            .collect();
+
+        let start = std::time::Instant::now();
+        let module = parse_source(&source).unwrap();
+        assert_eq!(module.items.len(), 2000);
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_nested_module() {
+        let module = parse("mod outer {\n    mod inner {\n        fn helper() {}\n    }\n}\n");
+
+        let Item::Mod(outer) = &module.items[0] else {
+            panic!("expected module");
+        };
+        assert_eq!(outer.name.value, "outer");
+        let [Item::Mod(inner)] = outer.items.as_slice() else {
+            panic!("expected one nested module");
+        };
+        assert_eq!(inner.name.value, "inner");
+        assert!(matches!(inner.items.as_slice(), [Item::Function(f)] if f.name.value == "helper"));
+    }
+
+    fn segment(name: &str) -> Spanned<String> {
+        Spanned::new(name.to_string(), Span::empty(0))
+    }
+
+    fn parse_import(source: &str) -> Import {
+        let mut module = parse(source);
+        module.strip_spans();
+        match module.items.pop() {
+            Some(Item::Import(import)) => import,
+            other => panic!("expected import, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_import_item() {
+        assert_eq!(
+            parse_import("import std::io;"),
+            Import {
+                path: vec![segment("std"), segment("io")],
+                alias: None,
+                glob: false,
+                group: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_import_alias() {
+        assert_eq!(
+            parse_import("import std::io as sio;"),
+            Import {
+                path: vec![segment("std"), segment("io")],
+                alias: Some(segment("sio")),
+                glob: false,
+                group: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_import_glob() {
+        assert_eq!(
+            parse_import("import std::collections::*;"),
+            Import {
+                path: vec![segment("std"), segment("collections")],
+                alias: None,
+                glob: true,
+                group: vec![],
+            }
+        );
+
+        // Without a semicolon, at the end of a line or of the file
+        let module = parse("import math::*\nfn main() {}\n");
+        assert!(matches!(&module.items[0], Item::Import(import) if import.glob));
+        assert!(matches!(&module.items[1], Item::Function(f) if f.name.value == "main"));
+        assert!(parse_import("import math::*").glob);
+        let module = parse("mod m {\n    import a::*\n}\n");
+        assert!(matches!(&module.items[0], Item::Mod(m) if m.items.len() == 1));
+
+        // A glob still can't run into something that isn't an item
+        let tokens = Lexer::new("import math::* x").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse_module().is_err());
+    }
+
+    #[test]
+    fn test_import_group() {
+        let import = parse_import("import std::io::{Read, Write, BufReader,};");
+        assert_eq!(import.path, vec![segment("std"), segment("io")]);
+        assert_eq!(
+            import.group,
+            vec![segment("Read"), segment("Write"), segment("BufReader")]
+        );
+    }
+
+    #[test]
+    fn test_trait_supertraits() {
+        let module = parse("trait A: B {}\ntrait C: B + D {}\n");
+        let supertraits = |item: &Item| match item {
+            Item::Trait(t) => t
+                .supertraits
+                .iter()
+                .map(|s| s.value.clone())
+                .collect::<Vec<_>>(),
+            other => panic!("expected trait, found {:?}", other),
+        };
+        assert_eq!(supertraits(&module.items[0]), ["B"]);
+        assert_eq!(supertraits(&module.items[1]), ["B", "D"]);
+    }
+
+    #[test]
+    fn test_bad_item_lists_item_starters() {
+        let tokens = Lexer::new("fn a() {}\nfoo()").tokenize().unwrap();
+        let err = Parser::new(tokens).parse_module().unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::UnexpectedToken);
+        assert_eq!(
+            err.message,
+            "Expected one of `fn`, `async`, `struct`, `enum`, `trait`, `impl`, `type`, \
+             `const`, `import`, `mod`, found identifier `foo`"
+        );
+    }
+
+    /// (param, traits) pairs of the first function's bounds
+    fn bounds_of(module: &Module) -> Vec<(String, Vec<String>)> {
+        let Item::Function(func) = &module.items[0] else {
+            panic!("expected function");
+        };
+        func.bounds
+            .iter()
+            .map(|b| {
+                let traits = b.traits.iter().map(|t| t.value.clone()).collect();
+                (b.param.value.clone(), traits)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generic_bound() {
+        let module = parse("fn show<T: Display>(x: T) { }");
+        assert_eq!(
+            bounds_of(&module),
+            vec![("T".to_string(), vec!["Display".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_generic_bound_with_several_traits() {
+        let module = parse("fn dup<T: Display + Clone, U>(x: T, y: U) { }");
+        let Item::Function(func) = &module.items[0] else {
+            panic!("expected function");
+        };
+        assert_eq!(func.generics.len(), 2);
+        assert_eq!(
+            bounds_of(&module),
+            vec![(
+                "T".to_string(),
+                vec!["Display".to_string(), "Clone".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_where_clause() {
+        let module =
+            parse("fn max<T, U>(a: T, b: U) -> T where T: Ord, U: Into + Clone { return a; }");
+        assert_eq!(
+            bounds_of(&module),
+            vec![
+                ("T".to_string(), vec!["Ord".to_string()]),
+                (
+                    "U".to_string(),
+                    vec!["Into".to_string(), "Clone".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binding_pattern() {
+        let module = parse("fn main() { let d = match x { n @ 1..=9 => n, _ => 0 }; }");
+
+        let ExprKind::Match { arms, .. } = &first_let_value(&module).kind else {
+            panic!("expected match");
+        };
+        match &arms[0].pattern {
+            Pattern::Binding { name, pattern } => {
+                assert_eq!(name.value, "n");
+                assert_eq!(
+                    **pattern,
+                    Pattern::Range {
+                        start: Literal::Integer(1),
+                        end: Literal::Integer(9),
+                        inclusive: true,
+                    }
+                );
+            }
+            other => panic!("expected binding pattern, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_or_and_range_patterns() {
+        let module = parse(
+            "fn main() { let d = match n { 1 | 2 | 3 => 0, 4..=10 => 1, _ => |x: i32| x }; }",
+        );
+
+        let ExprKind::Match { arms, .. } = &first_let_value(&module).kind else {
+            panic!("expected match");
+        };
+        assert_eq!(
+            arms[0].pattern,
+            Pattern::Or(vec![
+                Pattern::Literal(Literal::Integer(1)),
+                Pattern::Literal(Literal::Integer(2)),
+                Pattern::Literal(Literal::Integer(3)),
+            ])
+        );
+        assert_eq!(
+            arms[1].pattern,
+            Pattern::Range {
+                start: Literal::Integer(4),
+                end: Literal::Integer(10),
+                inclusive: true,
+            }
+        );
+        // The `|` after `=>` still opens a lambda
+        assert!(matches!(arms[2].body.kind, ExprKind::Lambda { .. }));
+    }
+
+    #[test]
+    fn test_or_pattern_alternatives_on_separate_lines() {
+        let module = parse("fn main() { let d = match n {\n    1\n    | 2 => 0,\n    _ => 1\n}; }");
+
+        let ExprKind::Match { arms, .. } = &first_let_value(&module).kind else {
+            panic!("expected match");
+        };
+        assert!(matches!(&arms[0].pattern, Pattern::Or(alternatives) if alternatives.len() == 2));
+    }
+
+    #[test]
+    fn test_tuple_pattern() {
+        let module = parse("fn main() { let d = match p { (a, (b, _)) => a, (c) => c }; }");
+
+        let ExprKind::Match { arms, .. } = &first_let_value(&module).kind else {
+            panic!("expected match");
+        };
+        assert_eq!(
+            arms[0].pattern,
+            Pattern::Tuple(vec![
+                Pattern::Ident("a".to_string()),
+                Pattern::Tuple(vec![Pattern::Ident("b".to_string()), Pattern::Wildcard]),
+            ])
+        );
+        // Parentheses around a single pattern only group it
+        assert_eq!(arms[1].pattern, Pattern::Ident("c".to_string()));
+    }
+
+    #[test]
+    fn test_struct_pattern() {
+        let module = parse("fn main() { let d = match p { Point { x, y: (a, b) } => x }; }");
+
+        let ExprKind::Match { arms, .. } = &first_let_value(&module).kind else {
+            panic!("expected match");
+        };
+        let Pattern::Struct { name, fields } = &arms[0].pattern else {
+            panic!("expected struct pattern, found {:?}", arms[0].pattern);
+        };
+        assert_eq!(name, "Point");
+        let names: Vec<_> = fields.iter().map(|(f, _)| f.value.as_str()).collect();
+        assert_eq!(names, ["x", "y"]);
+        assert!(matches!(&fields[1].1, Pattern::Tuple(items) if items.len() == 2));
+    }
+
+    #[test]
+    fn test_enum_discriminants() {
+        let module = parse("enum Color {\n    Red = 1,\n    Green,\n    Blue = 2 * 2\n}");
+
+        let Item::Enum(color) = &module.items[0] else {
+            panic!("expected enum");
+        };
+        let discriminants: Vec<Option<&ExprKind>> = color
+            .variants
+            .iter()
+            .map(|v| v.discriminant.as_ref().map(|e| &e.kind))
+            .collect();
+        assert!(matches!(discriminants[0], Some(ExprKind::Integer(1))));
+        assert!(discriminants[1].is_none());
+        assert!(matches!(
+            discriminants[2],
+            Some(ExprKind::Binary { op: BinOp::Mul, .. })
+        ));
+
+        let err = parse_source("enum Shape { Circle(f64) = 1 }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UnexpectedToken);
+        assert_eq!(
+            err.message,
+            "Only unit variants can have a discriminant, but 'Circle' carries data"
+        );
+    }
+
+    #[test]
+    fn test_struct_variant_pattern() {
+        let module = parse("fn main() { let d = match p { Point { x, y: 0 } => x, _ => 0 }; }");
+
+        let ExprKind::Match { arms, .. } = &first_let_value(&module).kind else {
+            panic!("expected match");
+        };
+        match &arms[0].pattern {
+            Pattern::Struct { name, fields } => {
+                assert_eq!(name, "Point");
+                assert_eq!(fields[0].0.value, "x");
+                assert_eq!(fields[0].1, Pattern::Ident("x".to_string()));
+                assert_eq!(fields[1].0.value, "y");
+                assert_eq!(fields[1].1, Pattern::Literal(Literal::Integer(0)));
+            }
+            other => panic!("expected struct variant pattern, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_impl_trait_types() {
+        let module =
+            parse("fn take(x: impl Display + Clone) {}\nfn make() -> impl Iterator { return 0; }");
+
+        let Item::Function(take) = &module.items[0] else {
+            panic!("expected function");
+        };
+        match &take.params[0].ty {
+            Type::ImplTrait(bounds) => {
+                let names: Vec<_> = bounds.iter().map(|b| b.value.as_str()).collect();
+                assert_eq!(names, ["Display", "Clone"]);
+            }
+            other => panic!("expected impl Trait parameter, found {:?}", other),
+        }
+
+        let Item::Function(make) = &module.items[1] else {
+            panic!("expected function");
+        };
+        assert!(matches!(&make.return_type, Some(Type::ImplTrait(bounds)) if bounds.len() == 1));
+    }
+
+    #[test]
+    fn test_if_let_chain() {
+        let module = parse("fn main() { let b = if let Some(x) = a && x > 0 { x } else { 0 }; }");
+
+        let ExprKind::If { condition, .. } = &first_let_value(&module).kind else {
+            panic!("expected if expression");
+        };
+
+        match &condition.kind {
+            ExprKind::Binary {
+                op: BinOp::And,
+                left,
+                right,
+            } => {
+                assert!(matches!(
+                    &left.kind,
+                    ExprKind::Let { pattern: Pattern::Variant { name, .. }, expr }
+                        if name == "Some" && matches!(expr.kind, ExprKind::Ident(_))
+                ));
+                assert!(matches!(right.kind, ExprKind::Binary { op: BinOp::Gt, .. }));
+            }
+            other => panic!("expected let chain, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_path_expressions() {
+        let module = parse("fn main() { let c = Color::Red; }");
+        match &first_let_value(&module).kind {
+            ExprKind::Path { path, .. } => {
+                let names: Vec<&str> = path.iter().map(|s| s.value.as_str()).collect();
+                assert_eq!(names, ["Color", "Red"]);
+            }
+            other => panic!("expected path, found {:?}", other),
+        }
+
+        let module = parse("fn main() { let s = shapes::Shape::Circle(1.0); }");
+        let ExprKind::Call { func, args, .. } = &first_let_value(&module).kind else {
+            panic!("expected call");
+        };
+        assert_eq!(args.len(), 1);
+        match &func.kind {
+            ExprKind::Path { path, .. } => {
+                let names: Vec<&str> = path.iter().map(|s| s.value.as_str()).collect();
+                assert_eq!(names, ["shapes", "Shape", "Circle"]);
+            }
+            other => panic!("expected path, found {:?}", other),
+        }
+
+        // A turbofish after a path still applies to the call
+        let module = parse("fn main() { let n = num::parse::<i32>(s); }");
+        let ExprKind::Call {
+            func, type_args, ..
+        } = &first_let_value(&module).kind
+        else {
+            panic!("expected call");
+        };
+        assert!(matches!(&func.kind, ExprKind::Path { path, .. } if path.len() == 2));
+        assert_eq!(type_args.len(), 1);
+
+        // Type arguments on a type stay on the path
+        let module = parse("fn main() { let v = Vec::<i32>::new(); }");
+        let ExprKind::Call {
+            func, type_args, ..
+        } = &first_let_value(&module).kind
+        else {
+            panic!("expected call");
+        };
+        assert!(type_args.is_empty());
+        let ExprKind::Path { path, type_args } = &func.kind else {
+            panic!("expected path, found {:?}", func.kind);
+        };
+        let names: Vec<&str> = path.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(names, ["Vec", "new"]);
+        assert!(matches!(&type_args[..], [Type::Simple(name)] if name.value == "i32"));
+
+        // And a plain path has none
+        let module = parse("fn main() { let x = a::b; }");
+        assert!(matches!(
+            &first_let_value(&module).kind,
+            ExprKind::Path { path, type_args } if path.len() == 2 && type_args.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_turbofish_call() {
+        let module = parse("fn main() { let n = parse::<i32>(\"5\"); }");
+
+        match &first_let_value(&module).kind {
+            ExprKind::Call {
+                func,
+                args,
+                type_args,
+            } => {
+                assert!(matches!(&func.kind, ExprKind::Ident(name) if name == "parse"));
+                assert_eq!(args.len(), 1);
+                assert!(matches!(&type_args[..], [Type::Simple(name)] if name.value == "i32"));
+            }
+            other => panic!("expected call, found {:?}", other),
+        }
+
+        let module = parse("fn main() { let v = make::<Option<i32>, bool>(); }");
+        let ExprKind::Call { type_args, .. } = &first_let_value(&module).kind else {
+            panic!("expected call");
+        };
+        assert_eq!(type_args.len(), 2);
+        assert!(matches!(&type_args[0], Type::Generic { name, .. } if name.value == "Option"));
+
+        // Without `::`, `<` stays a comparison
+        let module = parse("fn main() { let b = a < b; }");
+        assert!(matches!(
+            first_let_value(&module).kind,
+            ExprKind::Binary { op: BinOp::Lt, .. }
+        ));
+    }
+
+    #[test]
+    fn test_array_length_expression() {
+        let module = parse("fn fill(buf: [u8; SIZE]) {}");
+
+        let Item::Function(func) = &module.items[0] else {
+            panic!("expected function");
+        };
+        match &func.params[0].ty {
+            Type::Array(elem, Some(size)) => {
+                assert!(matches!(&**elem, Type::Simple(name) if name.value == "u8"));
+                assert!(matches!(&size.kind, ExprKind::Ident(name) if name == "SIZE"));
+            }
+            other => panic!("expected sized array, found {:?}", other),
+        }
+
+        let module = parse("fn sum(a: [i32; 4], b: [i32; 2 * 4]) {}");
+        let Item::Function(func) = &module.items[0] else {
+            panic!("expected function");
+        };
+        assert!(matches!(
+            &func.params[0].ty,
+            Type::Array(_, Some(size)) if matches!(size.kind, ExprKind::Integer(4))
+        ));
+        assert!(matches!(
+            &func.params[1].ty,
+            Type::Array(_, Some(size)) if matches!(size.kind, ExprKind::Binary { op: BinOp::Mul, .. })
+        ));
+    }
+
+    #[test]
+    fn test_last_line_is_trailing_expression() {
+        let module = parse("fn id(x: i32) -> i32 {\n    x\n}\nfn unit(x: i32) {\n    x;\n}");
+
+        let Item::Function(id) = &module.items[0] else {
+            panic!("expected function");
+        };
+        assert!(id.body.stmts.is_empty());
+        assert!(matches!(
+            id.body.expr.as_deref(),
+            Some(Expr {
+                kind: ExprKind::Ident(_),
+                ..
+            })
+        ));
+
+        // An explicit semicolon still makes a statement
+        let Item::Function(unit) = &module.items[1] else {
+            panic!("expected function");
+        };
+        assert_eq!(unit.body.stmts.len(), 1);
+        assert!(unit.body.expr.is_none());
+    }
+
+    #[test]
+    fn test_type_errors_show_the_parsed_type() {
+        let parse_err = |source: &str| {
+            let tokens = Lexer::new(source).tokenize().unwrap();
+            Parser::new(tokens).parse_module().unwrap_err()
+        };
+
+        let err = parse_err("fn f(x: Map<&str i32>) {}");
+        assert_eq!(
+            err.message,
+            "Expected `,` or `>` after type argument `&str` of `Map`, found identifier `i32`"
+        );
+        let err = parse_err("fn f(x: [(i32, bool) 4]) {}");
+        assert_eq!(
+            err.message,
+            "Expected `;` or `]` after array element type `(i32, bool)`, found number `4`"
+        );
+    }
+
+    #[test]
+    fn test_keyword_as_identifier() {
+        let parse_err = |source: &str| {
+            let tokens = Lexer::new(source).tokenize().unwrap();
+            Parser::new(tokens).parse_module().unwrap_err()
+        };
+
+        let err = parse_err("fn main() { let self = 1; }");
+        assert_eq!(err.message, "Cannot use keyword 'self' as an identifier");
+        assert_eq!(err.span, Span::new(16, 20));
+
+        let err = parse_err("fn as() {}");
+        assert_eq!(err.message, "Cannot use keyword 'as' as an identifier");
+    }
+
+    #[test]
+    fn test_impl_on_primitive() {
+        let module = parse(
+            "impl i32 {\n    fn squared(self) -> i32 {\n        self * self\n    }\n\n    fn plus(self, n: i32) -> i32 {\n        self + n\n    }\n}",
+        );
+
+        let Item::Impl(imp) = &module.items[0] else {
+            panic!("expected impl");
+        };
+        assert_eq!(imp.type_name.value, "i32");
+        assert_eq!(imp.methods.len(), 2);
+
+        let squared = &imp.methods[0];
+        assert_eq!(squared.receiver, Some(SelfKind::Value));
+        assert!(squared.params.is_empty());
+        assert!(matches!(
+            squared.body.expr.as_deref(),
+            Some(Expr {
+                kind: ExprKind::Binary { op: BinOp::Mul, .. },
+                ..
+            })
+        ));
+
+        let plus = &imp.methods[1];
+        assert_eq!(plus.receiver, Some(SelfKind::Value));
+        assert_eq!(plus.params.len(), 1);
+    }
+
+    #[test]
+    fn test_method_receivers() {
+        let module = parse(
+            "impl Point {
+    fn area(&self) -> i32 {
+        self.x * self.y
+    }
+
+    fn into_x(own self) -> i32 {
+        self.x
+    }
+
+    fn dist(&self, other: Point, scale: i32) -> i32 {
+        0
+    }
+}",
+        );
+
+        let Item::Impl(imp) = &module.items[0] else {
+            panic!("expected impl");
+        };
+        assert_eq!(imp.methods[0].receiver, Some(SelfKind::Ref));
+        assert!(imp.methods[0].params.is_empty());
+        assert_eq!(imp.methods[1].receiver, Some(SelfKind::Own));
+        assert!(imp.methods[1].params.is_empty());
+
+        let dist = &imp.methods[2];
+        assert_eq!(dist.receiver, Some(SelfKind::Ref));
+        let names: Vec<&str> = dist.params.iter().map(|p| p.name.value.as_str()).collect();
+        assert_eq!(names, ["other", "scale"]);
+
+        // A reference parameter that is not `self` is an ordinary parameter
+        let module = parse("fn show(p: &Point) {}");
+        let Item::Function(show) = &module.items[0] else {
+            panic!("expected function");
+        };
+        assert_eq!(show.receiver, None);
+        assert_eq!(show.params.len(), 1);
+    }
+
+    #[test]
+    fn test_trait_method_receivers() {
+        let module = parse("trait Show {\n    fn display(&self) -> i32\n    fn consume(self, n: i32)\n    fn make() -> i32\n}");
+
+        let Item::Trait(t) = &module.items[0] else {
+            panic!("expected trait");
+        };
+        let receivers: Vec<_> = t.methods.iter().map(|m| m.receiver).collect();
+        assert_eq!(
+            receivers,
+            [Some(SelfKind::Ref), Some(SelfKind::Value), None]
+        );
+        assert_eq!(t.methods[1].params.len(), 1);
+        assert_eq!(t.methods[1].params[0].name.value, "n");
+    }
+
+    #[test]
+    fn test_doc_comments_attach_to_items() {
+        let module = parse(
+            "/// Adds one.\n/// Never overflows.\n#[inline]\npub fn inc(x: i32) -> i32 {\n    /// Not an item\n    x + 1\n}\n\n\
+             /** A point */\nstruct P {\n    /// Ignored on fields\n    x: i32,\n}\n\n\
+             impl P {\n    /// The x coordinate\n    fn get(self) -> i32 {\n        self.x\n    }\n}\n\n\
+             // Plain comment\nconst N: i32 = 1;",
+        );
+
+        assert_eq!(module.items[0].doc(), Some("Adds one.\nNever overflows."));
+        assert_eq!(module.items[1].doc(), Some("A point"));
+        let Item::Impl(imp) = &module.items[2] else {
+            panic!("expected impl");
+        };
+        assert_eq!(imp.methods[0].doc.as_deref(), Some("The x coordinate"));
+        assert_eq!(module.items[3].doc(), None);
+    }
+
+    #[test]
+    fn test_default_parameter_value() {
+        let module = parse("fn greet(name: str, greeting: str = \"Hello\") {}");
+
+        let Item::Function(greet) = &module.items[0] else {
+            panic!("expected function");
+        };
+        assert_eq!(greet.params[0].default, None);
+        assert!(matches!(
+            greet.params[1].default.as_ref().map(|e| &e.kind),
+            Some(ExprKind::String(s)) if s == "Hello"
+        ));
+    }
+
+    #[test]
+    fn test_byte_literals() {
+        let module = parse("fn main() { let b = b'A'; }");
+        assert_eq!(first_let_value(&module).kind, ExprKind::Byte(65));
+
+        let module = parse(r#"fn main() { let s = b"\x00hi"; }"#);
+        assert_eq!(
+            first_let_value(&module).kind,
+            ExprKind::ByteString(vec![0, b'h', b'i'])
+        );
+    }
+
+    #[test]
+    fn test_lambda_without_return_type() {
+        let module = parse("fn main() { let f = |x| x + 1; }");
+
+        assert!(matches!(
+            first_let_value(&module).kind,
+            ExprKind::Lambda {
+                return_type: None,
+                ..
+            }
+        ));
+    }
+}
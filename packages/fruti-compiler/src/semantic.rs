@@ -2,18 +2,23 @@
 //
 // Type checking, name resolution, and semantic validation
 //
-// TODO (Phase 2): When implementing Result<T,E> and Option<T> types,
-// enforce simplified syntax per Language-Design-Decisions.md lines 3690-3697:
+// Result<T,E> and Option<T> are built-in generic enums whose variants are in
+// scope unqualified, per Language-Design-Decisions.md lines 3690-3697:
 // - Use Ok(val) NOT Result::Ok(val)
 // - Use Error(e) NOT Result::Err(e)
 // - Use Some(val) NOT Option::Some(val)
 // - Use None NOT Option::None
 // This is a deliberate design decision to improve on Rust's verbose syntax.
+//
+// `inf` and `nan` are prelude constants of type f64 rather than keywords, so
+// they can be shadowed. `-inf` is a negation like any other and folds in
+// const_eval.
 
 use crate::ast::*;
+use crate::const_eval::{eval_const, ConstEnv};
 use crate::error::{Error, ErrorKind, Result};
-use crate::span::Span;
-use std::collections::HashMap;
+use crate::span::{Span, Spanned};
+use std::collections::{HashMap, HashSet};
 
 /// Built-in primitive types
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -22,10 +27,12 @@ pub enum PrimitiveType {
     I16,
     I32,
     I64,
+    Isize,
     U8,
     U16,
     U32,
     U64,
+    Usize,
     F32,
     F64,
     Bool,
@@ -34,6 +41,30 @@ pub enum PrimitiveType {
     Unit, // ()
 }
 
+impl PrimitiveType {
+    /// Source-level spelling of the type, as used in annotations and casts
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrimitiveType::I8 => "i8",
+            PrimitiveType::I16 => "i16",
+            PrimitiveType::I32 => "i32",
+            PrimitiveType::I64 => "i64",
+            PrimitiveType::Isize => "isize",
+            PrimitiveType::U8 => "u8",
+            PrimitiveType::U16 => "u16",
+            PrimitiveType::U32 => "u32",
+            PrimitiveType::U64 => "u64",
+            PrimitiveType::Usize => "usize",
+            PrimitiveType::F32 => "f32",
+            PrimitiveType::F64 => "f64",
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::Char => "char",
+            PrimitiveType::String => "str",
+            PrimitiveType::Unit => "()",
+        }
+    }
+}
+
 /// Resolved type after semantic analysis
 #[derive(Debug, Clone, PartialEq)]
 pub enum ResolvedType {
@@ -46,34 +77,150 @@ pub enum ResolvedType {
         params: Vec<ResolvedType>,
         return_type: Box<ResolvedType>,
     },
+    Generic {
+        name: String,
+        args: Vec<ResolvedType>,
+    }, // Option<i32>, Result<T, E>
+    TypeVar(String),     // Generic parameter, e.g. T in Option<T>
+    Opaque(Vec<String>), // Known only by its trait bounds: impl Display
     UserDefined(String), // Struct, enum, trait
+    IntLiteral,          // Unsuffixed integer literal: any integer type, i32 unless constrained
+    FloatLiteral,        // Unsuffixed float literal: f32 or f64, f64 unless constrained
     Unknown,             // For type inference
 }
 
+/// How types read in diagnostics: the `Debug` form, except that unsuffixed
+/// literal types are named rather than shown as internal variants
+impl std::fmt::Display for ResolvedType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolvedType::IntLiteral => f.write_str("integer literal"),
+            ResolvedType::FloatLiteral => f.write_str("float literal"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+impl ResolvedType {
+    /// Replace type variables bound in `subst`, leaving unbound ones in place
+    pub fn substitute(&self, subst: &HashMap<String, ResolvedType>) -> ResolvedType {
+        self.map_leaves(&|ty| match ty {
+            ResolvedType::TypeVar(name) => subst.get(name).cloned(),
+            _ => None,
+        })
+    }
+
+    /// Whether a type variable appears anywhere in this type
+    fn mentions_type_var(&self) -> bool {
+        let found = std::cell::Cell::new(false);
+        self.map_leaves(&|ty| {
+            if matches!(ty, ResolvedType::TypeVar(_)) {
+                found.set(true);
+            }
+            None
+        });
+        found.get()
+    }
+
+    /// Rebuild this type, replacing each component `f` maps to something
+    fn map_leaves(&self, f: &impl Fn(&ResolvedType) -> Option<ResolvedType>) -> ResolvedType {
+        if let Some(ty) = f(self) {
+            return ty;
+        }
+        let recurse = |t: &ResolvedType| t.map_leaves(f);
+        match self {
+            ResolvedType::Reference(inner) => ResolvedType::Reference(Box::new(recurse(inner))),
+            ResolvedType::Owned(inner) => ResolvedType::Owned(Box::new(recurse(inner))),
+            ResolvedType::Tuple(types) => ResolvedType::Tuple(types.iter().map(recurse).collect()),
+            ResolvedType::Array(elem, size) => ResolvedType::Array(Box::new(recurse(elem)), *size),
+            ResolvedType::Function {
+                params,
+                return_type,
+            } => ResolvedType::Function {
+                params: params.iter().map(recurse).collect(),
+                return_type: Box::new(recurse(return_type)),
+            },
+            ResolvedType::Generic { name, args } => ResolvedType::Generic {
+                name: name.clone(),
+                args: args.iter().map(recurse).collect(),
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
 /// Symbol kinds
 #[derive(Debug, Clone)]
 pub enum Symbol {
     Variable {
         ty: ResolvedType,
         mutable: bool,
+        initialized: bool, // False for `let x;` until its first assignment
         span: Span,
     },
     Function {
+        generics: Vec<String>, // Type parameters, in declaration order
         params: Vec<ResolvedType>,
+        required: usize, // Leading parameters without a default value
         return_type: ResolvedType,
         span: Span,
     },
     Type {
         kind: TypeKind,
+        path: String, // Module-qualified name, e.g. shapes::Circle
+        span: Span,
+    },
+    Variant {
+        enum_name: String,
+        generics: Vec<String>,      // Type parameters of the enum
+        payload: Vec<ResolvedType>, // May mention the enum's type parameters
+        fields: Vec<String>,        // Field names of a struct variant, in payload order
+        span: Span,
+    },
+    Builtin {
+        builtin: Builtin,
+        span: Span,
+    },
+    Const {
+        ty: ResolvedType,
+        value: Option<Literal>, // Folded value, if the initializer is constant
         span: Span,
     },
 }
 
+/// Prelude functions whose signatures depend on their argument types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    Len, // len(array or str) -> u64
+    Min, // min(a, b) -> same numeric type
+    Max, // max(a, b) -> same numeric type
+    Abs, // abs(x) -> same numeric type
+}
+
+impl Builtin {
+    fn arity(self) -> usize {
+        match self {
+            Builtin::Len | Builtin::Abs => 1,
+            Builtin::Min | Builtin::Max => 2,
+        }
+    }
+}
+
+/// Signature of a trait method
+#[derive(Debug, Clone)]
+pub struct MethodSig {
+    pub receiver: Option<SelfKind>,
+    pub params: Vec<ResolvedType>,
+    pub required: usize, // Leading parameters without a default value
+    pub return_type: ResolvedType,
+}
+
 #[derive(Debug, Clone)]
 pub enum TypeKind {
     Struct,
     Enum,
     Trait,
+    Module,
 }
 
 /// Symbol table with scoping
@@ -87,6 +234,15 @@ impl Default for SymbolTable {
     }
 }
 
+impl ConstEnv for SymbolTable {
+    fn const_value(&self, name: &str) -> Option<Literal> {
+        match self.lookup(name) {
+            Some(Symbol::Const { value, .. }) => value.clone(),
+            _ => None,
+        }
+    }
+}
+
 impl SymbolTable {
     pub fn new() -> Self {
         let mut table = SymbolTable {
@@ -95,6 +251,7 @@ impl SymbolTable {
 
         // Add built-in types
         table.define_builtin_types();
+        table.define_prelude();
 
         table
     }
@@ -103,13 +260,14 @@ impl SymbolTable {
         let builtin_span = Span { start: 0, end: 0 };
 
         for type_name in &[
-            "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64", "bool", "char",
-            "str",
+            "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize", "f32", "f64",
+            "bool", "char", "str",
         ] {
             self.scopes[0].insert(
                 type_name.to_string(),
                 Symbol::Type {
                     kind: TypeKind::Struct, // Treat primitives as built-in "structs"
+                    path: type_name.to_string(),
                     span: builtin_span,
                 },
             );
@@ -119,7 +277,9 @@ impl SymbolTable {
         self.scopes[0].insert(
             "print".to_string(),
             Symbol::Function {
+                generics: Vec::new(),
                 params: vec![ResolvedType::Primitive(PrimitiveType::String)],
+                required: 1,
                 return_type: ResolvedType::Primitive(PrimitiveType::Unit),
                 span: builtin_span,
             },
@@ -128,11 +288,76 @@ impl SymbolTable {
         self.scopes[0].insert(
             "println".to_string(),
             Symbol::Function {
+                generics: Vec::new(),
                 params: vec![ResolvedType::Primitive(PrimitiveType::String)],
+                required: 1,
                 return_type: ResolvedType::Primitive(PrimitiveType::Unit),
                 span: builtin_span,
             },
         );
+
+        // Add built-in generic enums
+        self.define_builtin_enum("Option", &["T"], &[("Some", &["T"]), ("None", &[])]);
+        self.define_builtin_enum("Result", &["T", "E"], &[("Ok", &["T"]), ("Error", &["E"])]);
+    }
+
+    fn define_prelude(&mut self) {
+        let builtin_span = Span { start: 0, end: 0 };
+
+        for (name, builtin) in [
+            ("len", Builtin::Len),
+            ("min", Builtin::Min),
+            ("max", Builtin::Max),
+            ("abs", Builtin::Abs),
+        ] {
+            self.scopes[0].insert(
+                name.to_string(),
+                Symbol::Builtin {
+                    builtin,
+                    span: builtin_span,
+                },
+            );
+        }
+
+        for (name, value) in [("inf", f64::INFINITY), ("nan", f64::NAN)] {
+            self.scopes[0].insert(
+                name.to_string(),
+                Symbol::Const {
+                    ty: ResolvedType::Primitive(PrimitiveType::F64),
+                    value: Some(Literal::Float(value)),
+                    span: builtin_span,
+                },
+            );
+        }
+    }
+
+    fn define_builtin_enum(&mut self, name: &str, generics: &[&str], variants: &[(&str, &[&str])]) {
+        let builtin_span = Span { start: 0, end: 0 };
+
+        self.scopes[0].insert(
+            name.to_string(),
+            Symbol::Type {
+                kind: TypeKind::Enum,
+                path: name.to_string(),
+                span: builtin_span,
+            },
+        );
+
+        for (variant, payload) in variants {
+            self.scopes[0].insert(
+                variant.to_string(),
+                Symbol::Variant {
+                    enum_name: name.to_string(),
+                    generics: generics.iter().map(|g| g.to_string()).collect(),
+                    payload: payload
+                        .iter()
+                        .map(|t| ResolvedType::TypeVar(t.to_string()))
+                        .collect(),
+                    fields: Vec::new(),
+                    span: builtin_span,
+                },
+            );
+        }
     }
 
     pub fn enter_scope(&mut self) {
@@ -145,9 +370,29 @@ impl SymbolTable {
         }
     }
 
+    /// Enter a scope that starts out holding `symbols`
+    pub fn push_scope(&mut self, symbols: HashMap<String, Symbol>) {
+        self.scopes.push(symbols);
+    }
+
+    /// Leave the innermost scope, handing back what was defined in it
+    pub fn take_scope(&mut self) -> HashMap<String, Symbol> {
+        if self.scopes.len() > 1 {
+            self.scopes.pop().unwrap_or_default()
+        } else {
+            HashMap::new()
+        }
+    }
+
+    /// Add a symbol to the innermost scope. A variable may shadow another
+    /// variable there (`let x = 1; let x = x + 1;`); any other clash is an error.
     pub fn define(&mut self, name: String, symbol: Symbol) -> std::result::Result<(), String> {
         if let Some(scope) = self.scopes.last_mut() {
-            if scope.contains_key(&name) {
+            let shadows = matches!(
+                (scope.get(&name), &symbol),
+                (Some(Symbol::Variable { .. }), Symbol::Variable { .. })
+            );
+            if scope.contains_key(&name) && !shadows {
                 return Err(format!(
                     "Symbol '{}' already defined in current scope",
                     name
@@ -168,12 +413,67 @@ impl SymbolTable {
         }
         None
     }
+
+    /// Find a variant by name, looking past anything that shadows it
+    pub fn lookup_variant(&self, name: &str) -> Option<&Symbol> {
+        self.scopes
+            .iter()
+            .rev()
+            .filter_map(|scope| scope.get(name))
+            .find(|symbol| matches!(symbol, Symbol::Variant { .. }))
+    }
+
+    /// Declaration spans of the variables not assigned yet
+    pub fn unassigned(&self) -> HashSet<Span> {
+        self.scopes
+            .iter()
+            .flat_map(|scope| scope.values())
+            .filter_map(|symbol| match symbol {
+                Symbol::Variable {
+                    initialized: false,
+                    span,
+                    ..
+                } => Some(*span),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Mark which of the `tracked` variables are still unassigned
+    pub fn set_unassigned(&mut self, tracked: &HashSet<Span>, unassigned: &HashSet<Span>) {
+        for symbol in self.scopes.iter_mut().flat_map(|scope| scope.values_mut()) {
+            if let Symbol::Variable {
+                initialized, span, ..
+            } = symbol
+            {
+                if tracked.contains(span) {
+                    *initialized = !unassigned.contains(span);
+                }
+            }
+        }
+    }
+
+    pub fn lookup_mut(&mut self, name: &str) -> Option<&mut Symbol> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
 }
 
 /// Type checker
 pub struct TypeChecker {
     symbols: SymbolTable,
     current_function_return: Option<ResolvedType>,
+    traits: HashMap<String, HashMap<String, MethodSig>>, // Trait name -> method signatures
+    supertraits: HashMap<String, Vec<String>>,           // Trait name -> traits it requires
+    trait_impls: HashSet<(String, String)>, // (type, trait) for each `impl Trait for Type`
+    methods: HashMap<String, HashMap<String, MethodSig>>, // Type name -> methods from impls
+    structs: HashMap<String, Vec<(String, ResolvedType)>>, // Struct name -> fields in order
+    modules: HashMap<String, HashMap<String, Symbol>>, // Module path (a::b) -> its items
+    module_path: Vec<String>,               // Modules enclosing the current item
+    uninferred: HashSet<Span>,              // `let x;` bindings awaiting their first assignment
+    loop_exits: Vec<Vec<HashSet<Span>>>, // Per enclosing loop: unassigned variables at each `break`
 }
 
 impl Default for TypeChecker {
@@ -187,16 +487,31 @@ impl TypeChecker {
         TypeChecker {
             symbols: SymbolTable::new(),
             current_function_return: None,
+            traits: HashMap::new(),
+            supertraits: HashMap::new(),
+            trait_impls: HashSet::new(),
+            methods: HashMap::new(),
+            structs: HashMap::new(),
+            modules: HashMap::new(),
+            module_path: Vec::new(),
+            uninferred: HashSet::new(),
+            loop_exits: Vec::new(),
         }
     }
 
     /// Check a module
     pub fn check_module(&mut self, module: &Module) -> Result<()> {
-        // First pass: collect all top-level definitions
+        // Constants first, so that signatures can use them as array lengths
         for item in &module.items {
-            self.collect_item(item)?;
+            if let Item::Const(c) = item {
+                self.collect_const(c)?;
+            }
         }
 
+        // First pass: collect all top-level definitions, then resolve imports
+        // against them so that an import may name a module declared below it
+        self.collect_items(&module.items)?;
+
         // Second pass: type check all items
         for item in &module.items {
             self.check_item(item)?;
@@ -205,14 +520,64 @@ impl TypeChecker {
         Ok(())
     }
 
+    /// Collect a list of items, imports last
+    fn collect_items(&mut self, items: &[Item]) -> Result<()> {
+        let (imports, others): (Vec<&Item>, Vec<&Item>) = items
+            .iter()
+            .partition(|item| matches!(item, Item::Import(_)));
+        for item in others.into_iter().chain(imports) {
+            self.collect_item(item)?;
+        }
+        Ok(())
+    }
+
     /// Collect top-level definitions
     fn collect_item(&mut self, item: &Item) -> Result<()> {
         match item {
+            Item::Mod(m) => {
+                self.module_path.push(m.name.value.clone());
+                self.symbols.enter_scope();
+                let collected = m
+                    .items
+                    .iter()
+                    .filter_map(|item| match item {
+                        Item::Const(c) => Some(c),
+                        _ => None,
+                    })
+                    .try_for_each(|c| self.collect_const(c))
+                    .and_then(|()| self.collect_items(&m.items));
+                let symbols = self.symbols.take_scope();
+                let path = self.module_path.join("::");
+                self.module_path.pop();
+                collected?;
+
+                self.modules.insert(path, symbols);
+                self.symbols
+                    .define(
+                        m.name.value.clone(),
+                        Symbol::Type {
+                            kind: TypeKind::Module,
+                            path: self.qualify(&m.name.value),
+                            span: m.name.span,
+                        },
+                    )
+                    .map_err(|e| Error::new(ErrorKind::SemanticError, m.name.span, e))?;
+            }
+            Item::Import(import) => self.collect_import(import)?,
             Item::Function(func) => {
+                // Argument-position `impl Trait` is an anonymous generic parameter
+                // chosen by the caller; inside the body it is opaque (see check_function)
+                let generics: Vec<String> = func.generics.iter().map(|g| g.value.clone()).collect();
                 let params = func
                     .params
                     .iter()
-                    .map(|p| self.resolve_type(&p.ty))
+                    .map(|p| match &p.ty {
+                        Type::ImplTrait(bounds) => Ok(ResolvedType::TypeVar(format!(
+                            "impl {}",
+                            bound_names(bounds).join(" + ")
+                        ))),
+                        ty => Ok(type_params_to_vars(self.resolve_type(ty)?, &generics)),
+                    })
                     .collect::<Result<Vec<_>>>()?;
 
                 let return_type = func
@@ -220,13 +585,16 @@ impl TypeChecker {
                     .as_ref()
                     .map(|t| self.resolve_type(t))
                     .transpose()?
+                    .map(|t| type_params_to_vars(t, &generics))
                     .unwrap_or(ResolvedType::Primitive(PrimitiveType::Unit));
 
                 self.symbols
                     .define(
                         func.name.value.clone(),
                         Symbol::Function {
+                            generics,
                             params,
+                            required: required_params(&func.params),
                             return_type,
                             span: func.name.span,
                         },
@@ -239,10 +607,18 @@ impl TypeChecker {
                         s.name.value.clone(),
                         Symbol::Type {
                             kind: TypeKind::Struct,
+                            path: self.qualify(&s.name.value),
                             span: s.name.span,
                         },
                     )
                     .map_err(|e| Error::new(ErrorKind::SemanticError, s.name.span, e))?;
+
+                let fields = s
+                    .fields
+                    .iter()
+                    .map(|f| Ok((f.name.value.clone(), self.resolve_type(&f.ty)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                self.structs.insert(self.qualify(&s.name.value), fields);
             }
             Item::Enum(e) => {
                 self.symbols
@@ -250,10 +626,45 @@ impl TypeChecker {
                         e.name.value.clone(),
                         Symbol::Type {
                             kind: TypeKind::Enum,
+                            path: self.qualify(&e.name.value),
                             span: e.name.span,
                         },
                     )
                     .map_err(|err| Error::new(ErrorKind::SemanticError, e.name.span, err))?;
+
+                for variant in &e.variants {
+                    let (payload, fields) = match &variant.data {
+                        VariantData::Unit => (Vec::new(), Vec::new()),
+                        VariantData::Tuple(types) => (
+                            types
+                                .iter()
+                                .map(|t| self.resolve_type(t))
+                                .collect::<Result<Vec<_>>>()?,
+                            Vec::new(),
+                        ),
+                        VariantData::Struct(fields) => (
+                            fields
+                                .iter()
+                                .map(|f| self.resolve_type(&f.ty))
+                                .collect::<Result<Vec<_>>>()?,
+                            fields.iter().map(|f| f.name.value.clone()).collect(),
+                        ),
+                    };
+                    self.symbols
+                        .define(
+                            variant.name.value.clone(),
+                            Symbol::Variant {
+                                enum_name: self.qualify(&e.name.value),
+                                generics: Vec::new(),
+                                payload,
+                                fields,
+                                span: variant.name.span,
+                            },
+                        )
+                        .map_err(|err| {
+                            Error::new(ErrorKind::SemanticError, variant.name.span, err)
+                        })?;
+                }
             }
             Item::Trait(t) => {
                 self.symbols
@@ -261,30 +672,471 @@ impl TypeChecker {
                         t.name.value.clone(),
                         Symbol::Type {
                             kind: TypeKind::Trait,
+                            path: self.qualify(&t.name.value),
                             span: t.name.span,
                         },
                     )
                     .map_err(|err| Error::new(ErrorKind::SemanticError, t.name.span, err))?;
+
+                let mut methods = HashMap::new();
+                for method in &t.methods {
+                    let params = method
+                        .params
+                        .iter()
+                        .map(|p| self.resolve_type(&p.ty))
+                        .collect::<Result<Vec<_>>>()?;
+                    let return_type = method
+                        .return_type
+                        .as_ref()
+                        .map(|t| self.resolve_type(t))
+                        .transpose()?
+                        .unwrap_or(ResolvedType::Primitive(PrimitiveType::Unit));
+
+                    methods.insert(
+                        method.name.value.clone(),
+                        MethodSig {
+                            receiver: method.receiver,
+                            params,
+                            required: required_params(&method.params),
+                            return_type,
+                        },
+                    );
+                }
+                self.traits.insert(t.name.value.clone(), methods);
+                self.supertraits
+                    .insert(t.name.value.clone(), bound_names(&t.supertraits));
+            }
+            Item::Impl(imp) => {
+                let type_key = self.type_key(&imp.type_name.value);
+                if let Some(trait_name) = &imp.trait_name {
+                    self.trait_impls
+                        .insert((type_key.clone(), trait_name.value.clone()));
+                }
+
+                // Methods are keyed by the type's qualified name, so `impl i32`
+                // extends the primitive and same-named types in other modules
+                // keep their own
+                for method in &imp.methods {
+                    let params = method
+                        .params
+                        .iter()
+                        .map(|p| self.resolve_type(&p.ty))
+                        .collect::<Result<Vec<_>>>()?;
+                    let return_type = method
+                        .return_type
+                        .as_ref()
+                        .map(|t| self.resolve_type(t))
+                        .transpose()?
+                        .unwrap_or(ResolvedType::Primitive(PrimitiveType::Unit));
+
+                    let methods = self.methods.entry(type_key.clone()).or_default();
+                    if methods.contains_key(&method.name.value) {
+                        return Err(Error::new(
+                            ErrorKind::SemanticError,
+                            method.name.span,
+                            format!(
+                                "Method '{}' is already defined for '{}'",
+                                method.name.value, imp.type_name.value
+                            ),
+                        ));
+                    }
+                    methods.insert(
+                        method.name.value.clone(),
+                        MethodSig {
+                            receiver: method.receiver,
+                            params,
+                            required: required_params(&method.params),
+                            return_type,
+                        },
+                    );
+                }
             }
             _ => {}
         }
         Ok(())
     }
 
+    /// Bring an imported module item into the current scope under its alias
+    /// or last path segment, every item of a module for a glob, or each
+    /// name of a `{...}` group. Paths that
+    /// don't start at a module in this file are left to the driver, which
+    /// loads them from sibling files.
+    fn collect_import(&mut self, import: &Import) -> Result<()> {
+        if !import.group.is_empty() {
+            for name in &import.group {
+                let mut path = import.path.clone();
+                path.push(name.clone());
+                self.collect_import(&Import {
+                    path,
+                    alias: None,
+                    glob: false,
+                    group: Vec::new(),
+                })?;
+            }
+            return Ok(());
+        }
+
+        let (Some(first), Some((last, prefix))) = (import.path.first(), import.path.split_last())
+        else {
+            return Ok(());
+        };
+        let in_file = self.module_path.first() == Some(&first.value)
+            || self
+                .modules
+                .keys()
+                .any(|path| path.split("::").next() == Some(first.value.as_str()));
+        if !in_file {
+            return Ok(());
+        }
+
+        let path: Vec<&str> = import.path.iter().map(|s| s.value.as_str()).collect();
+        let path_span = first.span.merge(last.span);
+        if import.glob {
+            let Some(symbols) = self.modules.get(&path.join("::")) else {
+                return Err(Error::new(
+                    ErrorKind::UndefinedVariable,
+                    path_span,
+                    format!("Unresolved import '{}::*'", path.join("::")),
+                ));
+            };
+            let mut symbols: Vec<_> = symbols.clone().into_iter().collect();
+            symbols.sort_by(|a, b| a.0.cmp(&b.0));
+            for (name, symbol) in symbols {
+                self.symbols
+                    .define(name, symbol)
+                    .map_err(|e| Error::new(ErrorKind::SemanticError, path_span, e))?;
+            }
+            return Ok(());
+        }
+        if self.modules.contains_key(&path.join("::")) {
+            // Importing a module itself; its name is already in scope
+            return Ok(());
+        }
+
+        let module = path[..prefix.len()].join("::");
+        let resolved = self
+            .modules
+            .get(&module)
+            .and_then(|symbols| symbols.get(&last.value))
+            .cloned();
+
+        let Some(symbol) = resolved else {
+            return Err(Error::new(
+                ErrorKind::UndefinedVariable,
+                path_span,
+                format!("Unresolved import '{}'", path.join("::")),
+            ));
+        };
+
+        let name = import.alias.as_ref().unwrap_or(last);
+        self.symbols
+            .define(name.value.clone(), symbol)
+            .map_err(|e| Error::new(ErrorKind::SemanticError, name.span, e))
+    }
+
+    /// Check a constant's initializer and record its folded value
+    fn collect_const(&mut self, c: &Const) -> Result<()> {
+        let ty = self.resolve_type(&c.ty)?;
+        let value_ty = self.check_expr(&c.value)?;
+        let value = Some(eval_const(&c.value, &self.symbols)?);
+
+        // Integer constants take their declared width, like literals in arithmetic
+        let adapts = matches!(value, Some(Literal::Integer(_)))
+            && self.is_integer(&ty)
+            && self.is_integer(&value_ty);
+        if !adapts && !self.types_compatible(&value_ty, &ty) {
+            return Err(Error::new(
+                ErrorKind::TypeMismatch,
+                c.value.span,
+                format!("Type mismatch: expected {}, found {}", ty, value_ty),
+            ));
+        }
+
+        self.symbols
+            .define(
+                c.name.value.clone(),
+                Symbol::Const {
+                    ty,
+                    value,
+                    span: c.name.span,
+                },
+            )
+            .map_err(|e| Error::new(ErrorKind::SemanticError, c.name.span, e))
+    }
+
+    /// `name` as declared in the current module: shapes::Circle
+    fn qualify(&self, name: &str) -> String {
+        self.module_path
+            .iter()
+            .map(String::as_str)
+            .chain([name])
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
+    /// The qualified name of the type `name` refers to here. Types not
+    /// collected yet are taken to be declared in the current module.
+    fn type_key(&self, name: &str) -> String {
+        match self.symbols.lookup(name) {
+            Some(Symbol::Type { path, .. }) => path.clone(),
+            _ => self.qualify(name),
+        }
+    }
+
     /// Type check an item
     fn check_item(&mut self, item: &Item) -> Result<()> {
         match item {
-            Item::Function(func) => self.check_function(func),
+            Item::Function(func) => {
+                if func.name.value == "main" && self.module_path.is_empty() {
+                    self.check_main_signature(func)?;
+                }
+                self.check_function(func, None)
+            }
+            Item::Mod(m) => {
+                // The module's own items, as collected, are in scope for its bodies
+                self.module_path.push(m.name.value.clone());
+                let path = self.module_path.join("::");
+                let symbols = self.modules.get(&path).cloned().unwrap_or_default();
+                self.symbols.push_scope(symbols);
+
+                let result = m.items.iter().try_for_each(|item| self.check_item(item));
+
+                self.symbols.exit_scope();
+                self.module_path.pop();
+                result
+            }
+            Item::Impl(imp) => {
+                if let Some(trait_name) = &imp.trait_name {
+                    self.check_supertraits_implemented(
+                        &self.type_key(&imp.type_name.value),
+                        trait_name,
+                    )?;
+                    self.check_trait_methods_implemented(imp, trait_name)?;
+                }
+                let self_ty = self.resolve_type(&Type::Simple(imp.type_name.clone()))?;
+                for method in &imp.methods {
+                    self.check_function(method, Some(&self_ty))?;
+                }
+                Ok(())
+            }
             _ => Ok(()), // TODO: Implement other items
         }
     }
 
-    /// Type check a function
-    fn check_function(&mut self, func: &Function) -> Result<()> {
+    /// Implementing a trait requires implementing each of its supertraits
+    fn check_supertraits_implemented(
+        &self,
+        type_name: &str,
+        trait_name: &Spanned<String>,
+    ) -> Result<()> {
+        let Some(supertraits) = self.supertraits.get(&trait_name.value) else {
+            return Ok(());
+        };
+        for supertrait in supertraits {
+            let key = (type_name.to_string(), supertrait.clone());
+            if !self.trait_impls.contains(&key) {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    trait_name.span,
+                    format!(
+                        "'{}' implements '{}' but not its supertrait '{}'",
+                        type_name, trait_name.value, supertrait
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// An impl of a trait provides exactly the trait's methods, each with
+    /// the declared parameter count and return type
+    fn check_trait_methods_implemented(
+        &self,
+        imp: &Impl,
+        trait_name: &Spanned<String>,
+    ) -> Result<()> {
+        let Some(required) = self.traits.get(&trait_name.value) else {
+            return Ok(());
+        };
+        let provided = self.methods.get(&self.type_key(&imp.type_name.value));
+
+        for method in &imp.methods {
+            let Some(expected) = required.get(&method.name.value) else {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    method.name.span,
+                    format!(
+                        "Method '{}' is not a member of trait '{}'",
+                        method.name.value, trait_name.value
+                    ),
+                ));
+            };
+            let Some(actual) = provided.and_then(|m| m.get(&method.name.value)) else {
+                continue;
+            };
+            if actual.receiver != expected.receiver {
+                let describe = |receiver: Option<SelfKind>| match receiver {
+                    Some(kind) => format!("`{}`", kind.as_str()),
+                    None => "no receiver".to_string(),
+                };
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    method.name.span,
+                    format!(
+                        "Method '{}' takes {} but trait '{}' declares {}",
+                        method.name.value,
+                        describe(actual.receiver),
+                        trait_name.value,
+                        describe(expected.receiver)
+                    ),
+                ));
+            }
+            if actual.params.len() != expected.params.len() {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    method.name.span,
+                    format!(
+                        "Method '{}' takes {} parameter(s) but trait '{}' declares {}",
+                        method.name.value,
+                        actual.params.len(),
+                        trait_name.value,
+                        expected.params.len()
+                    ),
+                ));
+            }
+            if !self.types_compatible(&expected.return_type, &actual.return_type) {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    method.name.span,
+                    format!(
+                        "Method '{}' returns {} but trait '{}' declares {}",
+                        method.name.value,
+                        actual.return_type,
+                        trait_name.value,
+                        expected.return_type
+                    ),
+                ));
+            }
+        }
+
+        let mut missing: Vec<&String> = required
+            .keys()
+            .filter(|name| !imp.methods.iter().any(|m| &m.name.value == *name))
+            .collect();
+        missing.sort();
+        if let Some(name) = missing.first() {
+            return Err(Error::new(
+                ErrorKind::SemanticError,
+                trait_name.span,
+                format!(
+                    "'{}' implements '{}' but is missing method '{}'",
+                    imp.type_name.value, trait_name.value, name
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The entry point takes nothing or the command-line arguments as
+    /// `args: [str]`, and returns nothing or an integer exit code
+    fn check_main_signature(&mut self, func: &Function) -> Result<()> {
+        match func.params.as_slice() {
+            [] => {}
+            [param] => {
+                let ty = self.resolve_type(&param.ty)?;
+                let is_args = matches!(
+                    &ty,
+                    ResolvedType::Array(elem, None)
+                        if **elem == ResolvedType::Primitive(PrimitiveType::String)
+                );
+                if !is_args {
+                    return Err(Error::new(
+                        ErrorKind::SemanticError,
+                        param.name.span,
+                        format!(
+                            "'main' can only take the program arguments as `[str]`, found {}",
+                            ty
+                        ),
+                    ));
+                }
+            }
+            [_, extra, ..] => {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    extra.name.span,
+                    format!(
+                        "'main' takes at most one parameter, found {}",
+                        func.params.len()
+                    ),
+                ));
+            }
+        }
+
+        if let Some(return_type) = &func.return_type {
+            let ty = self.resolve_type(return_type)?;
+            let is_exit_code =
+                self.is_integer(&ty) || ty == ResolvedType::Primitive(PrimitiveType::Unit);
+            if !is_exit_code {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    func.name.span,
+                    format!(
+                        "'main' must return nothing or an integer exit code, found {}",
+                        ty
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Type check a function, or a method of `self_ty`
+    fn check_function(&mut self, func: &Function, self_ty: Option<&ResolvedType>) -> Result<()> {
+        self.check_param_defaults(&func.params)?;
         self.symbols.enter_scope();
 
+        // `&self` and `own self` differ only in ownership, which is not
+        // tracked yet, so every receiver is typed as the impl's type
+        if func.receiver.is_some() {
+            let Some(self_ty) = self_ty else {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    func.name.span,
+                    format!(
+                        "Function '{}' takes 'self' but is not a method",
+                        func.name.value
+                    ),
+                ));
+            };
+            self.symbols
+                .define(
+                    "self".to_string(),
+                    Symbol::Variable {
+                        ty: self_ty.clone(),
+                        mutable: false,
+                        initialized: true,
+                        span: func.name.span,
+                    },
+                )
+                .map_err(|e| Error::new(ErrorKind::SemanticError, func.name.span, e))?;
+        }
+
         // Add parameters to scope
-        for param in &func.params {
+        for (i, param) in func.params.iter().enumerate() {
+            if func.params[..i]
+                .iter()
+                .any(|p| p.name.value == param.name.value)
+            {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    param.name.span,
+                    format!(
+                        "Parameter '{}' is declared more than once",
+                        param.name.value
+                    ),
+                ));
+            }
             let ty = self.resolve_type(&param.ty)?;
             self.symbols
                 .define(
@@ -292,6 +1144,7 @@ impl TypeChecker {
                     Symbol::Variable {
                         ty,
                         mutable: false,
+                        initialized: true,
                         span: param.name.span,
                     },
                 )
@@ -308,7 +1161,34 @@ impl TypeChecker {
         self.current_function_return = Some(return_type.clone());
 
         // Check function body
-        self.check_block(&func.body)?;
+        let body_ty = self.check_block(&func.body)?;
+
+        // A value-returning function can't fall off the end of its body
+        if !matches!(
+            return_type,
+            ResolvedType::Primitive(PrimitiveType::Unit) | ResolvedType::Unknown
+        ) {
+            if !block_yields(&func.body) {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    func.name.span,
+                    format!(
+                        "Function '{}' must return {} on every path, but the end of its body can be reached without a value",
+                        func.name.value, return_type
+                    ),
+                ));
+            }
+            if func.body.expr.is_some() && !self.types_compatible(&body_ty, &return_type) {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    func.name.span,
+                    format!(
+                        "Function '{}' returns {}, but its body evaluates to {}",
+                        func.name.value, return_type, body_ty
+                    ),
+                ));
+            }
+        }
 
         self.current_function_return = None;
         self.symbols.exit_scope();
@@ -316,6 +1196,37 @@ impl TypeChecker {
         Ok(())
     }
 
+    /// Defaults are evaluated at the call site, so they can't see the other
+    /// parameters. Only trailing parameters can have them.
+    fn check_param_defaults(&mut self, params: &[Param]) -> Result<()> {
+        let required = required_params(params);
+        for param in &params[required..] {
+            let Some(default) = &param.default else {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    param.name.span,
+                    format!(
+                        "Parameter '{}' needs a default value because an earlier parameter has one",
+                        param.name.value
+                    ),
+                ));
+            };
+            let expected = self.resolve_type(&param.ty)?;
+            let found = self.check_expr(default)?;
+            if !self.types_compatible(&found, &expected) {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    default.span,
+                    format!(
+                        "Default value of parameter '{}' has type {}, expected {}",
+                        param.name.value, found, expected
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Type check a block
     fn check_block(&mut self, block: &Block) -> Result<ResolvedType> {
         self.symbols.enter_scope();
@@ -330,6 +1241,22 @@ impl TypeChecker {
             ResolvedType::Primitive(PrimitiveType::Unit)
         };
 
+        // A `let x;` never assigned in its block has no type to give it
+        for stmt in &block.stmts {
+            if let Stmt::Let { name, .. } = stmt {
+                if self.uninferred.remove(&name.span) {
+                    return Err(Error::new(
+                        ErrorKind::SemanticError,
+                        name.span,
+                        format!(
+                            "Cannot infer the type of '{}'; assign it a value or add a type annotation",
+                            name.value
+                        ),
+                    ));
+                }
+            }
+        }
+
         self.symbols.exit_scope();
         Ok(result_type)
     }
@@ -343,37 +1270,43 @@ impl TypeChecker {
                 value,
                 mutable,
             } => {
-                let value_type = if let Some(v) = value {
-                    self.check_expr(v)?
-                } else {
-                    return Err(Error::new(
-                        ErrorKind::SemanticError,
-                        name.span,
-                        "Let binding must have an initializer or explicit type".to_string(),
-                    ));
+                let mut value_type = match (value, ty) {
+                    (Some(v), _) => self.check_expr(v)?,
+                    (None, Some(annotated_ty)) => self.resolve_type(annotated_ty)?,
+                    // Typed by the first assignment later in the block
+                    (None, None) => {
+                        self.uninferred.insert(name.span);
+                        ResolvedType::Unknown
+                    }
                 };
 
                 // If type annotation exists, check compatibility
-                if let Some(annotated_ty) = ty {
+                if let (Some(annotated_ty), Some(_)) = (ty, value) {
                     let expected_ty = self.resolve_type(annotated_ty)?;
                     if !self.types_compatible(&value_type, &expected_ty) {
                         return Err(Error::new(
                             ErrorKind::TypeMismatch,
                             name.span,
                             format!(
-                                "Type mismatch: expected {:?}, found {:?}",
+                                "Type mismatch: expected {}, found {}",
                                 expected_ty, value_type
                             ),
                         ));
                     }
+                    if let Some(v) = value {
+                        self.settle_unknown(v, &expected_ty);
+                    }
+                    // The annotation decides, e.g., which width a literal has
+                    value_type = expected_ty;
                 }
 
                 self.symbols
                     .define(
                         name.value.clone(),
                         Symbol::Variable {
-                            ty: value_type,
+                            ty: default_literals(value_type),
                             mutable: *mutable,
+                            initialized: value.is_some(),
                             span: name.span,
                         },
                     )
@@ -386,17 +1319,20 @@ impl TypeChecker {
                     ResolvedType::Primitive(PrimitiveType::Unit)
                 };
 
-                if let Some(expected) = &self.current_function_return {
-                    if !self.types_compatible(&return_type, expected) {
+                if let Some(expected) = self.current_function_return.clone() {
+                    if !self.types_compatible(&return_type, &expected) {
                         return Err(Error::new(
                             ErrorKind::TypeMismatch,
                             Span { start: 0, end: 0 }, // TODO: Better span
                             format!(
-                                "Return type mismatch: expected {:?}, found {:?}",
+                                "Return type mismatch: expected {}, found {}",
                                 expected, return_type
                             ),
                         ));
                     }
+                    if let Some(e) = expr {
+                        self.settle_unknown(e, &expected);
+                    }
                 }
             }
             Stmt::Expr(expr) => {
@@ -408,10 +1344,10 @@ impl TypeChecker {
                     return Err(Error::new(
                         ErrorKind::TypeMismatch,
                         condition.span,
-                        format!("While condition must be bool, found {:?}", cond_ty),
+                        format!("While condition must be bool, found {}", cond_ty),
                     ));
                 }
-                self.check_block(body)?;
+                self.check_loop_body(body, false)?;
             }
             Stmt::For { var, iter, body } => {
                 self.symbols.enter_scope();
@@ -423,58 +1359,189 @@ impl TypeChecker {
                         Symbol::Variable {
                             ty: ResolvedType::Primitive(PrimitiveType::I32),
                             mutable: false,
+                            initialized: true,
                             span: var.span,
                         },
                     )
                     .map_err(|e| Error::new(ErrorKind::SemanticError, var.span, e))?;
 
                 self.check_expr(iter)?;
-                self.check_block(body)?;
+                self.check_loop_body(body, false)?;
 
                 self.symbols.exit_scope();
             }
             Stmt::Loop { body } => {
-                self.check_block(body)?;
+                self.check_loop_body(body, true)?;
             }
-            _ => {}
+            Stmt::Break(span) | Stmt::Continue(span) if self.loop_exits.is_empty() => {
+                let keyword = if matches!(stmt, Stmt::Break(_)) {
+                    "break"
+                } else {
+                    "continue"
+                };
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    *span,
+                    format!("`{}` outside of a loop", keyword),
+                ));
+            }
+            Stmt::Break(_) => {
+                let unassigned = self.symbols.unassigned();
+                if let Some(exits) = self.loop_exits.last_mut() {
+                    exits.push(unassigned);
+                }
+            }
+            Stmt::Continue(_) => {}
         }
         Ok(())
     }
 
+    /// Type check the body of a `while`, `for`, or `loop`. A `while` or `for`
+    /// body may not run, so its assignments don't initialize anything declared
+    /// outside it. A `loop` body always runs and is only left by `break`, so
+    /// a variable is assigned after it if it was assigned at every `break`.
+    fn check_loop_body(&mut self, body: &Block, runs_once: bool) -> Result<()> {
+        let before = self.symbols.unassigned();
+        self.loop_exits.push(Vec::new());
+        let result = self.check_block(body);
+        let exits = self.loop_exits.pop().unwrap_or_default();
+        if runs_once {
+            self.join_branches(&before, exits.into_iter().map(Some).collect());
+        } else {
+            self.symbols.set_unassigned(&before, &before);
+        }
+        result.map(|_| ())
+    }
+
+    /// Finish checking one of several alternative branches: return the
+    /// variables it leaves unassigned, or `None` if it never finishes, and
+    /// undo its assignments so the next branch starts from `before`
+    fn end_branch(&mut self, before: &HashSet<Span>, finishes: bool) -> Option<HashSet<Span>> {
+        let after = finishes.then(|| {
+            self.symbols
+                .unassigned()
+                .intersection(before)
+                .copied()
+                .collect()
+        });
+        self.symbols.set_unassigned(before, before);
+        after
+    }
+
+    /// After the branches, a variable is assigned only if every branch that
+    /// finishes assigned it
+    fn join_branches(&mut self, before: &HashSet<Span>, ends: Vec<Option<HashSet<Span>>>) {
+        let unassigned: HashSet<Span> = ends.into_iter().flatten().flatten().collect();
+        self.symbols.set_unassigned(before, &unassigned);
+    }
+
     /// Type check an expression
     fn check_expr(&mut self, expr: &Expr) -> Result<ResolvedType> {
         match &expr.kind {
-            ExprKind::Integer(_) => Ok(ResolvedType::Primitive(PrimitiveType::I32)),
-            ExprKind::Float(_) => Ok(ResolvedType::Primitive(PrimitiveType::F64)),
+            ExprKind::Integer(_) => Ok(ResolvedType::IntLiteral),
+            ExprKind::Float(_) => Ok(ResolvedType::FloatLiteral),
             ExprKind::String(_) => Ok(ResolvedType::Primitive(PrimitiveType::String)),
             ExprKind::Char(_) => Ok(ResolvedType::Primitive(PrimitiveType::Char)),
             ExprKind::Bool(_) => Ok(ResolvedType::Primitive(PrimitiveType::Bool)),
+            ExprKind::Byte(_) => Ok(ResolvedType::Primitive(PrimitiveType::U8)),
+            ExprKind::ByteString(bytes) => Ok(ResolvedType::Array(
+                Box::new(ResolvedType::Primitive(PrimitiveType::U8)),
+                Some(bytes.len()),
+            )),
 
             ExprKind::Ident(name) => {
-                match self.symbols.lookup(name) {
-                    Some(Symbol::Variable { ty, .. }) => Ok(ty.clone()),
-                    Some(Symbol::Function {
-                        params,
-                        return_type,
-                        ..
-                    }) => {
-                        // Allow functions to be used as values (for function pointers, closures, etc.)
-                        Ok(ResolvedType::Function {
-                            params: params.clone(),
-                            return_type: Box::new(return_type.clone()),
-                        })
-                    }
-                    _ => Err(Error::new(
+                if self.is_uninitialized(name) {
+                    return Err(Error::new(
                         ErrorKind::SemanticError,
                         expr.span,
-                        format!("Undefined variable '{}'", name),
-                    )),
+                        format!("Variable '{}' is used before it is assigned", name),
+                    ));
+                }
+                self.symbol_value(name, self.symbols.lookup(name), expr.span)
+            }
+
+            ExprKind::Path { path, type_args } => {
+                if !type_args.is_empty() {
+                    return Err(Error::new(
+                        ErrorKind::SemanticError,
+                        expr.span,
+                        format!(
+                            "Type arguments in path '{}' aren't supported yet",
+                            path_name(path)
+                        ),
+                    ));
+                }
+                let symbol = self.resolve_path(path)?;
+                self.symbol_value(&path_name(path), Some(symbol), expr.span)
+            }
+
+            ExprKind::Binary {
+                op: BinOp::Assign,
+                left,
+                right,
+            } if matches!(&left.kind, ExprKind::Ident(name) if self.is_uninitialized(name)) => {
+                // First assignment to `let x;` initializes it, and fixes its
+                // type when it has no annotation
+                let value_ty = self.check_expr(right)?;
+                let ExprKind::Ident(name) = &left.kind else {
+                    unreachable!("guarded above");
+                };
+                let Some(Symbol::Variable { ty, span, .. }) = self.symbols.lookup(name).cloned()
+                else {
+                    unreachable!("guarded above");
+                };
+
+                let ty = if self.uninferred.remove(&span) {
+                    default_literals(value_ty)
+                } else if self.types_compatible(&value_ty, &ty) {
+                    ty
+                } else {
+                    return Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        right.span,
+                        format!(
+                            "Type mismatch: expected {}, found {}",
+                            ty, value_ty
+                        ),
+                    ));
+                };
+                if let Some(Symbol::Variable {
+                    ty: slot,
+                    initialized,
+                    ..
+                }) = self.symbols.lookup_mut(name)
+                {
+                    *slot = ty;
+                    *initialized = true;
                 }
+                Ok(ResolvedType::Primitive(PrimitiveType::Unit))
             }
 
             ExprKind::Binary { op, left, right } => {
-                let left_ty = self.check_expr(left)?;
-                let right_ty = self.check_expr(right)?;
+                if let BinOp::Assign
+                | BinOp::AddAssign
+                | BinOp::SubAssign
+                | BinOp::MulAssign
+                | BinOp::DivAssign
+                | BinOp::RemAssign = op
+                {
+                    self.check_assignable(left)?;
+                }
+
+                let mut left_ty = self.check_expr(left)?;
+                let mut right_ty = self.check_expr(right)?;
+
+                // An unsuffixed literal takes the other operand's type, except
+                // across a shift, whose amount needn't match the shifted value
+                if !matches!(op, BinOp::Shl | BinOp::Shr) {
+                    if self.literal_adapts(&left_ty, &right_ty) {
+                        left_ty = right_ty.clone();
+                    } else if self.literal_adapts(&right_ty, &left_ty) {
+                        right_ty = left_ty.clone();
+                    }
+                    self.settle_unknown(left, &right_ty);
+                    self.settle_unknown(right, &left_ty);
+                }
 
                 self.check_binary_op(*op, &left_ty, &right_ty, expr.span)
             }
@@ -484,14 +1551,86 @@ impl TypeChecker {
                 self.check_unary_op(*op, &inner_ty, expr.span)
             }
 
-            ExprKind::Call { func, args: _ } => {
+            ExprKind::Call {
+                func,
+                args,
+                type_args,
+            } => {
+                if let ExprKind::Ident(name) = &func.kind {
+                    if let Some(Symbol::Variant {
+                        enum_name,
+                        generics,
+                        payload,
+                        ..
+                    }) = self.symbols.lookup(name).cloned()
+                    {
+                        self.reject_type_args(name, type_args, expr.span)?;
+                        return self.check_variant_constructor(
+                            name, &enum_name, &generics, &payload, args, expr.span,
+                        );
+                    }
+
+                    if let Some(Symbol::Builtin { builtin, .. }) = self.symbols.lookup(name) {
+                        let builtin = *builtin;
+                        self.reject_type_args(name, type_args, expr.span)?;
+                        return self.check_builtin_call(builtin, name, args, expr.span);
+                    }
+                }
+                if let ExprKind::Path { path, .. } = &func.kind {
+                    if let Symbol::Variant {
+                        enum_name,
+                        generics,
+                        payload,
+                        ..
+                    } = self.resolve_path(path)?.clone()
+                    {
+                        let name = &path[path.len() - 1].value;
+                        self.reject_type_args(name, type_args, expr.span)?;
+                        return self.check_variant_constructor(
+                            name, &enum_name, &generics, &payload, args, expr.span,
+                        );
+                    }
+                }
+
+                // Only a named function's parameters can have defaults, and only
+                // its type parameters can be given explicitly
+                let named = match &func.kind {
+                    ExprKind::Ident(name) => self.symbols.lookup(name),
+                    ExprKind::Path { path, .. } => Some(self.resolve_path(path)?),
+                    _ => None,
+                };
+                let (required, generics) = match named {
+                    Some(Symbol::Function {
+                        required, generics, ..
+                    }) => (Some(*required), generics.clone()),
+                    _ => (None, Vec::new()),
+                };
                 let func_ty = self.check_expr(func)?;
 
                 // Extract return type from function type
                 match func_ty {
-                    ResolvedType::Function { return_type, .. } => Ok(*return_type),
+                    ResolvedType::Function {
+                        params,
+                        return_type,
+                    } => {
+                        let callee = match &func.kind {
+                            ExprKind::Ident(name) => format!("'{}'", name),
+                            ExprKind::Path { path, .. } => format!("'{}'", path_name(path)),
+                            _ => "Function".to_string(),
+                        };
+                        let required = required.unwrap_or(params.len());
+                        let explicit =
+                            self.explicit_type_args(&callee, &generics, type_args, expr.span)?;
+                        let params: Vec<_> = params.iter().map(|p| p.substitute(&explicit)).collect();
+                        let subst =
+                            self.check_call_args(&callee, &params, required, args, expr.span)?;
+                        Ok(instantiate(&return_type.substitute(&explicit), &subst))
+                    }
                     _ => {
                         // For now, allow any type to be called (simplified)
+                        if !type_args.is_empty() {
+                            self.explicit_type_args("Function", &[], type_args, expr.span)?;
+                        }
                         Ok(ResolvedType::Unknown)
                     }
                 }
@@ -502,29 +1641,40 @@ impl TypeChecker {
                 then_block,
                 else_block,
             } => {
-                let cond_ty = self.check_expr(condition)?;
-                if cond_ty != ResolvedType::Primitive(PrimitiveType::Bool) {
-                    return Err(Error::new(
-                        ErrorKind::TypeMismatch,
-                        condition.span,
-                        format!("If condition must be bool, found {:?}", cond_ty),
-                    ));
-                }
+                let before = self.symbols.unassigned();
 
-                let then_ty = self.check_block(then_block)?;
+                // Bindings from `let` conditions are visible to later conditions
+                // and the then-block, but not the else-block
+                self.symbols.enter_scope();
+                let then_result = self
+                    .check_condition(condition)
+                    .and_then(|_| self.check_block(then_block));
+                self.symbols.exit_scope();
+                let then_ty = then_result?;
+                let then_end = self.end_branch(&before, !block_diverges(then_block));
 
-                if let Some(else_blk) = else_block {
-                    let else_ty = self.check_block(else_blk)?;
-                    if self.types_compatible(&then_ty, &else_ty) {
-                        Ok(then_ty)
-                    } else {
-                        Ok(ResolvedType::Primitive(PrimitiveType::Unit))
-                    }
+                let Some(else_blk) = else_block else {
+                    // Skipping the then-block assigns nothing
+                    self.join_branches(&before, vec![then_end, Some(before.clone())]);
+                    return Ok(ResolvedType::Primitive(PrimitiveType::Unit));
+                };
+                let else_ty = self.check_block(else_blk)?;
+                let else_end = self.end_branch(&before, !block_diverges(else_blk));
+                self.join_branches(&before, vec![then_end, else_end]);
+
+                if self.types_compatible(&then_ty, &else_ty) {
+                    Ok(self.join_types(then_ty, else_ty))
                 } else {
                     Ok(ResolvedType::Primitive(PrimitiveType::Unit))
                 }
             }
 
+            ExprKind::Let { .. } => Err(Error::new(
+                ErrorKind::SemanticError,
+                expr.span,
+                "`let` expressions are only allowed in `if` conditions, optionally joined with `&&`",
+            )),
+
             ExprKind::Block(block) => self.check_block(block),
 
             ExprKind::Range { .. } => {
@@ -532,176 +1682,1862 @@ impl TypeChecker {
                 Ok(ResolvedType::Unknown)
             }
 
-            _ => Ok(ResolvedType::Unknown),
-        }
-    }
+            ExprKind::Match {
+                expr: scrutinee,
+                arms,
+            } => {
+                let scrutinee_ty = self.check_expr(scrutinee)?;
+                let before = self.symbols.unassigned();
+                let mut ends = Vec::new();
 
-    /// Check binary operation type compatibility
-    fn check_binary_op(
-        &self,
-        op: BinOp,
-        left: &ResolvedType,
-        right: &ResolvedType,
-        span: Span,
-    ) -> Result<ResolvedType> {
-        use BinOp::*;
-        use PrimitiveType::*;
+                // Every arm that produces a value must agree with the first;
+                // arms that return or break don't count
+                let mut match_ty = ResolvedType::Unknown;
+                for arm in arms {
+                    self.symbols.enter_scope();
+                    let result = self.check_match_arm(arm, &scrutinee_ty, expr.span);
+                    self.symbols.exit_scope();
+                    let arm_ty = result?;
+                    ends.push(self.end_branch(&before, !expr_diverges(&arm.body)));
 
-        match op {
-            Add | Sub | Mul | Div | Rem => {
-                // Arithmetic operators require numeric types
-                if self.is_numeric(left) && self.types_compatible(left, right) {
-                    Ok(left.clone())
-                } else {
-                    Err(Error::new(
-                        ErrorKind::TypeMismatch,
-                        span,
-                        format!(
-                            "Arithmetic operation requires numeric types, found {:?} and {:?}",
-                            left, right
-                        ),
-                    ))
+                    if expr_diverges(&arm.body) {
+                        continue;
+                    }
+                    if match_ty == ResolvedType::Unknown {
+                        match_ty = arm_ty;
+                    } else if self.types_compatible(&match_ty, &arm_ty) {
+                        match_ty = self.join_types(match_ty, arm_ty);
+                    } else {
+                        return Err(Error::new(
+                            ErrorKind::TypeMismatch,
+                            arm.body.span,
+                            format!(
+                                "Match arms have incompatible types, expected {}, found {}",
+                                match_ty, arm_ty
+                            ),
+                        ));
+                    }
                 }
+                self.join_branches(&before, ends);
+
+                Ok(match_ty)
             }
-            Eq | Ne | Lt | Le | Gt | Ge => {
-                // Comparison operators return bool
-                if self.types_compatible(left, right) {
-                    Ok(ResolvedType::Primitive(Bool))
-                } else {
-                    Err(Error::new(
-                        ErrorKind::TypeMismatch,
-                        span,
-                        format!(
-                            "Comparison requires compatible types, found {:?} and {:?}",
-                            left, right
-                        ),
-                    ))
-                }
+
+            ExprKind::Try(inner) => {
+                let inner_ty = self.check_expr(inner)?;
+                self.check_try(&inner_ty, expr.span)
             }
-            And | Or => {
-                // Logical operators require bool
-                if *left == ResolvedType::Primitive(Bool) && *right == ResolvedType::Primitive(Bool)
-                {
-                    Ok(ResolvedType::Primitive(Bool))
-                } else {
-                    Err(Error::new(
-                        ErrorKind::TypeMismatch,
-                        span,
-                        format!(
-                            "Logical operation requires bool, found {:?} and {:?}",
-                            left, right
-                        ),
-                    ))
+
+            ExprKind::Array(elements) => {
+                let mut elem_ty = ResolvedType::Unknown;
+                for element in elements {
+                    let ty = self.check_expr(element)?;
+                    if elem_ty == ResolvedType::Unknown {
+                        elem_ty = ty;
+                    } else if self.types_compatible(&elem_ty, &ty) {
+                        elem_ty = self.join_types(elem_ty, ty);
+                    } else {
+                        return Err(Error::new(
+                            ErrorKind::TypeMismatch,
+                            element.span,
+                            format!(
+                                "Array elements must have the same type, expected {}, found {}",
+                                elem_ty, ty
+                            ),
+                        ));
+                    }
                 }
+
+                Ok(ResolvedType::Array(Box::new(elem_ty), Some(elements.len())))
             }
-            _ => Ok(ResolvedType::Unknown), // TODO: Implement other operators
-        }
-    }
 
-    /// Check unary operation type compatibility
-    fn check_unary_op(&self, op: UnOp, operand: &ResolvedType, span: Span) -> Result<ResolvedType> {
-        use PrimitiveType::*;
-        use UnOp::*;
+            ExprKind::Index {
+                expr: container,
+                index,
+            } => {
+                let container_ty = self.check_expr(container)?;
+                let index_ty = self.check_expr(index)?;
 
-        match op {
-            Neg => {
-                if self.is_numeric(operand) {
-                    Ok(operand.clone())
-                } else {
-                    Err(Error::new(
+                if !self.is_integer(&index_ty) && index_ty != ResolvedType::Unknown {
+                    return Err(Error::new(
                         ErrorKind::TypeMismatch,
-                        span,
-                        format!("Negation requires numeric type, found {:?}", operand),
-                    ))
+                        index.span,
+                        format!("Index must be an integer, found {}", index_ty),
+                    ));
                 }
-            }
-            Not => {
-                if *operand == ResolvedType::Primitive(Bool) {
-                    Ok(ResolvedType::Primitive(Bool))
-                } else {
-                    Err(Error::new(
+
+                // References and owned values index like what they point to
+                let mut container_ty = container_ty;
+                while let ResolvedType::Reference(inner) | ResolvedType::Owned(inner) =
+                    container_ty
+                {
+                    container_ty = *inner;
+                }
+
+                match container_ty {
+                    ResolvedType::Array(elem_ty, size) => {
+                        // Constant indices into sized arrays are checked now;
+                        // everything else is left to runtime
+                        if let (Some(size), Some(value)) = (size, self.const_int(index)) {
+                            if value < 0 || value as u64 >= size as u64 {
+                                return Err(Error::new(
+                                    ErrorKind::SemanticError,
+                                    index.span,
+                                    format!(
+                                        "Index {} is out of bounds for array of length {}",
+                                        value, size
+                                    ),
+                                ));
+                            }
+                        }
+                        Ok(*elem_ty)
+                    }
+                    ResolvedType::Generic { name, mut args } if name == "Vec" && args.len() == 1 => {
+                        Ok(args.remove(0))
+                    }
+                    // `str` and types the checker can't see inside may still
+                    // be indexable, so their element type is left open
+                    ResolvedType::Primitive(PrimitiveType::String)
+                    | ResolvedType::Generic { .. }
+                    | ResolvedType::UserDefined(_)
+                    | ResolvedType::TypeVar(_)
+                    | ResolvedType::Opaque(_)
+                    | ResolvedType::Unknown => Ok(ResolvedType::Unknown),
+                    other => Err(Error::new(
                         ErrorKind::TypeMismatch,
-                        span,
-                        format!("Logical not requires bool, found {:?}", operand),
-                    ))
+                        container.span,
+                        format!("Cannot index into {}", other),
+                    )),
                 }
             }
-            BitNot => {
-                if self.is_integer(operand) {
-                    Ok(operand.clone())
-                } else {
-                    Err(Error::new(
-                        ErrorKind::TypeMismatch,
-                        span,
-                        format!("Bitwise not requires integer type, found {:?}", operand),
-                    ))
+
+            ExprKind::Field {
+                expr: receiver,
+                field,
+            } => {
+                // Fields are reached through references and owned values too
+                let mut receiver_ty = self.check_expr(receiver)?;
+                while let ResolvedType::Reference(inner) | ResolvedType::Owned(inner) = receiver_ty
+                {
+                    receiver_ty = *inner;
+                }
+
+                match receiver_ty {
+                    ResolvedType::UserDefined(name) if self.structs.contains_key(&name) => {
+                        let fields = &self.structs[&name];
+                        match fields.iter().find(|(f, _)| *f == field.value) {
+                            Some((_, ty)) => Ok(ty.clone()),
+                            None => Err(Error::new(
+                                ErrorKind::SemanticError,
+                                field.span,
+                                format!("Struct '{}' has no field '{}'", name, field.value),
+                            )),
+                        }
+                    }
+                    _ => Ok(ResolvedType::Unknown),
                 }
             }
-        }
-    }
 
-    /// Resolve AST type to semantic type
-    fn resolve_type(&self, ty: &Type) -> Result<ResolvedType> {
-        resolve_type_helper(ty)
-    }
-}
+            ExprKind::MethodCall {
+                receiver,
+                method,
+                args,
+            } => {
+                let receiver_ty = self.check_expr(receiver)?;
 
-/// Helper function to resolve AST type to semantic type
-fn resolve_type_helper(ty: &Type) -> Result<ResolvedType> {
-    match ty {
-        Type::Simple(name) => match name.value.as_str() {
-            "i8" => Ok(ResolvedType::Primitive(PrimitiveType::I8)),
-            "i16" => Ok(ResolvedType::Primitive(PrimitiveType::I16)),
-            "i32" => Ok(ResolvedType::Primitive(PrimitiveType::I32)),
-            "i64" => Ok(ResolvedType::Primitive(PrimitiveType::I64)),
-            "u8" => Ok(ResolvedType::Primitive(PrimitiveType::U8)),
-            "u16" => Ok(ResolvedType::Primitive(PrimitiveType::U16)),
-            "u32" => Ok(ResolvedType::Primitive(PrimitiveType::U32)),
-            "u64" => Ok(ResolvedType::Primitive(PrimitiveType::U64)),
-            "f32" => Ok(ResolvedType::Primitive(PrimitiveType::F32)),
-            "f64" => Ok(ResolvedType::Primitive(PrimitiveType::F64)),
-            "bool" => Ok(ResolvedType::Primitive(PrimitiveType::Bool)),
-            "char" => Ok(ResolvedType::Primitive(PrimitiveType::Char)),
-            "str" => Ok(ResolvedType::Primitive(PrimitiveType::String)),
-            _ => Ok(ResolvedType::UserDefined(name.value.clone())),
-        },
-        Type::Ref(inner) => {
-            let inner_ty = resolve_type_helper(inner)?;
-            Ok(ResolvedType::Reference(Box::new(inner_ty)))
-        }
-        Type::Own(inner) => {
-            let inner_ty = resolve_type_helper(inner)?;
-            Ok(ResolvedType::Owned(Box::new(inner_ty)))
-        }
-        Type::Tuple(types) => {
-            let resolved: Result<Vec<_>> = types.iter().map(resolve_type_helper).collect();
-            Ok(ResolvedType::Tuple(resolved?))
+                let sig = match default_literals(receiver_ty) {
+                    ResolvedType::Opaque(bounds) => self.bounded_method(&bounds, method)?,
+                    ResolvedType::Primitive(prim) => self.impl_method(prim.name(), method)?,
+                    ResolvedType::UserDefined(name)
+                        if self.methods.contains_key(&name) || self.structs.contains_key(&name) =>
+                    {
+                        self.impl_method(&name, method)?
+                    }
+                    _ => {
+                        for arg in args {
+                            self.check_expr(arg)?;
+                        }
+                        return Ok(ResolvedType::Unknown);
+                    }
+                };
+
+                let callee = format!("Method '{}'", method.value);
+                let subst = self.check_call_args(&callee, &sig.params, sig.required, args, expr.span)?;
+                Ok(instantiate(&sig.return_type, &subst))
+            }
+
+            ExprKind::Lambda {
+                params,
+                return_type,
+                body,
+            } => self.check_lambda(params, return_type.as_ref(), body),
+
+            ExprKind::StructLit { name, fields } => self.check_struct_lit(name, fields),
+
+            _ => Ok(ResolvedType::Unknown),
         }
-        Type::Array(elem_ty, size) => {
-            let elem = resolve_type_helper(elem_ty)?;
-            Ok(ResolvedType::Array(Box::new(elem), *size))
+    }
+
+    /// The type of a symbol used as a value
+    fn symbol_value(
+        &self,
+        name: &str,
+        symbol: Option<&Symbol>,
+        span: Span,
+    ) -> Result<ResolvedType> {
+        match symbol {
+            Some(Symbol::Variable { ty, .. }) | Some(Symbol::Const { ty, .. }) => Ok(ty.clone()),
+            Some(Symbol::Function {
+                params,
+                return_type,
+                ..
+            }) => {
+                // Allow functions to be used as values (for function pointers, closures, etc.)
+                Ok(ResolvedType::Function {
+                    params: params.clone(),
+                    return_type: Box::new(return_type.clone()),
+                })
+            }
+            Some(Symbol::Variant {
+                enum_name,
+                generics,
+                payload,
+                ..
+            }) => {
+                // Unit variants are values; tuple variants are constructor functions
+                let subst = HashMap::new();
+                let enum_ty = instantiate_enum(enum_name, generics, &subst);
+                if payload.is_empty() {
+                    Ok(enum_ty)
+                } else {
+                    Ok(ResolvedType::Function {
+                        params: payload.iter().map(|t| instantiate(t, &subst)).collect(),
+                        return_type: Box::new(enum_ty),
+                    })
+                }
+            }
+            Some(Symbol::Builtin { .. }) => Err(Error::new(
+                ErrorKind::SemanticError,
+                span,
+                format!("Built-in function '{}' can only be called", name),
+            )),
+            Some(Symbol::Type { .. }) => Err(Error::new(
+                ErrorKind::SemanticError,
+                span,
+                format!("Expected a value, found type '{}'", name),
+            )),
+            None => Err(Error::new(
+                ErrorKind::SemanticError,
+                span,
+                format!("Undefined variable '{}'", name),
+            )),
         }
-        Type::Infer => Ok(ResolvedType::Unknown),
-        _ => Ok(ResolvedType::Unknown),
     }
-}
 
-impl TypeChecker {
-    /// Check if two types are compatible
-    fn types_compatible(&self, a: &ResolvedType, b: &ResolvedType) -> bool {
-        if a == b {
-            return true;
+    /// Resolve `a::b::item` to an item of a module in this file, and
+    /// `Enum::Variant` (possibly behind a module path) to the variant
+    fn resolve_path(&self, path: &[Spanned<String>]) -> Result<&Symbol> {
+        let names: Vec<&str> = path.iter().map(|s| s.value.as_str()).collect();
+        let span = path[0].span.merge(path[path.len() - 1].span);
+        let Some((last, prefix)) = names.split_last() else {
+            unreachable!("paths have at least two segments");
+        };
+
+        let module_item = |module: &[&str], name: &str| {
+            self.modules
+                .get(&module.join("::"))
+                .and_then(|items| items.get(name))
+        };
+        if let Some(symbol) = module_item(prefix, last) {
+            return Ok(symbol);
         }
 
-        // Allow Unknown to be compatible with anything (for type inference)
-        if matches!(a, ResolvedType::Unknown) || matches!(b, ResolvedType::Unknown) {
-            return true;
+        if let Some((enum_name, module)) = prefix.split_last() {
+            let (ty, variant) = if module.is_empty() {
+                (
+                    self.symbols.lookup(enum_name),
+                    self.symbols.lookup_variant(last),
+                )
+            } else {
+                (module_item(module, enum_name), module_item(module, last))
+            };
+            if let Some(Symbol::Type {
+                kind: TypeKind::Enum,
+                path: enum_path,
+                ..
+            }) = ty
+            {
+                return match variant {
+                    Some(
+                        symbol @ Symbol::Variant {
+                            enum_name: owner, ..
+                        },
+                    ) if owner == enum_path => Ok(symbol),
+                    _ => Err(Error::new(
+                        ErrorKind::UndefinedVariable,
+                        span,
+                        format!("Enum '{}' has no variant '{}'", enum_name, last),
+                    )),
+                };
+            }
         }
 
-        false
+        Err(Error::new(
+            ErrorKind::UndefinedVariable,
+            span,
+            format!("Unresolved path '{}'", names.join("::")),
+        ))
     }
 
-    /// Check if type is numeric
+    /// Reject assignment to a variable that was not declared `mut`
+    fn check_assignable(&self, target: &Expr) -> Result<()> {
+        if let ExprKind::Ident(name) = &target.kind {
+            if let Some(Symbol::Variable { mutable: false, .. }) = self.symbols.lookup(name) {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    target.span,
+                    format!(
+                        "Cannot assign twice to immutable variable '{}'; declare it with `let mut`",
+                        name
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// A variable bound to a value of unknown type, such as the result of
+    /// calling an unannotated lambda, takes the type its first typed use expects, so
+    /// that later uses are checked against it instead of passing silently
+    fn settle_unknown(&mut self, expr: &Expr, expected: &ResolvedType) {
+        if matches!(
+            expected,
+            ResolvedType::Unknown | ResolvedType::IntLiteral | ResolvedType::FloatLiteral
+        ) || expected.mentions_type_var()
+        {
+            return;
+        }
+        let ExprKind::Ident(name) = &expr.kind else {
+            return;
+        };
+        if let Some(Symbol::Variable {
+            ty: ty @ ResolvedType::Unknown,
+            ..
+        }) = self.symbols.lookup_mut(name)
+        {
+            *ty = expected.clone();
+        }
+    }
+
+    /// Whether `name` is a `let` still waiting for its first assignment
+    fn is_uninitialized(&self, name: &str) -> bool {
+        matches!(
+            self.symbols.lookup(name),
+            Some(Symbol::Variable {
+                initialized: false,
+                ..
+            })
+        )
+    }
+
+    /// Check an `if` condition, binding `let` patterns left to right in the current scope
+    fn check_condition(&mut self, condition: &Expr) -> Result<()> {
+        match &condition.kind {
+            ExprKind::Binary {
+                op: BinOp::And,
+                left,
+                right,
+            } if contains_let(left) || contains_let(right) => {
+                self.check_condition(left)?;
+                self.check_condition(right)
+            }
+            ExprKind::Let { pattern, expr } => {
+                let scrutinee_ty = self.check_expr(expr)?;
+                self.bind_pattern(pattern, &scrutinee_ty, condition.span)
+            }
+            _ => {
+                let cond_ty = self.check_expr(condition)?;
+                if cond_ty != ResolvedType::Primitive(PrimitiveType::Bool) {
+                    return Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        condition.span,
+                        format!("If condition must be bool, found {}", cond_ty),
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Bind an arm's pattern and check its guard and body, returning the
+    /// body's type. The caller owns the arm's scope.
+    fn check_match_arm(
+        &mut self,
+        arm: &MatchArm,
+        scrutinee_ty: &ResolvedType,
+        span: Span,
+    ) -> Result<ResolvedType> {
+        self.bind_pattern(&arm.pattern, scrutinee_ty, span)?;
+        if let Some(guard) = &arm.guard {
+            let guard_ty = self.check_expr(guard)?;
+            if !self.types_compatible(&ResolvedType::Primitive(PrimitiveType::Bool), &guard_ty) {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    guard.span,
+                    format!("Match guard must be bool, found {}", guard_ty),
+                ));
+            }
+        }
+        self.check_expr(&arm.body)
+    }
+
+    /// Look up a method defined for a named type in an `impl` block
+    fn impl_method(&self, type_name: &str, method: &Spanned<String>) -> Result<MethodSig> {
+        let Some(sig) = self
+            .methods
+            .get(type_name)
+            .and_then(|methods| methods.get(&method.value))
+        else {
+            return Err(Error::new(
+                ErrorKind::SemanticError,
+                method.span,
+                format!(
+                    "No method '{}' found for type '{}'",
+                    method.value, type_name
+                ),
+            ));
+        };
+        Ok(sig.clone())
+    }
+
+    /// Look up a method called on an `impl Trait` value among its bounds
+    fn bounded_method(&self, bounds: &[String], method: &Spanned<String>) -> Result<MethodSig> {
+        let sig = bounds
+            .iter()
+            .filter_map(|bound| self.traits.get(bound))
+            .find_map(|methods| methods.get(&method.value));
+
+        let Some(sig) = sig else {
+            return Err(Error::new(
+                ErrorKind::SemanticError,
+                method.span,
+                format!(
+                    "No method '{}' found in bounds `impl {}`",
+                    method.value,
+                    bounds.join(" + ")
+                ),
+            ));
+        };
+        Ok(sig.clone())
+    }
+
+    /// Check a lambda body against its parameters and optional return
+    /// annotation, giving the lambda a function type. The body sees the
+    /// enclosing scopes, so captured variables resolve like any other.
+    fn check_lambda(
+        &mut self,
+        params: &[Param],
+        return_type: Option<&Type>,
+        body: &Expr,
+    ) -> Result<ResolvedType> {
+        self.symbols.enter_scope();
+
+        for param in params {
+            let ty = self.resolve_type(&param.ty)?;
+            self.symbols
+                .define(
+                    param.name.value.clone(),
+                    Symbol::Variable {
+                        ty,
+                        mutable: false,
+                        initialized: true,
+                        span: param.name.span,
+                    },
+                )
+                .map_err(|e| Error::new(ErrorKind::SemanticError, param.name.span, e))?;
+        }
+
+        // `return` inside the lambda returns from the lambda, not the enclosing function
+        let expected = return_type
+            .map(|t| self.resolve_type(t))
+            .transpose()?
+            .unwrap_or(ResolvedType::Unknown);
+        let enclosing_return = self.current_function_return.replace(expected.clone());
+        // Nor can `break` leave a loop around the lambda
+        let enclosing_loops = std::mem::take(&mut self.loop_exits);
+        // The body runs later, if at all, so its assignments initialize nothing here
+        let before = self.symbols.unassigned();
+
+        let body_result = self.check_expr(body);
+        self.symbols.set_unassigned(&before, &before);
+
+        // Unannotated parameters may have taken a type from their first use
+        let param_types = params
+            .iter()
+            .map(|param| match self.symbols.lookup(&param.name.value) {
+                Some(Symbol::Variable { ty, .. }) => ty.clone(),
+                _ => ResolvedType::Unknown,
+            })
+            .collect();
+
+        self.loop_exits = enclosing_loops;
+        self.current_function_return = enclosing_return;
+        self.symbols.exit_scope();
+
+        let body_ty = body_result?;
+        let lambda_ty = |return_type| ResolvedType::Function {
+            params: param_types,
+            return_type: Box::new(return_type),
+        };
+        // Each `return` was checked against the annotation already
+        if expr_diverges(body) {
+            return Ok(lambda_ty(expected));
+        }
+        if !self.types_compatible(&expected, &body_ty) {
+            return Err(Error::new(
+                ErrorKind::TypeMismatch,
+                body.span,
+                format!(
+                    "Lambda body type mismatch: expected {}, found {}",
+                    expected, body_ty
+                ),
+            ));
+        }
+
+        Ok(lambda_ty(match expected {
+            ResolvedType::Unknown => default_literals(body_ty),
+            annotated => annotated,
+        }))
+    }
+
+    /// Check a call to a prelude function such as `len` or `min`
+    fn check_builtin_call(
+        &mut self,
+        builtin: Builtin,
+        name: &str,
+        args: &[Expr],
+        span: Span,
+    ) -> Result<ResolvedType> {
+        if args.len() != builtin.arity() {
+            return Err(Error::new(
+                ErrorKind::TypeMismatch,
+                span,
+                format!(
+                    "Function '{}' takes {} argument(s), found {}",
+                    name,
+                    builtin.arity(),
+                    args.len()
+                ),
+            ));
+        }
+
+        let arg_types = args
+            .iter()
+            .map(|arg| self.check_expr(arg))
+            .collect::<Result<Vec<_>>>()?;
+
+        match builtin {
+            Builtin::Len => match &arg_types[0] {
+                ResolvedType::Array(..)
+                | ResolvedType::Primitive(PrimitiveType::String)
+                | ResolvedType::Unknown => Ok(ResolvedType::Primitive(PrimitiveType::U64)),
+                other => Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    args[0].span,
+                    format!("'len' requires an array or str, found {}", other),
+                )),
+            },
+            Builtin::Min | Builtin::Max | Builtin::Abs => {
+                for (arg, ty) in args.iter().zip(&arg_types) {
+                    if !self.is_numeric(ty) && *ty != ResolvedType::Unknown {
+                        return Err(Error::new(
+                            ErrorKind::TypeMismatch,
+                            arg.span,
+                            format!("'{}' requires numeric arguments, found {}", name, ty),
+                        ));
+                    }
+                }
+
+                if arg_types.len() == 2 && !self.types_compatible(&arg_types[0], &arg_types[1]) {
+                    return Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!(
+                            "'{}' requires arguments of the same type, found {} and {}",
+                            name, arg_types[0], arg_types[1]
+                        ),
+                    ));
+                }
+
+                Ok(arg_types[0].clone())
+            }
+        }
+    }
+
+    /// Check a variant constructor call like `Some(5)`, inferring the enum's type arguments
+    fn check_variant_constructor(
+        &mut self,
+        name: &str,
+        enum_name: &str,
+        generics: &[String],
+        payload: &[ResolvedType],
+        args: &[Expr],
+        span: Span,
+    ) -> Result<ResolvedType> {
+        if args.len() != payload.len() {
+            return Err(Error::new(
+                ErrorKind::TypeMismatch,
+                span,
+                format!(
+                    "Variant '{}' takes {} argument(s), found {}",
+                    name,
+                    payload.len(),
+                    args.len()
+                ),
+            ));
+        }
+
+        let mut subst = HashMap::new();
+        for (arg, expected) in args.iter().zip(payload) {
+            let arg_ty = self.check_expr(arg)?;
+            bind_type_vars(expected, &arg_ty, &mut subst);
+
+            let expected = instantiate(expected, &subst);
+            if !self.types_compatible(&arg_ty, &expected) {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    arg.span,
+                    format!("Variant '{}' expects {}, found {}", name, expected, arg_ty),
+                ));
+            }
+        }
+
+        Ok(instantiate_enum(enum_name, generics, &subst))
+    }
+
+    /// Check call arguments against the callee's parameter types, inferring
+    /// the type parameters they mention. Function values, such as a named
+    /// function passed to a higher-order function, are compared by signature.
+    /// `callee` names the function in messages. Returns what each type
+    /// parameter was inferred to be.
+    /// Bind a call's turbofish arguments to the callee's type parameters
+    fn explicit_type_args(
+        &self,
+        callee: &str,
+        generics: &[String],
+        type_args: &[Type],
+        span: Span,
+    ) -> Result<HashMap<String, ResolvedType>> {
+        if type_args.is_empty() {
+            return Ok(HashMap::new());
+        }
+        if type_args.len() != generics.len() {
+            return Err(Error::new(
+                ErrorKind::TypeMismatch,
+                span,
+                format!(
+                    "{} takes {} type argument(s), found {}",
+                    callee,
+                    generics.len(),
+                    type_args.len()
+                ),
+            ));
+        }
+        generics
+            .iter()
+            .zip(type_args)
+            .map(|(name, ty)| Ok((name.clone(), self.resolve_type(ty)?)))
+            .collect()
+    }
+
+    /// Type arguments on variant constructors and prelude functions aren't
+    /// bound to anything yet
+    fn reject_type_args(&self, name: &str, type_args: &[Type], span: Span) -> Result<()> {
+        if type_args.is_empty() {
+            return Ok(());
+        }
+        Err(Error::new(
+            ErrorKind::SemanticError,
+            span,
+            format!("Type arguments on '{}' aren't supported yet", name),
+        ))
+    }
+
+    fn check_call_args(
+        &mut self,
+        callee: &str,
+        params: &[ResolvedType],
+        required: usize,
+        args: &[Expr],
+        span: Span,
+    ) -> Result<HashMap<String, ResolvedType>> {
+        if args.len() < required || args.len() > params.len() {
+            let expected = if required == params.len() {
+                required.to_string()
+            } else {
+                format!("{} to {}", required, params.len())
+            };
+            return Err(Error::new(
+                ErrorKind::TypeMismatch,
+                span,
+                format!(
+                    "{} takes {} argument(s), found {}",
+                    callee,
+                    expected,
+                    args.len()
+                ),
+            ));
+        }
+
+        let arg_types = args
+            .iter()
+            .map(|arg| self.check_expr(arg))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Infer type parameters from the arguments. A literal's type gives
+        // way to a concrete one, so `max(1, n)` takes n's width.
+        let mut subst = HashMap::new();
+        for ((arg, arg_ty), expected) in args.iter().zip(&arg_types).zip(params) {
+            let mut found = HashMap::new();
+            bind_type_vars(expected, arg_ty, &mut found);
+            // Each `impl Trait` parameter is its own anonymous type, so
+            // those sharing a bound don't have to agree
+            found.retain(|var, _| !var.starts_with("impl "));
+            for (var, ty) in found {
+                match subst.get(&var) {
+                    Some(bound) if !self.types_compatible(bound, &ty) => {
+                        return Err(Error::new(
+                            ErrorKind::TypeMismatch,
+                            arg.span,
+                            format!(
+                                "Conflicting types for type parameter '{}' of {}: {} and {}",
+                                var, callee, bound, ty
+                            ),
+                        ));
+                    }
+                    Some(bound)
+                        if *bound != ResolvedType::Unknown && !self.literal_adapts(bound, &ty) => {}
+                    _ => {
+                        subst.insert(var, ty);
+                    }
+                }
+            }
+        }
+
+        for (i, ((arg, arg_ty), expected)) in args.iter().zip(&arg_types).zip(params).enumerate() {
+            let expected = &instantiate(expected, &subst);
+
+            // `impl Trait` parameters accept any argument until trait impls are tracked
+            let adapts = matches!(expected, ResolvedType::TypeVar(_));
+            if !adapts && !self.types_compatible(arg_ty, expected) {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    arg.span,
+                    format!(
+                        "Argument {} of {} expects {}, found {}",
+                        i + 1,
+                        callee,
+                        expected,
+                        arg_ty
+                    ),
+                ));
+            }
+            self.settle_unknown(arg, expected);
+        }
+
+        Ok(subst)
+    }
+
+    /// Check a struct literal: every declared field is given exactly once, and
+    /// each value matches the field's declared type
+    fn check_struct_lit(
+        &mut self,
+        name: &Spanned<String>,
+        fields: &[(Spanned<String>, Expr)],
+    ) -> Result<ResolvedType> {
+        let path = self.type_key(&name.value);
+        let Some(declared) = self.structs.get(&path).cloned() else {
+            return Err(Error::new(
+                ErrorKind::SemanticError,
+                name.span,
+                format!("Unknown struct '{}'", name.value),
+            ));
+        };
+
+        let mut seen: Vec<&str> = Vec::new();
+        for (field, value) in fields {
+            if seen.contains(&field.value.as_str()) {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    field.span,
+                    format!("Field '{}' is specified more than once", field.value),
+                ));
+            }
+            seen.push(&field.value);
+
+            let Some((_, expected)) = declared.iter().find(|(n, _)| *n == field.value) else {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    field.span,
+                    format!("Struct '{}' has no field '{}'", name.value, field.value),
+                ));
+            };
+
+            let value_ty = self.check_expr(value)?;
+            if !self.types_compatible(&value_ty, expected) {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    value.span,
+                    format!(
+                        "Field '{}' of '{}' expects {}, found {}",
+                        field.value, name.value, expected, value_ty
+                    ),
+                ));
+            }
+        }
+
+        let missing: Vec<&str> = declared
+            .iter()
+            .map(|(n, _)| n.as_str())
+            .filter(|n| !seen.contains(n))
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::new(
+                ErrorKind::SemanticError,
+                name.span,
+                format!(
+                    "Missing field(s) {} in struct literal of '{}'",
+                    missing
+                        .iter()
+                        .map(|n| format!("'{}'", n))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    name.value
+                ),
+            ));
+        }
+
+        Ok(ResolvedType::UserDefined(path))
+    }
+
+    /// Bind the names introduced by a pattern matched against a value of type `ty`
+    fn bind_pattern(&mut self, pattern: &Pattern, ty: &ResolvedType, span: Span) -> Result<()> {
+        match pattern {
+            Pattern::Wildcard => Ok(()),
+            Pattern::Literal(literal) => {
+                let (kind, expected, matches) = match literal {
+                    Literal::Integer(_) => ("Integer", "an integer", self.is_integer(ty)),
+                    Literal::Float(_) => (
+                        "Float",
+                        "a float",
+                        matches!(
+                            ty,
+                            ResolvedType::Primitive(PrimitiveType::F32 | PrimitiveType::F64)
+                        ),
+                    ),
+                    Literal::String(_) => (
+                        "String",
+                        "a str",
+                        *ty == ResolvedType::Primitive(PrimitiveType::String),
+                    ),
+                    Literal::Char(_) => (
+                        "Char",
+                        "a char",
+                        *ty == ResolvedType::Primitive(PrimitiveType::Char),
+                    ),
+                    Literal::Bool(_) => (
+                        "Bool",
+                        "a bool",
+                        *ty == ResolvedType::Primitive(PrimitiveType::Bool),
+                    ),
+                };
+                if matches || *ty == ResolvedType::Unknown {
+                    Ok(())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!("{} pattern requires {}, found {}", kind, expected, ty),
+                    ))
+                }
+            }
+            Pattern::Ident(name) => {
+                // A bare unit variant such as `None` matches rather than binds
+                if let Some(Symbol::Variant { .. }) = self.symbols.lookup(name) {
+                    return self.bind_variant_pattern(name, &[], ty, span);
+                }
+
+                self.symbols
+                    .define(
+                        name.clone(),
+                        Symbol::Variable {
+                            ty: ty.clone(),
+                            mutable: false,
+                            initialized: true,
+                            span,
+                        },
+                    )
+                    .map_err(|e| Error::new(ErrorKind::SemanticError, span, e))
+            }
+            Pattern::Tuple(patterns) => match ty {
+                ResolvedType::Tuple(types) if types.len() == patterns.len() => {
+                    for (pattern, ty) in patterns.iter().zip(types) {
+                        self.bind_pattern(pattern, ty, span)?;
+                    }
+                    Ok(())
+                }
+                ResolvedType::Unknown => {
+                    for pattern in patterns {
+                        self.bind_pattern(pattern, &ResolvedType::Unknown, span)?;
+                    }
+                    Ok(())
+                }
+                _ => Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    span,
+                    format!(
+                        "Tuple pattern with {} element(s) cannot match {}",
+                        patterns.len(),
+                        ty
+                    ),
+                )),
+            },
+            Pattern::Variant { name, patterns } => {
+                self.bind_variant_pattern(name, patterns, ty, span)
+            }
+            Pattern::Struct { name, fields } => self.bind_struct_pattern(name, fields, ty, span),
+            Pattern::Range { .. } => {
+                if self.is_integer(ty) || *ty == ResolvedType::Unknown {
+                    Ok(())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!("Range pattern requires an integer, found {}", ty),
+                    ))
+                }
+            }
+            Pattern::Or(alternatives) => {
+                // Bind each alternative on its own; all must agree on the
+                // names they bind and on their types
+                let mut bound: Option<HashMap<String, ResolvedType>> = None;
+                for alternative in alternatives {
+                    self.symbols.enter_scope();
+                    let result = self.bind_pattern(alternative, ty, span);
+                    let scope = self.symbols.take_scope();
+                    result?;
+
+                    let names: HashMap<String, ResolvedType> = scope
+                        .into_iter()
+                        .filter_map(|(name, symbol)| match symbol {
+                            Symbol::Variable { ty, .. } => Some((name, ty)),
+                            _ => None,
+                        })
+                        .collect();
+                    let Some(first) = &bound else {
+                        bound = Some(names);
+                        continue;
+                    };
+
+                    let mut all: Vec<&String> = first.keys().chain(names.keys()).collect();
+                    all.sort();
+                    for name in all {
+                        match (first.get(name), names.get(name)) {
+                            (Some(a), Some(b)) if a == b => {}
+                            (Some(a), Some(b)) => {
+                                return Err(Error::new(
+                                    ErrorKind::TypeMismatch,
+                                    span,
+                                    format!(
+                                        "Variable '{}' is {} in one alternative and {} in another",
+                                        name, a, b
+                                    ),
+                                ));
+                            }
+                            _ => {
+                                return Err(Error::new(
+                                    ErrorKind::SemanticError,
+                                    span,
+                                    format!(
+                                        "Variable '{}' is not bound in every alternative",
+                                        name
+                                    ),
+                                ));
+                            }
+                        }
+                    }
+                }
+
+                for (name, ty) in bound.unwrap_or_default() {
+                    self.symbols
+                        .define(
+                            name,
+                            Symbol::Variable {
+                                ty,
+                                mutable: false,
+                                initialized: true,
+                                span,
+                            },
+                        )
+                        .map_err(|e| Error::new(ErrorKind::SemanticError, span, e))?;
+                }
+                Ok(())
+            }
+            Pattern::Binding { name, pattern } => {
+                self.bind_pattern(pattern, ty, span)?;
+                self.symbols
+                    .define(
+                        name.value.clone(),
+                        Symbol::Variable {
+                            ty: ty.clone(),
+                            mutable: false,
+                            initialized: true,
+                            span: name.span,
+                        },
+                    )
+                    .map_err(|e| Error::new(ErrorKind::SemanticError, name.span, e))
+            }
+        }
+    }
+
+    /// Bind the payload patterns of an enum variant pattern like `Some(x)`
+    fn bind_variant_pattern(
+        &mut self,
+        name: &str,
+        patterns: &[Pattern],
+        ty: &ResolvedType,
+        span: Span,
+    ) -> Result<()> {
+        let (payload, names) = self.variant_payload(name, ty, span)?;
+
+        if !names.is_empty() {
+            return Err(Error::new(
+                ErrorKind::SemanticError,
+                span,
+                format!(
+                    "Variant '{}' has named fields; match it with {} {{ ... }}",
+                    name, name
+                ),
+            ));
+        }
+
+        if patterns.len() != payload.len() {
+            return Err(Error::new(
+                ErrorKind::TypeMismatch,
+                span,
+                format!(
+                    "Variant '{}' has {} field(s), pattern has {}",
+                    name,
+                    payload.len(),
+                    patterns.len()
+                ),
+            ));
+        }
+
+        for (pattern, field_ty) in patterns.iter().zip(&payload) {
+            self.bind_pattern(pattern, field_ty, span)?;
+        }
+
+        Ok(())
+    }
+
+    /// Bind the field patterns of a struct or struct variant pattern like
+    /// `Point { x, y }`. Fields left out of the pattern match anything.
+    fn bind_struct_pattern(
+        &mut self,
+        name: &str,
+        fields: &[(Spanned<String>, Pattern)],
+        ty: &ResolvedType,
+        span: Span,
+    ) -> Result<()> {
+        let path = self.type_key(name);
+        let (payload, names): (Vec<_>, Vec<_>) = match self.structs.get(&path) {
+            Some(declared) => {
+                let matches = match ty {
+                    ResolvedType::UserDefined(ty_name) => *ty_name == path,
+                    ty => *ty == ResolvedType::Unknown,
+                };
+                if !matches {
+                    return Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!("Pattern '{}' matches {}, found {}", name, name, ty),
+                    ));
+                }
+                declared.iter().map(|(n, t)| (t.clone(), n.clone())).unzip()
+            }
+            None => self.variant_payload(name, ty, span)?,
+        };
+
+        if names.is_empty() && !payload.is_empty() {
+            return Err(Error::new(
+                ErrorKind::SemanticError,
+                span,
+                format!(
+                    "Variant '{}' has no named fields; match it with {}(...)",
+                    name, name
+                ),
+            ));
+        }
+
+        for (i, (field, pattern)) in fields.iter().enumerate() {
+            if fields[..i]
+                .iter()
+                .any(|(seen, _)| seen.value == field.value)
+            {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    field.span,
+                    format!("Field '{}' appears more than once in pattern", field.value),
+                ));
+            }
+            let Some(slot) = names.iter().position(|n| *n == field.value) else {
+                return Err(Error::new(
+                    ErrorKind::SemanticError,
+                    field.span,
+                    format!("Variant '{}' has no field '{}'", name, field.value),
+                ));
+            };
+            self.bind_pattern(pattern, &payload[slot], field.span)?;
+        }
+
+        Ok(())
+    }
+
+    /// The payload types of variant `name` as matched against a value of type
+    /// `ty`, with the enum's type arguments filled in, and its field names
+    fn variant_payload(
+        &self,
+        name: &str,
+        ty: &ResolvedType,
+        span: Span,
+    ) -> Result<(Vec<ResolvedType>, Vec<String>)> {
+        let Some(Symbol::Variant {
+            enum_name,
+            generics,
+            payload,
+            fields,
+            ..
+        }) = self.symbols.lookup(name).cloned()
+        else {
+            return Err(Error::new(
+                ErrorKind::SemanticError,
+                span,
+                format!("Unknown variant '{}' in pattern", name),
+            ));
+        };
+
+        // Recover the enum's type arguments from the scrutinee's type
+        let mut subst = HashMap::new();
+        match ty {
+            ResolvedType::Generic {
+                name: ty_name,
+                args,
+            } if *ty_name == enum_name => {
+                for (param, arg) in generics.iter().zip(args) {
+                    subst.insert(param.clone(), arg.clone());
+                }
+            }
+            ResolvedType::UserDefined(ty_name) if *ty_name == enum_name => {}
+            ResolvedType::Unknown => {}
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    span,
+                    format!("Pattern '{}' matches {}, found {}", name, enum_name, ty),
+                ));
+            }
+        }
+
+        let payload = payload.iter().map(|t| instantiate(t, &subst)).collect();
+        Ok((payload, fields))
+    }
+
+    /// Check the `?` operator: the operand must be an Option/Result and the
+    /// enclosing function must return the same kind so the failure can propagate
+    fn check_try(&self, operand: &ResolvedType, span: Span) -> Result<ResolvedType> {
+        let (enum_name, args) = match operand {
+            ResolvedType::Generic { name, args } if name == "Option" || name == "Result" => {
+                (name, args)
+            }
+            ResolvedType::Unknown => return Ok(ResolvedType::Unknown),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    span,
+                    format!(
+                        "The '?' operator requires an Option or Result, found {}",
+                        operand
+                    ),
+                ));
+            }
+        };
+
+        match &self.current_function_return {
+            Some(ResolvedType::Generic {
+                name: return_name,
+                args: return_args,
+            }) if return_name == enum_name => {
+                if let (Some(error), Some(return_error)) = (args.get(1), return_args.get(1)) {
+                    if !self.types_compatible(error, return_error) {
+                        return Err(Error::new(
+                            ErrorKind::TypeMismatch,
+                            span,
+                            format!(
+                                "The '?' operator propagates error type {}, but the function returns errors of type {}",
+                                error, return_error
+                            ),
+                        ));
+                    }
+                }
+            }
+            Some(ResolvedType::Unknown) | None => {}
+            Some(other) => {
+                return Err(Error::new(
+                    ErrorKind::TypeMismatch,
+                    span,
+                    format!(
+                        "The '?' operator on {} requires the function to return {}, found {}",
+                        enum_name, enum_name, other
+                    ),
+                ));
+            }
+        }
+
+        Ok(args.first().cloned().unwrap_or(ResolvedType::Unknown))
+    }
+
+    /// Check binary operation type compatibility
+    fn check_binary_op(
+        &self,
+        op: BinOp,
+        left: &ResolvedType,
+        right: &ResolvedType,
+        span: Span,
+    ) -> Result<ResolvedType> {
+        use BinOp::*;
+        use PrimitiveType::*;
+
+        // No implicit widening: mixed integer widths need an explicit cast
+        if let (ResolvedType::Primitive(l), ResolvedType::Primitive(r)) = (left, right) {
+            if l != r && self.is_integer(left) && self.is_integer(right) {
+                if let Add | Sub | Mul | Div | Rem | BitAnd | BitOr | BitXor | Eq | Ne | Lt | Le
+                | Gt | Ge = op
+                {
+                    return Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!(
+                            "Mismatched integer types {} and {}; convert one side explicitly, e.g. `as {}`",
+                            l.name(),
+                            r.name(),
+                            r.name()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        match op {
+            Add | Sub | Mul | Div | Rem => {
+                // Arithmetic operators require numeric types
+                if self.is_numeric(left) && self.types_compatible(left, right) {
+                    Ok(left.clone())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!(
+                            "Arithmetic operation requires numeric types, found {} and {}",
+                            left, right
+                        ),
+                    ))
+                }
+            }
+            Eq | Ne | Lt | Le | Gt | Ge => {
+                // Comparison operators return bool
+                if self.types_compatible(left, right) {
+                    Ok(ResolvedType::Primitive(Bool))
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!(
+                            "Comparison requires compatible types, found {} and {}",
+                            left, right
+                        ),
+                    ))
+                }
+            }
+            And | Or => {
+                // Logical operators require bool
+                if *left == ResolvedType::Primitive(Bool) && *right == ResolvedType::Primitive(Bool)
+                {
+                    Ok(ResolvedType::Primitive(Bool))
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!(
+                            "Logical operation requires bool, found {} and {}",
+                            left, right
+                        ),
+                    ))
+                }
+            }
+            BitAnd | BitOr | BitXor => {
+                if self.is_integer(left) && self.types_compatible(left, right) {
+                    Ok(left.clone())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!(
+                            "Bitwise '{}' requires integer operands, found {} and {}",
+                            op.symbol(),
+                            left,
+                            right
+                        ),
+                    ))
+                }
+            }
+            Shl | Shr => {
+                // The shift amount may be any integer width
+                if self.is_integer(left) && self.is_integer(right) {
+                    Ok(left.clone())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!(
+                            "Shift '{}' requires integer operands, found {} and {}",
+                            op.symbol(),
+                            left,
+                            right
+                        ),
+                    ))
+                }
+            }
+            _ => Ok(ResolvedType::Unknown), // TODO: Implement other operators
+        }
+    }
+
+    /// Check unary operation type compatibility
+    fn check_unary_op(&self, op: UnOp, operand: &ResolvedType, span: Span) -> Result<ResolvedType> {
+        use PrimitiveType::*;
+        use UnOp::*;
+
+        match op {
+            Neg => {
+                if self.is_numeric(operand) {
+                    Ok(operand.clone())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!("Negation requires numeric type, found {}", operand),
+                    ))
+                }
+            }
+            Not => {
+                if *operand == ResolvedType::Primitive(Bool) {
+                    Ok(ResolvedType::Primitive(Bool))
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!("Logical not requires bool, found {}", operand),
+                    ))
+                }
+            }
+            BitNot => {
+                if self.is_integer(operand) {
+                    Ok(operand.clone())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::TypeMismatch,
+                        span,
+                        format!("Bitwise not requires integer type, found {}", operand),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Fold an integer expression built from literals, constants, and arithmetic
+    fn const_int(&self, expr: &Expr) -> Option<i64> {
+        match self.const_value(expr)? {
+            Literal::Integer(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Fold a constant expression, if it is one
+    fn const_value(&self, expr: &Expr) -> Option<Literal> {
+        eval_const(expr, &self.symbols).ok()
+    }
+
+    /// Resolve AST type to semantic type
+    fn resolve_type(&self, ty: &Type) -> Result<ResolvedType> {
+        match ty {
+            Type::Simple(name) => match name.value.as_str() {
+                "i8" => Ok(ResolvedType::Primitive(PrimitiveType::I8)),
+                "i16" => Ok(ResolvedType::Primitive(PrimitiveType::I16)),
+                "i32" => Ok(ResolvedType::Primitive(PrimitiveType::I32)),
+                "i64" => Ok(ResolvedType::Primitive(PrimitiveType::I64)),
+                "isize" => Ok(ResolvedType::Primitive(PrimitiveType::Isize)),
+                "u8" => Ok(ResolvedType::Primitive(PrimitiveType::U8)),
+                "u16" => Ok(ResolvedType::Primitive(PrimitiveType::U16)),
+                "u32" => Ok(ResolvedType::Primitive(PrimitiveType::U32)),
+                "u64" => Ok(ResolvedType::Primitive(PrimitiveType::U64)),
+                "usize" => Ok(ResolvedType::Primitive(PrimitiveType::Usize)),
+                "f32" => Ok(ResolvedType::Primitive(PrimitiveType::F32)),
+                "f64" => Ok(ResolvedType::Primitive(PrimitiveType::F64)),
+                "bool" => Ok(ResolvedType::Primitive(PrimitiveType::Bool)),
+                "char" => Ok(ResolvedType::Primitive(PrimitiveType::Char)),
+                "str" => Ok(ResolvedType::Primitive(PrimitiveType::String)),
+                _ => match self.symbols.lookup(&name.value) {
+                    Some(Symbol::Type { path, .. }) => Ok(ResolvedType::UserDefined(path.clone())),
+                    None => Ok(ResolvedType::UserDefined(name.value.clone())),
+                    Some(_) => Err(Error::new(
+                        ErrorKind::SemanticError,
+                        name.span,
+                        format!("Expected a type, found value '{}'", name.value),
+                    )),
+                },
+            },
+            Type::Generic { name, args } => {
+                let arity = match name.value.as_str() {
+                    "Option" => Some(1),
+                    "Result" => Some(2),
+                    _ => None,
+                };
+                if let Some(arity) = arity.filter(|&n| n != args.len()) {
+                    return Err(Error::new(
+                        ErrorKind::SemanticError,
+                        name.span,
+                        format!(
+                            "'{}' takes {} type argument{}, found {}",
+                            name.value,
+                            arity,
+                            if arity == 1 { "" } else { "s" },
+                            args.len()
+                        ),
+                    ));
+                }
+                let resolved: Result<Vec<_>> = args.iter().map(|t| self.resolve_type(t)).collect();
+                Ok(ResolvedType::Generic {
+                    name: name.value.clone(),
+                    args: resolved?,
+                })
+            }
+            Type::Ref(inner) => {
+                let inner_ty = self.resolve_type(inner)?;
+                Ok(ResolvedType::Reference(Box::new(inner_ty)))
+            }
+            Type::Own(inner) => {
+                let inner_ty = self.resolve_type(inner)?;
+                Ok(ResolvedType::Owned(Box::new(inner_ty)))
+            }
+            Type::Tuple(types) => {
+                let resolved: Result<Vec<_>> = types.iter().map(|t| self.resolve_type(t)).collect();
+                Ok(ResolvedType::Tuple(resolved?))
+            }
+            Type::Array(elem_ty, size) => {
+                let elem = self.resolve_type(elem_ty)?;
+                let size = size.as_ref().map(|e| self.array_length(e)).transpose()?;
+                Ok(ResolvedType::Array(Box::new(elem), size))
+            }
+            Type::ImplTrait(bounds) => Ok(ResolvedType::Opaque(bound_names(bounds))),
+            Type::Function {
+                params,
+                return_type,
+            } => {
+                let params = params
+                    .iter()
+                    .map(|t| self.resolve_type(t))
+                    .collect::<Result<Vec<_>>>()?;
+                // Match the unit return of functions declared without `->`
+                let return_type = match return_type.as_ref() {
+                    Type::Tuple(types) if types.is_empty() => {
+                        ResolvedType::Primitive(PrimitiveType::Unit)
+                    }
+                    ty => self.resolve_type(ty)?,
+                };
+                Ok(ResolvedType::Function {
+                    params,
+                    return_type: Box::new(return_type),
+                })
+            }
+            Type::Infer => Ok(ResolvedType::Unknown),
+        }
+    }
+
+    /// Fold an array length to a positive constant
+    fn array_length(&self, size: &Expr) -> Result<usize> {
+        match eval_const(size, &self.symbols) {
+            Ok(Literal::Integer(n)) if n > 0 => Ok(n as usize),
+            Ok(Literal::Integer(n)) => Err(Error::new(
+                ErrorKind::SemanticError,
+                size.span,
+                format!("Array length must be positive, found {}", n),
+            )),
+            Ok(_) => Err(Error::new(
+                ErrorKind::SemanticError,
+                size.span,
+                "Array length must be a constant integer expression",
+            )),
+            Err(e) => Err(Error::new(
+                ErrorKind::SemanticError,
+                e.span,
+                format!(
+                    "Array length must be a constant integer expression: {}",
+                    e.message
+                ),
+            )),
+        }
+    }
+}
+
+/// Whether every path through a block ends in a value or leaves the function.
+/// A block-like expression on the last line parses as a statement, so it
+/// counts as the block's value.
+fn block_yields(block: &Block) -> bool {
+    if block.stmts.iter().any(stmt_diverges) {
+        return true;
+    }
+    match (&block.expr, block.stmts.last()) {
+        (Some(expr), _) => expr_yields(expr),
+        (None, Some(Stmt::Expr(expr))) => {
+            matches!(
+                expr.kind,
+                ExprKind::If { .. } | ExprKind::Match { .. } | ExprKind::Block(_)
+            ) && expr_yields(expr)
+        }
+        _ => false,
+    }
+}
+
+/// Whether an expression in value position produces a value on every path
+fn expr_yields(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::If {
+            then_block,
+            else_block,
+            ..
+        } => else_block
+            .as_ref()
+            .is_some_and(|else_block| block_yields(then_block) && block_yields(else_block)),
+        ExprKind::Match { arms, .. } => arms.iter().all(|arm| expr_yields(&arm.body)),
+        ExprKind::Block(block) => block_yields(block),
+        _ => true,
+    }
+}
+
+/// Whether every path through a block leaves the function
+fn block_diverges(block: &Block) -> bool {
+    block.stmts.iter().any(stmt_diverges) || block.expr.as_deref().is_some_and(expr_diverges)
+}
+
+fn stmt_diverges(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Return(_) => true,
+        Stmt::Loop { body } => !breaks_out(body),
+        Stmt::Expr(expr) => expr_diverges(expr),
+        _ => false,
+    }
+}
+
+fn expr_diverges(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::If {
+            then_block,
+            else_block,
+            ..
+        } => else_block
+            .as_ref()
+            .is_some_and(|else_block| block_diverges(then_block) && block_diverges(else_block)),
+        ExprKind::Match { arms, .. } => arms.iter().all(|arm| expr_diverges(&arm.body)),
+        ExprKind::Block(block) => block_diverges(block),
+        _ => false,
+    }
+}
+
+/// Whether a loop body contains a `break` for that loop, rather than a nested one
+fn breaks_out(body: &Block) -> bool {
+    fn in_expr(expr: &Expr) -> bool {
+        match &expr.kind {
+            ExprKind::If {
+                then_block,
+                else_block,
+                ..
+            } => breaks_out(then_block) || else_block.as_ref().is_some_and(breaks_out),
+            ExprKind::Match { arms, .. } => arms.iter().any(|arm| in_expr(&arm.body)),
+            ExprKind::Block(block) => breaks_out(block),
+            _ => false,
+        }
+    }
+
+    body.stmts.iter().any(|stmt| match stmt {
+        Stmt::Break(_) => true,
+        Stmt::Expr(expr) => in_expr(expr),
+        _ => false,
+    }) || body.expr.as_deref().is_some_and(in_expr)
+}
+
+/// Give unconstrained literal types their defaults: i32 and f64
+fn default_literals(ty: ResolvedType) -> ResolvedType {
+    match ty {
+        ResolvedType::IntLiteral => ResolvedType::Primitive(PrimitiveType::I32),
+        ResolvedType::FloatLiteral => ResolvedType::Primitive(PrimitiveType::F64),
+        ResolvedType::Tuple(types) => {
+            ResolvedType::Tuple(types.into_iter().map(default_literals).collect())
+        }
+        ResolvedType::Array(elem, size) => {
+            ResolvedType::Array(Box::new(default_literals(*elem)), size)
+        }
+        ResolvedType::Generic { name, args } => ResolvedType::Generic {
+            name,
+            args: args.into_iter().map(default_literals).collect(),
+        },
+        ty => ty,
+    }
+}
+
+/// Whether a condition is, or is `&&`-chained with, a `let` pattern test
+fn contains_let(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Let { .. } => true,
+        ExprKind::Binary {
+            op: BinOp::And,
+            left,
+            right,
+        } => contains_let(left) || contains_let(right),
+        _ => false,
+    }
+}
+
+/// Number of parameters a call must pass; the rest have defaults
+fn required_params(params: &[Param]) -> usize {
+    params.iter().take_while(|p| p.default.is_none()).count()
+}
+
+/// A path as written: `a::b::c`
+fn path_name(path: &[Spanned<String>]) -> String {
+    let names: Vec<&str> = path.iter().map(|s| s.value.as_str()).collect();
+    names.join("::")
+}
+
+fn bound_names(bounds: &[Spanned<String>]) -> Vec<String> {
+    bounds.iter().map(|b| b.value.clone()).collect()
+}
+
+/// Instantiate a generic enum type from the inferred type arguments
+fn instantiate_enum(
+    enum_name: &str,
+    generics: &[String],
+    subst: &HashMap<String, ResolvedType>,
+) -> ResolvedType {
+    if generics.is_empty() {
+        return ResolvedType::UserDefined(enum_name.to_string());
+    }
+
+    ResolvedType::Generic {
+        name: enum_name.to_string(),
+        args: generics
+            .iter()
+            .map(|g| instantiate(&ResolvedType::TypeVar(g.clone()), subst))
+            .collect(),
+    }
+}
+
+/// Turn references to a function's type parameters, which resolve as
+/// user-defined types, into type variables to infer at each call
+fn type_params_to_vars(ty: ResolvedType, generics: &[String]) -> ResolvedType {
+    if generics.is_empty() {
+        return ty;
+    }
+    let vars = generics
+        .iter()
+        .map(|g| (g.clone(), ResolvedType::TypeVar(g.clone())))
+        .collect();
+    substitute_named(&ty, &vars)
+}
+
+/// Replace user-defined types named in `subst`
+fn substitute_named(ty: &ResolvedType, subst: &HashMap<String, ResolvedType>) -> ResolvedType {
+    ty.map_leaves(&|t| match t {
+        ResolvedType::UserDefined(name) => subst.get(name).cloned(),
+        _ => None,
+    })
+}
+
+/// Replace type variables with their inferred types (Unknown if not inferred)
+fn instantiate(ty: &ResolvedType, subst: &HashMap<String, ResolvedType>) -> ResolvedType {
+    ty.map_leaves(&|t| match t {
+        ResolvedType::TypeVar(name) => {
+            Some(subst.get(name).cloned().unwrap_or(ResolvedType::Unknown))
+        }
+        _ => None,
+    })
+}
+
+/// Record what each type variable in `expected` stands for, given the `actual` type
+fn bind_type_vars(
+    expected: &ResolvedType,
+    actual: &ResolvedType,
+    subst: &mut HashMap<String, ResolvedType>,
+) {
+    match (expected, actual) {
+        (ResolvedType::TypeVar(name), _) => {
+            let unbound = matches!(subst.get(name), None | Some(ResolvedType::Unknown));
+            if unbound {
+                subst.insert(name.clone(), actual.clone());
+            }
+        }
+        (ResolvedType::Reference(e), ResolvedType::Reference(a))
+        | (ResolvedType::Owned(e), ResolvedType::Owned(a))
+        | (ResolvedType::Array(e, _), ResolvedType::Array(a, _)) => bind_type_vars(e, a, subst),
+        (ResolvedType::Tuple(es), ResolvedType::Tuple(as_))
+        | (ResolvedType::Generic { args: es, .. }, ResolvedType::Generic { args: as_, .. }) => {
+            for (e, a) in es.iter().zip(as_) {
+                bind_type_vars(e, a, subst);
+            }
+        }
+        (
+            ResolvedType::Function {
+                params: es,
+                return_type: e,
+            },
+            ResolvedType::Function {
+                params: as_,
+                return_type: a,
+            },
+        ) => {
+            for (e, a) in es.iter().zip(as_) {
+                bind_type_vars(e, a, subst);
+            }
+            bind_type_vars(e, a, subst);
+        }
+        _ => {}
+    }
+}
+
+impl TypeChecker {
+    /// Whether `ty` has an `impl` of every trait in `bounds`
+    fn implements_all(&self, ty: &ResolvedType, bounds: &[String]) -> bool {
+        let name = match default_literals(ty.clone()) {
+            ResolvedType::Opaque(known) => return bounds.iter().all(|b| known.contains(b)),
+            // A generic parameter's bounds are checked where it is instantiated
+            ResolvedType::TypeVar(_) => return true,
+            ResolvedType::Primitive(prim) => prim.name().to_string(),
+            ResolvedType::UserDefined(name) | ResolvedType::Generic { name, .. } => name,
+            _ => return false,
+        };
+        bounds
+            .iter()
+            .all(|bound| self.trait_impls.contains(&(name.clone(), bound.clone())))
+    }
+
+    /// Check if two types are compatible
+    fn types_compatible(&self, a: &ResolvedType, b: &ResolvedType) -> bool {
+        if a == b {
+            return true;
+        }
+
+        match (a, b) {
+            // Allow Unknown to be compatible with anything (for type inference)
+            (ResolvedType::Unknown, _) | (_, ResolvedType::Unknown) => true,
+
+            // A type stands in for an opaque `impl Trait` if it implements the bounds
+            (ResolvedType::Opaque(bounds), other) | (other, ResolvedType::Opaque(bounds)) => {
+                self.implements_all(other, bounds)
+            }
+
+            // An unsuffixed literal fits any type of its kind
+            (ResolvedType::IntLiteral, other) | (other, ResolvedType::IntLiteral) => {
+                self.is_integer(other)
+            }
+            (ResolvedType::FloatLiteral, other) | (other, ResolvedType::FloatLiteral) => matches!(
+                other,
+                ResolvedType::FloatLiteral
+                    | ResolvedType::Primitive(PrimitiveType::F32 | PrimitiveType::F64)
+            ),
+
+            // Generic instances are compatible argument by argument, so that
+            // `None` (Option<Unknown>) fits an Option<i32>
+            (
+                ResolvedType::Generic {
+                    name: a_name,
+                    args: a_args,
+                },
+                ResolvedType::Generic {
+                    name: b_name,
+                    args: b_args,
+                },
+            ) => {
+                a_name == b_name
+                    && a_args.len() == b_args.len()
+                    && a_args
+                        .iter()
+                        .zip(b_args)
+                        .all(|(a, b)| self.types_compatible(a, b))
+            }
+            (ResolvedType::Tuple(a_types), ResolvedType::Tuple(b_types)) => {
+                a_types.len() == b_types.len()
+                    && a_types
+                        .iter()
+                        .zip(b_types)
+                        .all(|(a, b)| self.types_compatible(a, b))
+            }
+            (ResolvedType::Array(a_elem, a_size), ResolvedType::Array(b_elem, b_size)) => {
+                a_size == b_size && self.types_compatible(a_elem, b_elem)
+            }
+            (ResolvedType::Reference(a), ResolvedType::Reference(b))
+            | (ResolvedType::Owned(a), ResolvedType::Owned(b)) => self.types_compatible(a, b),
+
+            // Function values match by signature
+            (
+                ResolvedType::Function {
+                    params: a_params,
+                    return_type: a_ret,
+                },
+                ResolvedType::Function {
+                    params: b_params,
+                    return_type: b_ret,
+                },
+            ) => {
+                a_params.len() == b_params.len()
+                    && a_params
+                        .iter()
+                        .zip(b_params)
+                        .all(|(a, b)| self.types_compatible(a, b))
+                    && self.types_compatible(a_ret, b_ret)
+            }
+
+            _ => false,
+        }
+    }
+
+    /// The type of a value that may come from either of two compatible
+    /// sources: a literal's type gives way to a known one, so
+    /// `if c { 1 } else { n }` has n's type
+    fn join_types(&self, first: ResolvedType, second: ResolvedType) -> ResolvedType {
+        if self.literal_adapts(&first, &second) {
+            second
+        } else {
+            first
+        }
+    }
+
+    /// Whether `ty` is a literal's type that `other`, a known type, can settle
+    fn literal_adapts(&self, ty: &ResolvedType, other: &ResolvedType) -> bool {
+        matches!(ty, ResolvedType::IntLiteral | ResolvedType::FloatLiteral)
+            && *other != ResolvedType::Unknown
+            && self.types_compatible(ty, other)
+    }
+
+    /// Check if type is numeric
     fn is_numeric(&self, ty: &ResolvedType) -> bool {
         matches!(
             ty,
@@ -710,30 +3546,1381 @@ impl TypeChecker {
                     | PrimitiveType::I16
                     | PrimitiveType::I32
                     | PrimitiveType::I64
+                    | PrimitiveType::Isize
                     | PrimitiveType::U8
                     | PrimitiveType::U16
                     | PrimitiveType::U32
                     | PrimitiveType::U64
+                    | PrimitiveType::Usize
                     | PrimitiveType::F32
                     | PrimitiveType::F64
+            ) | ResolvedType::IntLiteral
+                | ResolvedType::FloatLiteral
+        )
+    }
+
+    /// Check if type is integer
+    fn is_integer(&self, ty: &ResolvedType) -> bool {
+        matches!(
+            ty,
+            ResolvedType::Primitive(
+                PrimitiveType::I8
+                    | PrimitiveType::I16
+                    | PrimitiveType::I32
+                    | PrimitiveType::I64
+                    | PrimitiveType::Isize
+                    | PrimitiveType::U8
+                    | PrimitiveType::U16
+                    | PrimitiveType::U32
+                    | PrimitiveType::U64
+                    | PrimitiveType::Usize
+            ) | ResolvedType::IntLiteral
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn check(source: &str) -> Result<()> {
+        let tokens = Lexer::new(source).tokenize()?;
+        let module = Parser::new(tokens).parse_module()?;
+        TypeChecker::new().check_module(&module)
+    }
+
+    /// Check `body` as the body of `main`, after the `items` it uses
+    fn check_main(items: &str, body: &str) -> Result<()> {
+        check(&format!("{}fn main() {{\n{}\n}}", items, body))
+    }
+
+    #[test]
+    fn test_option_construction() {
+        assert!(check("fn main() { let x: Option<i32> = Some(5); }").is_ok());
+        assert!(check("fn main() { let x: Option<i32> = None; }").is_ok());
+
+        let err = check("fn main() { let x: Option<bool> = Some(5); }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_user_enum_construction() {
+        let shape = "enum Shape { Circle(f64), Rect(f64, f64), Empty }\n";
+        assert!(check_main(
+            shape,
+            "let a: Shape = Circle(1.5); let b: Shape = Rect(1.0, 2.0);"
+        )
+        .is_ok());
+        assert!(check_main(shape, "let e: Shape = Empty;").is_ok());
+
+        let err = check_main(shape, "let a = Circle(true);").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert_eq!(
+            err.message,
+            "Variant 'Circle' expects Primitive(F64), found Primitive(Bool)"
+        );
+
+        let err = check_main(shape, "let b = Rect(1.0);").unwrap_err();
+        assert_eq!(err.message, "Variant 'Rect' takes 2 argument(s), found 1");
+
+        let err = check_main(shape, "let e: i32 = Empty;").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_enum_variant_paths() {
+        let shape = "enum Shape { Circle(f64), Empty }\nmod geo { enum Dir { North, South } }\n";
+        assert!(check_main(
+            shape,
+            "let a: Shape = Shape::Circle(1.5); let e: Shape = Shape::Empty;"
+        )
+        .is_ok());
+        assert!(check_main(shape, "let n: Option<i32> = Option::None;").is_ok());
+        assert!(check_main(shape, "let d = geo::Dir::North;").is_ok());
+
+        // A local binding doesn't hide the variant behind its enum
+        assert!(check_main(shape, "let Empty = 1; let e: Shape = Shape::Empty;").is_ok());
+
+        let err = check_main(shape, "let e: i32 = Shape::Empty;").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check_main(shape, "let a = Shape::Circle(true);").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Variant 'Circle' expects Primitive(F64), found Primitive(Bool)"
+        );
+
+        let err = check_main(shape, "let s = Shape::Square;").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::UndefinedVariable);
+        assert_eq!(err.message, "Enum 'Shape' has no variant 'Square'");
+
+        let err = check_main(shape, "let s = Option::Empty;").unwrap_err();
+        assert_eq!(err.message, "Enum 'Option' has no variant 'Empty'");
+
+        let err = check_main(shape, "let s = nowhere::thing;").unwrap_err();
+        assert_eq!(err.message, "Unresolved path 'nowhere::thing'");
+    }
+
+    #[test]
+    fn test_option_match_binds_payload() {
+        let source = r#"
+fn unwrap_or_zero(x: Option<i32>) -> i32 {
+    match x {
+        Some(n) => n + 1,
+        None => 0
+    }
+}
+"#;
+        assert!(check(source).is_ok());
+
+        // `n` is bound to i32, so using it as a bool fails
+        let source = r#"
+fn f(x: Option<i32>) -> bool {
+    match x {
+        Some(n) => n and true,
+        None => false
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_binding_pattern_in_scope() {
+        let source = r#"
+fn digit(x: i32) -> i32 {
+    match x {
+        n @ 0..10 => n * 2,
+        _ => 0
+    }
+}
+"#;
+        assert!(check(source).is_ok());
+
+        let source = r#"
+fn f(x: bool) -> i32 {
+    match x {
+        n @ 0..10 => 1,
+        _ => 0
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_match_arms_share_a_type() {
+        let source = r#"
+fn f(x: Option<i32>) -> i32 {
+    let n = match x {
+        Some(v) if v > 0 => v,
+        Some(_) => { return 0; },
+        None => 1
+    }
+    let ok: bool = match n { 0 => false, _ => true }
+    n
+}
+"#;
+        assert!(check(source).is_ok());
+
+        let source = r#"
+fn f(n: i32) -> i32 {
+    match n {
+        0 => 1,
+        _ => "many"
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        // Reported at the arm that disagrees
+        assert_eq!(&source[err.span.start..err.span.end], "\"many\"");
+
+        let err = check("fn f(n: i32) -> i32 { match n { k if k => 1, _ => 0 } }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err.message.contains("Match guard must be bool"));
+    }
+
+    #[test]
+    fn test_or_pattern_alternatives_must_agree() {
+        let source = r#"
+fn f(p: (Option<i32>, i32)) -> i32 {
+    match p {
+        (Some(v), 0) | (None, v) => v + 1,
+        (_, 1 | 2 | 3) => 1,
+        (_, 4..=10) => 2,
+        _ => 0
+    }
+}
+"#;
+        assert!(check(source).is_ok());
+
+        let source = r#"
+fn f(x: Option<i32>) -> i32 {
+    match x {
+        Some(v) | None => 0
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert!(err.message.contains("not bound in every alternative"));
+
+        let source = r#"
+fn f(p: (i32, bool)) -> i32 {
+    match p {
+        (v, true) | (_, v) => 0
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_tuple_and_struct_patterns_bind_in_arm() {
+        let source = r#"
+struct Point { x: i32, y: i32 }
+fn f(p: Point, pair: (i32, bool)) -> i32 {
+    let a = match p {
+        Point { x, y: 0 } => x,
+        Point { x, y } => x + y
+    }
+    match pair {
+        (n, true) => n + a,
+        (_, false) => a
+    }
+}
+"#;
+        assert!(check(source).is_ok());
+
+        // `flag` is bound to bool, so adding it to an integer fails
+        let source = r#"
+fn f(pair: (i32, bool)) -> i32 {
+    match pair {
+        (n, flag) => n + flag
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let source = r#"
+struct Point { x: i32, y: i32 }
+struct Size { w: i32, h: i32 }
+fn f(s: Size) -> i32 {
+    match s {
+        Point { x, y } => x
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_struct_variant_pattern_binds_fields() {
+        let source = r#"
+enum Shape { Point { x: i32, y: i32 }, Label { text: str } }
+fn sum(s: Shape) -> i32 {
+    match s {
+        Point { x, y: height } => x + height,
+        Label { text } => 0
+    }
+}
+"#;
+        assert!(check(source).is_ok());
+
+        // `x` is bound to i32, so using it as a bool fails
+        let source = r#"
+enum Shape { Point { x: i32, y: i32 } }
+fn f(s: Shape) -> bool {
+    match s {
+        Point { x, y } => x and true
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let source = r#"
+enum Shape { Point { x: i32, y: i32 } }
+fn f(s: Shape) -> i32 {
+    match s {
+        Point { x, z } => x
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert!(err.message.contains("has no field 'z'"));
+
+        // Named fields can't be matched by position
+        let source = r#"
+enum Shape { Circle { r: i32 } }
+fn f(s: Shape) -> i32 {
+    match s {
+        Circle(r) => r
+    }
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(
+            err.message,
+            "Variant 'Circle' has named fields; match it with Circle { ... }"
+        );
+    }
+
+    #[test]
+    fn test_main_signature() {
+        assert!(check("fn main() {}").is_ok());
+        assert!(check("fn main() -> i32 { return 0; }").is_ok());
+        assert!(check("fn main(args: [str]) {}").is_ok());
+
+        let err = check("fn main(x: i32) {}").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        let err = check("fn main() -> bool { return true; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+    }
+
+    #[test]
+    fn test_function_arguments_match_by_signature() {
+        let source = r#"
+fn double(x: i32) -> i32 {
+    return x * 2;
+}
+fn apply(f: fn(i32) -> i32, x: i32) -> i32 {
+    return f(x);
+}
+fn main() {
+    let y = apply(double, 5);
+}
+"#;
+        assert!(check(source).is_ok());
+
+        let source = r#"
+fn negate(b: bool) -> bool {
+    return not b;
+}
+fn apply(f: fn(i32) -> i32, x: i32) -> i32 {
+    return f(x);
+}
+fn main() {
+    let y = apply(negate, 5);
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err
+            .message
+            .contains("Argument 1 of 'apply' expects Function"));
+    }
+
+    #[test]
+    fn test_call_argument_checks() {
+        assert!(check("fn main() { println(\"hi\"); }").is_ok());
+
+        let err = check("fn main() { println(\"a\", \"b\", \"c\"); }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert_eq!(err.message, "'println' takes 1 argument(s), found 3");
+
+        let err =
+            check("fn add(a: i32, b: i32) -> i32 { return a + b; }\nfn main() { let x = add(1); }")
+                .unwrap_err();
+        assert_eq!(err.message, "'add' takes 2 argument(s), found 1");
+
+        let source = "fn main() { println(1); }";
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert_eq!(&source[err.span.start..err.span.end], "1");
+    }
+
+    #[test]
+    fn test_field_access() {
+        let decl = "struct Point { x: i32, y: bool }\n";
+        assert!(check(&format!(
+            "{}fn f(p: Point) -> bool {{ let x: i32 = p.x; return p.y; }}",
+            decl
+        ))
+        .is_ok());
+
+        let err = check(&format!("{}fn f(p: Point) -> i32 {{ return p.y; }}", decl)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check(&format!("{}fn f(p: Point) -> i32 {{ return p.z; }}", decl)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(err.message, "Struct 'Point' has no field 'z'");
+
+        // Through a reference or an owned value
+        assert!(check(&format!("{}fn f(p: &Point) -> i32 {{ return p.x; }}", decl)).is_ok());
+        let err = check(&format!("{}fn f(p: &Point) -> i32 {{ return p.z; }}", decl)).unwrap_err();
+        assert_eq!(err.message, "Struct 'Point' has no field 'z'");
+        let err = check(&format!(
+            "{}fn f(p: own Point) -> i32 {{ return p.y; }}",
+            decl
+        ))
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_struct_literal_fields() {
+        let decl = "struct Point { x: i32, y: i32 }\n";
+        assert!(check(&format!(
+            "{}fn main() {{ let p = Point {{ x: 1, y: 2 }}; }}",
+            decl
+        ))
+        .is_ok());
+
+        let err = check(&format!(
+            "{}fn main() {{ let p = Point {{ x: 1 }}; }}",
+            decl
+        ))
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(
+            err.message,
+            "Missing field(s) 'y' in struct literal of 'Point'"
+        );
+
+        let err = check(&format!(
+            "{}fn main() {{ let p = Point {{ x: 1, y: true }}; }}",
+            decl
+        ))
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check(&format!(
+            "{}fn main() {{ let p = Point {{ x: 1, z: 2 }}; }}",
+            decl
+        ))
+        .unwrap_err();
+        assert_eq!(err.message, "Struct 'Point' has no field 'z'");
+    }
+
+    #[test]
+    fn test_import_from_nested_module() {
+        let math = "mod math { fn square(x: i32) -> i32 { return x * x; } }\n";
+
+        assert!(check(&format!(
+            "{}import math::square;\nfn main() {{ let y: i32 = square(3); }}",
+            math
+        ))
+        .is_ok());
+
+        assert!(check(&format!("{}fn main() {{ let y = square(3); }}", math)).is_err());
+
+        let source = format!("{}import math::cube;\nfn main() {{}}", math);
+        let err = check(&source).unwrap_err();
+        assert_eq!(err.message, "Unresolved import 'math::cube'");
+        assert_eq!(&source[err.span.start..err.span.end], "math::cube");
+    }
+
+    #[test]
+    fn test_import_alias_and_glob() {
+        let math = "mod math { fn square(x: i32) -> i32 { return x * x; } fn cube(x: i32) -> i32 { return x * x * x; } }\n";
+
+        assert!(check(&format!(
+            "{}import math::square as sq;\nfn main() {{ let y: i32 = sq(3); }}",
+            math
+        ))
+        .is_ok());
+        assert!(check(&format!(
+            "{}import math::square as sq;\nfn main() {{ let y = square(3); }}",
+            math
+        ))
+        .is_err());
+
+        assert!(check(&format!(
+            "{}import math::*;\nfn main() {{ let y: i32 = square(cube(2)); }}",
+            math
+        ))
+        .is_ok());
+        assert!(check(&format!(
+            "{}import math::{{square, cube}};\nfn main() {{ let y: i32 = square(cube(2)); }}",
+            math
+        ))
+        .is_ok());
+    }
+
+    #[test]
+    fn test_supertrait_must_be_implemented() {
+        let decls = "trait B {}\ntrait A: B {}\nstruct S { x: i32 }\nimpl A for S {}\n";
+        assert!(check(&format!("{}impl B for S {{}}\nfn main() {{}}", decls)).is_ok());
+
+        let err = check(&format!("{}fn main() {{}}", decls)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(err.message, "'S' implements 'A' but not its supertrait 'B'");
+    }
+
+    #[test]
+    fn test_trait_impl_matches_trait_signatures() {
+        let decls =
+            "trait Shape {\n    fn area(self) -> i32\n    fn scale(self, k: i32) -> i32\n}\n\
+                     struct Sq { side: i32 }\n";
+        let area = "    fn area(self) -> i32 {\n        self.side * self.side\n    }\n";
+        let scale = "    fn scale(self, k: i32) -> i32 {\n        self.side * k\n    }\n";
+        let with_impl = |methods: &str| {
+            format!(
+                "{}impl Shape for Sq {{\n{}}}\nfn main() {{}}",
+                decls, methods
             )
+        };
+
+        assert!(check(&with_impl(&format!("{}{}", area, scale))).is_ok());
+
+        let err = check(&with_impl(area)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(
+            err.message,
+            "'Sq' implements 'Shape' but is missing method 'scale'"
+        );
+
+        let extra = "    fn perimeter(self) -> i32 {\n        4 * self.side\n    }\n";
+        let source = with_impl(&format!("{}{}{}", area, scale, extra));
+        let err = check(&source).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Method 'perimeter' is not a member of trait 'Shape'"
+        );
+        assert_eq!(&source[err.span.start..err.span.end], "perimeter");
+
+        let bad_scale = "    fn scale(self) -> i32 {\n        self.side\n    }\n";
+        let err = check(&with_impl(&format!("{}{}", area, bad_scale))).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Method 'scale' takes 0 parameter(s) but trait 'Shape' declares 1"
+        );
+
+        let bad_area = "    fn area(self) -> bool {\n        true\n    }\n";
+        let err = check(&with_impl(&format!("{}{}", bad_area, scale))).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        // Receivers must match too
+        let ref_area = "    fn area(&self) -> i32 {\n        self.side\n    }\n";
+        let err = check(&with_impl(&format!("{}{}", ref_area, scale))).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Method 'area' takes `&self` but trait 'Shape' declares `self`"
+        );
+        let source = "trait Show {\n    fn display() -> i32\n}\nstruct P { x: i32 }\n\
+                      impl Show for P {\n    fn display(&self) -> i32 {\n        self.x\n    }\n}\n";
+        let err = check(source).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Method 'display' takes `&self` but trait 'Show' declares no receiver"
+        );
+        let source = source.replace("fn display() -> i32\n}", "fn display(&self) -> i32\n}");
+        assert!(check(&source).is_ok());
+    }
+
+    #[test]
+    fn test_module_items_stay_inside_module() {
+        // Items see their siblings, but nothing leaks out of the module
+        let inner = "mod util { fn one() -> i32 { return 1; } fn two() -> i32 { return one() + one(); } }\n";
+        assert!(check(&format!("{}fn main() {{}}", inner)).is_ok());
+
+        let err = check(&format!("{}fn main() {{ let x = one(); }}", inner)).unwrap_err();
+        assert_eq!(err.message, "Undefined variable 'one'");
+    }
+
+    #[test]
+    fn test_same_named_types_in_different_modules() {
+        let source = "mod a { struct P { x: i32 } impl P { fn get(&self) -> i32 { self.x } } }\n\
+                      mod b { struct P { y: bool } impl P { fn get(&self) -> bool { self.y } } }\n\
+                      struct P { z: i32 }\n\
+                      fn main() { let p = P { z: 1 }; let z = p.z; }";
+        assert!(check(source).is_ok());
+
+        // A module's struct doesn't lend its fields to a same-named one outside
+        let source =
+            "mod a { struct P { x: i32 } }\nstruct P { z: i32 }\nfn f(p: P) -> i32 { p.x }";
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+    }
+
+    #[test]
+    fn test_let_inferred_from_first_assignment() {
+        assert!(check("fn main() { let x; x = 5; let y: i32 = x + 1; }").is_ok());
+        assert!(check("fn main() { let x: bool; x = true; }").is_ok());
+
+        let err = check("fn main() { let x; println(x); }").unwrap_err();
+        assert_eq!(err.message, "Variable 'x' is used before it is assigned");
+
+        let err = check("fn main() { let x; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert!(err.message.starts_with("Cannot infer the type of 'x'"));
+    }
+
+    #[test]
+    fn test_match_on_string_literals() {
+        let source = "fn code(s: str) -> i32 { match s { \"get\" => 1, \"put\" => 2, _ => 0 } }";
+        assert!(check(source).is_ok());
+        assert!(check("fn f(c: char) { match c { 'a' => 1, _ => 0 }; }").is_ok());
+    }
+
+    #[test]
+    fn test_byte_literal_types() {
+        assert!(check("fn main() { let b: u8 = b'A'; let s: [u8; 3] = b\"abc\"; }").is_ok());
+
+        let err = check("fn main() { let b: char = b'A'; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check("fn main() { let s: [u8; 2] = b\"abc\"; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_mismatch_names_unsuffixed_literals() {
+        let err = check("fn main() { let b: bool = 1; }").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Type mismatch: expected Primitive(Bool), found integer literal"
+        );
+
+        let err = check("fn main() { if 1.5 {} }").unwrap_err();
+        assert_eq!(
+            err.message,
+            "If condition must be bool, found float literal"
+        );
+    }
+
+    #[test]
+    fn test_literal_branch_takes_the_other_branch_type() {
+        assert!(
+            check("fn f(c: bool, y: i64) { let x = if c { 1 } else { y }; let z: i64 = x; }")
+                .is_ok()
+        );
+        assert!(check(
+            "fn f(n: i32, y: i64) { let x = match n { 0 => 1, _ => y }; let z: i64 = x; }"
         )
+        .is_ok());
+        assert!(check("fn f(y: i64) { let a = [1, y]; let z: [i64; 2] = a; }").is_ok());
+
+        let err = check("fn f(c: bool, y: i64) { let x = if c { 1 } else { y }; let z: i32 = x; }")
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
     }
 
-    /// Check if type is integer
-    fn is_integer(&self, ty: &ResolvedType) -> bool {
-        matches!(
-            ty,
-            ResolvedType::Primitive(
-                PrimitiveType::I8
-                    | PrimitiveType::I16
-                    | PrimitiveType::I32
-                    | PrimitiveType::I64
-                    | PrimitiveType::U8
-                    | PrimitiveType::U16
-                    | PrimitiveType::U32
-                    | PrimitiveType::U64
-            )
+    #[test]
+    fn test_literal_pattern_type_mismatch() {
+        let err = check("fn f(n: i32) { match n { \"one\" => 1, _ => 0 }; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert_eq!(
+            err.message,
+            "String pattern requires a str, found Primitive(I32)"
+        );
+
+        let err = check("fn f(s: str) { match s { true => 1, _ => 0 }; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_assign_to_immutable_variable() {
+        let err = check("fn main() { let x = 1; x = 2; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert!(err.message.contains("immutable variable 'x'"));
+
+        let err = check("fn main() { let x = 1; x += 2; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+
+        let err = check("fn bump(n: i32) { n = n + 1; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+    }
+
+    #[test]
+    fn test_assign_to_mutable_variable() {
+        assert!(check("fn main() { let mut x = 1; x = 2; x += 3; }").is_ok());
+        // A deferred `let` may be assigned once without `mut`
+        assert!(check("fn main() { let x; x = 2; }").is_ok());
+    }
+
+    #[test]
+    fn test_annotated_let_read_before_assignment() {
+        let err = check("fn main() { let x: i32; let y = x; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(err.message, "Variable 'x' is used before it is assigned");
+    }
+
+    #[test]
+    fn test_annotated_let_assigned_then_read() {
+        assert!(check("fn main() { let x: i32; x = 5; let y: i32 = x; }").is_ok());
+
+        let err = check("fn main() { let x: i32; x = true; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_conditional_assignment_does_not_initialize() {
+        let err =
+            check("fn f(c: bool) { let x: i32; if c { x = 1; } let y = x + 1; }").unwrap_err();
+        assert_eq!(err.message, "Variable 'x' is used before it is assigned");
+
+        let err = check("fn f(c: bool) { let x: i32; while c { x = 1; } let y = x; }").unwrap_err();
+        assert_eq!(err.message, "Variable 'x' is used before it is assigned");
+
+        // A `loop` body runs, but can break out before the assignment
+        let err = check(
+            "fn f(c: bool) { let x: i32; loop { if c { break; } x = 1; break; } let y = x; }",
+        )
+        .unwrap_err();
+        assert_eq!(err.message, "Variable 'x' is used before it is assigned");
+
+        let err =
+            check("fn f(n: i32) { let x: i32; match n { 0 => { x = 1; }, _ => {} } let y = x; }")
+                .unwrap_err();
+        assert_eq!(err.message, "Variable 'x' is used before it is assigned");
+
+        // Assigned on every path that reaches the read
+        assert!(
+            check("fn f(c: bool) { let x: i32; if c { x = 1; } else { x = 2; } let y = x; }")
+                .is_ok()
+        );
+        assert!(
+            check("fn f(c: bool) { let x: i32; if c { x = 1; } else { return; } let y = x; }")
+                .is_ok()
+        );
+        assert!(check("fn f() { let x: i32; loop { x = 1; break; } let y = x; }").is_ok());
+        assert!(
+            check("fn f(c: bool) { let x: i32; loop { if c { x = 1; break; } } let y = x; }")
+                .is_ok()
+        );
+        assert!(check(
+            "fn f(n: i32) { let x: i32; match n { 0 => { x = 1; }, _ => { x = 2; } } let y = x; }"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_prelude_functions() {
+        assert!(check("fn main() { let n: u64 = len([1, 2, 3]); }").is_ok());
+        assert!(check("fn main() { let n: u64 = len(\"abc\"); }").is_ok());
+        assert!(check("fn main() { let m: i32 = min(1, 2); }").is_ok());
+        assert!(check("fn main() { let m: f64 = max(1.5, 2.5); }").is_ok());
+        assert!(check("fn main() { let a: i32 = abs(0 - 4); }").is_ok());
+
+        let err = check("fn main() { let m: bool = min(1, 2); }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check("fn main() { len(true); }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_prelude_wrong_arity() {
+        let err = check("fn main() { let m = min(1); }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err.message.contains("takes 2 argument(s), found 1"));
+    }
+
+    #[test]
+    fn test_lambda_return_annotation() {
+        assert!(check("fn main() { let f = |x: i32| -> i32 { x + 1 }; }").is_ok());
+
+        let err = check("fn main() { let f = |x: i32| -> bool { x + 1 }; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        // `return` in the body is checked against the lambda's annotation
+        let source = "fn g() -> bool { let f = |x: i32| -> i32 { return x; }; true }";
+        assert!(check(source).is_ok());
+        let source = "fn main() { let f = |x: i32| -> i32 { if x > 0 { return true; } x }; }";
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_unit_function_may_end_in_call() {
+        let decls = "fn get() -> i32 {\n    return 1\n}\nfn log(x: i32) {\n}\n";
+        let source = format!(
+            "{}fn a() {{\n    get()\n}}\nfn b() {{\n    log(2)\n}}\nfn main() {{\n    println(\"hi\")\n}}",
+            decls
+        );
+        assert!(check(&source).is_ok());
+
+        // A non-unit function still needs its last line to have the right type
+        let err = check(&format!("{}fn c() -> i32 {{\n    log(2)\n}}", decls)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_lambda_has_function_type() {
+        let items = "fn takes(b: bool) {}\n";
+
+        assert!(check_main(items, "let inc: fn(i32) -> i32 = |x: i32| x + 1;").is_ok());
+        assert!(check_main(items, "let inc = |x: i32| x + 1; let n: i32 = inc(2);").is_ok());
+
+        let err = check_main(items, "let inc = |x: i32| x + 1; let b: bool = inc(2);").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        let err = check_main(items, "let inc = |x: i32| x + 1; let n = inc(true);").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Argument 1 of 'inc' expects Primitive(I32), found Primitive(Bool)"
+        );
+
+        // An unannotated parameter takes its type from the body
+        assert!(check_main(items, "let f: fn(bool) = |b| takes(b);").is_ok());
+        let err = check_main(items, "let f: fn(i32) = |b| takes(b);").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        // The body sees enclosing variables, but nothing else
+        assert!(check_main(items, "let k = 3; let add = |x: i32| x + k;").is_ok());
+        let err = check_main(items, "let add = |x: i32| x + missing;").unwrap_err();
+        assert_eq!(err.message, "Undefined variable 'missing'");
+        let err = check_main(items, "let f = |x: i32| x; let y = x;").unwrap_err();
+        assert_eq!(err.message, "Undefined variable 'x'");
+    }
+
+    #[test]
+    fn test_integer_literal_adapts_to_operand() {
+        assert!(check("fn inc(x: i64) -> i64 { return x + 1; }").is_ok());
+        assert!(check("fn dec(x: u8) -> u8 { return 1 - x; }").is_ok());
+        assert!(check("fn small(x: i64) -> bool { return x < -5; }").is_ok());
+    }
+
+    #[test]
+    fn test_mixed_integer_widths_error() {
+        let err = check("fn add(x: i32, y: i64) -> i64 { return x + y; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err.message.contains("`as i64`"), "{}", err.message);
+
+        // Comparisons don't widen either, but a literal takes the other side's type
+        let err = check("fn less(x: i32, y: i64) -> bool { return x < y; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        let err = check("fn same(x: i64, y: i32) -> bool { return x == y; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(check("fn big(y: i64) -> bool { return 3000000000 <= y; }").is_ok());
+        assert!(check("fn less(x: i32, y: i64) -> bool { return (x as i64) < y; }").is_ok());
+    }
+
+    #[test]
+    fn test_bitwise_and_shift_operators() {
+        assert!(check("fn main() { let x: i32 = 5 & 3; }").is_ok());
+        assert!(check("fn main() { let x: i32 = 1 << 2; }").is_ok());
+        assert!(check("fn f(x: u64, n: u8) -> u64 { return x >> n | 1; }").is_ok());
+        // A shifted literal doesn't take the shift amount's type
+        assert!(check("fn f(n: u8) { let x: i64 = 1 << n; }").is_ok());
+
+        let err = check("fn main() { let x = true ^ false; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err
+            .message
+            .contains("Bitwise '^' requires integer operands"));
+
+        let err = check("fn f(x: u32, y: u64) -> u32 { return x & y; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        let err = check("fn f(x: i32) -> i32 { return x << 1.5; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_impl_trait_bound_methods() {
+        let source = "trait Display {\n    fn show() -> str\n}\n\
+                      fn render(x: impl Display) -> str {\n    return x.show();\n}";
+        assert!(check(source).is_ok());
+
+        let source = "trait Display {\n    fn show() -> str\n}\n\
+                      fn render(x: impl Display) -> i32 {\n    return x.show();\n}";
+        assert_eq!(check(source).unwrap_err().kind, ErrorKind::TypeMismatch);
+
+        let source = "trait Display {\n    fn show() -> str\n}\n\
+                      fn render(x: impl Display) {\n    x.hide();\n}";
+        assert!(check(source)
+            .unwrap_err()
+            .message
+            .contains("No method 'hide'"));
+    }
+
+    #[test]
+    fn test_impl_trait_return() {
+        let source = "trait Display {\n    fn show(self) -> str\n}\n\
+                      fn make() -> impl Display {\n    return 5;\n}";
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let with_impl = format!(
+            "{}\nimpl Display for i32 {{\n    fn show(self) -> str {{\n        \"n\"\n    }}\n}}",
+            source
+        );
+        assert!(check(&with_impl).is_ok());
+
+        // The bound must be satisfied by the returned type, not just any type
+        let source = "trait Display {\n    fn show(self) -> str\n}\nstruct P { x: i32 }\n\
+                      impl Display for P {\n    fn show(self) -> str {\n        \"p\"\n    }\n}\n\
+                      fn good() -> impl Display {\n    return P { x: 1 };\n}\n\
+                      fn bad() -> impl Display {\n    return true;\n}";
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(check(&source.replace("return true;", "return P { x: 2 };")).is_ok());
+    }
+
+    #[test]
+    fn test_constant_index_bounds() {
+        assert!(check("fn main() { let x: i32 = [1, 2, 3][2]; }").is_ok());
+        assert!(check("fn get(i: u64) -> i32 { return [1, 2, 3][i]; }").is_ok());
+
+        let err = check("fn main() { let x = [1, 2, 3][5]; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert!(err.message.contains("out of bounds"));
+
+        let err = check("fn main() { let x = [1, 2, 3][1 + 2]; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+    }
+
+    #[test]
+    fn test_index_requires_array_and_integer() {
+        assert!(check("fn get(a: [i32; 4], i: usize) -> i32 { return a[i]; }").is_ok());
+        let err = check("fn get(a: [i32; 4]) -> bool { return a[0]; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check("fn get(a: [i32; 4]) -> i32 { return a[true]; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err.message.contains("Index must be an integer"));
+
+        let err = check("fn get(n: i32) -> i32 { return n[0]; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err.message.contains("Cannot index into"));
+
+        // Through references, and into vectors and strings
+        assert!(check("fn f(a: &[i32]) -> i32 { a[0] }").is_ok());
+        assert!(check("fn f(v: Vec<i32>) -> i32 { v[0] }").is_ok());
+        let err = check("fn f(v: &Vec<i32>) -> bool { v[0] }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(check("fn f(s: str) { let c = s[0]; }").is_ok());
+    }
+
+    #[test]
+    fn test_if_let_chain_binds_left_to_right() {
+        let source = "fn positive(a: Option<i32>) -> bool {\n\
+                      if let Some(x) = a && x > 0 {\n    return true;\n}\n\
+                      return false;\n}";
+        assert!(check(source).is_ok());
+
+        // The binding is not visible in the else-block
+        let source = "fn positive(a: Option<i32>) -> i32 {\n\
+                      if let Some(x) = a { return x; } else { return x; }\n}";
+        assert!(check(source).is_err());
+
+        let err = check("fn main() { let b = let x = 1; }").unwrap_err();
+        assert!(err.message.contains("only allowed in `if` conditions"));
+    }
+
+    #[test]
+    fn test_array_length_from_const() {
+        let source = "const SIZE: usize = 8;\n\
+                      fn last(buf: [u8; SIZE]) -> u8 { return buf[SIZE - 1]; }";
+        assert!(check(source).is_ok());
+
+        let source = "const SIZE: usize = 8;\n\
+                      fn past(buf: [u8; SIZE]) -> u8 { return buf[SIZE]; }";
+        assert!(check(source).unwrap_err().message.contains("out of bounds"));
+
+        let err = check("fn f(n: usize, buf: [u8; n]) {}").unwrap_err();
+        assert!(err.message.contains("constant integer expression"));
+
+        let err = check("const EMPTY: usize = 0;\nfn f(buf: [u8; EMPTY]) {}").unwrap_err();
+        assert!(err.message.contains("must be positive"));
+    }
+
+    #[test]
+    fn test_const_initializer_must_be_constant() {
+        assert!(check("const N: usize = 2 * 3 + 1;\nfn f(buf: [u8; N - 1]) {}").is_ok());
+
+        let err = check("const X: i32 = min(1, 2);").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(
+            err.message,
+            "Function calls are not allowed in constant expressions"
+        );
+    }
+
+    #[test]
+    fn test_missing_return_value() {
+        let err = check("fn f(n: i32) -> i32 { let y = n; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err
+            .message
+            .starts_with("Function 'f' must return Primitive(I32) on every path"));
+
+        let err = check("fn f() -> i32 { true }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_if_else_returning_on_both_branches() {
+        let source =
+            "fn sign(n: i32) -> i32 {\n    if n < 0 { return 0 - 1; } else { return 1; }\n}";
+        assert!(check(source).is_ok());
+
+        let source = "fn pick(c: bool) -> i32 {\n    if c { 1 } else { 2 }\n}";
+        assert!(check(source).is_ok());
+
+        let source = "fn spin() -> i32 {\n    loop {\n        while true { break; }\n    }\n}";
+        assert!(check(source).is_ok());
+    }
+
+    #[test]
+    fn test_if_without_else_falls_through() {
+        let source = "fn f(c: bool) -> i32 {\n    if c { return 1; }\n}";
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert_eq!(&source[err.span.start..err.span.end], "f");
+    }
+
+    #[test]
+    fn test_break_outside_loop() {
+        let err = check("fn main() { break; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(err.message, "`break` outside of a loop");
+        assert_eq!(err.span, Span { start: 12, end: 17 });
+
+        let err = check("fn main() { if true { continue; } }").unwrap_err();
+        assert_eq!(err.message, "`continue` outside of a loop");
+
+        let err = check("fn main() { loop { let f = |x: i32| { break; }; } }").unwrap_err();
+        assert_eq!(err.message, "`break` outside of a loop");
+    }
+
+    #[test]
+    fn test_break_inside_loop() {
+        assert!(check("fn main() { while true { break; } }").is_ok());
+        assert!(check("fn main() { for i in 0..3 { if i == 1 { continue; } } }").is_ok());
+    }
+
+    #[test]
+    fn test_generic_call_infers_type_parameters() {
+        let id = "fn id<T>(x: T) -> T { x }\n";
+        assert!(check(&format!("{}fn main() {{ let n: i32 = id(5); }}", id)).is_ok());
+        assert!(check(&format!("{}fn main() {{ let s: str = id(\"x\"); }}", id)).is_ok());
+        let err = check(&format!("{}fn main() {{ let s: str = id(5); }}", id)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let pair = "fn pair<A, B>(a: A, b: B) -> (A, B) { (a, b) }\n";
+        assert!(check(&format!(
+            "{}fn main() {{ let p: (i32, bool) = pair(1, true); }}",
+            pair
+        ))
+        .is_ok());
+        let source = format!(
+            "{}fn main() {{ let p: (bool, i32) = pair(1, true); }}",
+            pair
+        );
+        assert_eq!(check(&source).unwrap_err().kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_substitute_replaces_nested_type_vars() {
+        let var = |name: &str| ResolvedType::TypeVar(name.to_string());
+        let vec_of = |ty| ResolvedType::Generic {
+            name: "Vec".to_string(),
+            args: vec![ty],
+        };
+        let i32 = ResolvedType::Primitive(PrimitiveType::I32);
+        let subst = HashMap::from([("T".to_string(), i32.clone())]);
+
+        assert_eq!(vec_of(var("T")).substitute(&subst), vec_of(i32.clone()));
+        assert_eq!(
+            ResolvedType::Reference(Box::new(vec_of(vec_of(var("T"))))).substitute(&subst),
+            ResolvedType::Reference(Box::new(vec_of(vec_of(i32.clone()))))
+        );
+
+        // Unbound variables are left for a later substitution
+        let pair = ResolvedType::Tuple(vec![var("T"), var("U")]);
+        assert_eq!(
+            pair.substitute(&subst),
+            ResolvedType::Tuple(vec![i32, var("U")])
+        );
+    }
+
+    #[test]
+    fn test_generic_call_conflicting_inference() {
+        let source = "fn same<T>(a: T, b: T) -> T { a }\nfn main() { let x = same(true, \"no\"); }";
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err
+            .message
+            .starts_with("Conflicting types for type parameter 'T' of 'same'"));
+
+        // A literal takes the width the other argument settles on
+        let source = "fn same<T>(a: T, b: T) -> T { a }\nfn f(n: i64) { let x: i64 = same(1, n); }";
+        assert!(check(source).is_ok());
+    }
+
+    #[test]
+    fn test_turbofish_binds_type_parameters() {
+        let id = "fn id<T>(x: T) -> T { x }\n";
+        assert!(check(&format!("{}fn main() {{ let v: i64 = id::<i64>(5); }}", id)).is_ok());
+
+        let err = check(&format!(
+            "{}fn main() {{ let v: bool = id::<i32>(true); }}",
+            id
+        ))
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check(&format!(
+            "{}fn main() {{ let v = id::<i32, bool>(1); }}",
+            id
+        ))
+        .unwrap_err();
+        assert_eq!(err.message, "'id' takes 1 type argument(s), found 2");
+
+        let err = check("fn main() { let v = Some::<i32>(1); }").unwrap_err();
+        assert_eq!(err.message, "Type arguments on 'Some' aren't supported yet");
+    }
+
+    #[test]
+    fn test_integer_literal_fits_every_width() {
+        for ty in [
+            "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize",
+        ] {
+            let source = format!("fn main() {{ let x: {} = 5; let y: {} = x + 1; }}", ty, ty);
+            assert!(check(&source).is_ok(), "literal should fit {}", ty);
+        }
+
+        let err = check("fn main() { let x: bool = 5; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+    }
+
+    #[test]
+    fn test_float_literal_fits_f32() {
+        assert!(check("fn main() { let x: f32 = 1.5; let y: f32 = x * 2.0; }").is_ok());
+        assert!(check("fn main() { let x: i32 = 1.5; }").is_err());
+    }
+
+    #[test]
+    fn test_unconstrained_literal_defaults() {
+        // `x` settles on i32, so it no longer fits an i64
+        let err = check("fn main() { let x = 5; let y: i64 = x; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(check("fn main() { let x = 1.5; let y: f64 = x; }").is_ok());
+    }
+
+    #[test]
+    fn test_type_used_as_value() {
+        let err = check("fn main() { let x = i32; }").unwrap_err();
+        assert_eq!(err.message, "Expected a value, found type 'i32'");
+
+        let err = check("struct Point { x: i32 }\nfn main() { let p = Point; }").unwrap_err();
+        assert_eq!(err.message, "Expected a value, found type 'Point'");
+    }
+
+    #[test]
+    fn test_value_used_as_type() {
+        let err = check("fn helper() {}\nfn main() { let x: helper = 1; }").unwrap_err();
+        assert_eq!(err.message, "Expected a type, found value 'helper'");
+
+        let err = check("fn main() { let count = 1; let y: count = 2; }").unwrap_err();
+        assert_eq!(err.message, "Expected a type, found value 'count'");
+    }
+
+    #[test]
+    fn test_negative_zero_folds() {
+        let span = Span::new(0, 0);
+        let neg_zero = Expr::new(
+            ExprKind::Unary {
+                op: UnOp::Neg,
+                expr: Box::new(Expr::float(0.0, span)),
+            },
+            span,
+        );
+
+        match TypeChecker::new().const_value(&neg_zero) {
+            Some(Literal::Float(value)) => {
+                assert_eq!(value, 0.0);
+                assert!(value.is_sign_negative());
+            }
+            other => panic!("expected a float constant, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_inf_and_nan_constants() {
+        assert!(
+            check("fn main() { let x: f64 = inf; let y: f64 = -inf; let z: f64 = nan; }").is_ok()
+        );
+        assert!(check("const BIG: f64 = inf * 2.0;\nfn main() {}").is_ok());
+
+        let err = check("fn main() { let x: i32 = nan; }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let checker = TypeChecker::new();
+        let inf = Expr::ident("inf", Span::new(0, 0));
+        assert_eq!(
+            checker.const_value(&inf),
+            Some(Literal::Float(f64::INFINITY))
+        );
+    }
+
+    #[test]
+    fn test_extension_method_on_primitive() {
+        let source = "impl i32 {\n    fn squared(self) -> i32 {\n        self * self\n    }\n}\n\
+                      fn main() {\n    let n: i32 = 5.squared();\n}";
+        assert!(check(source).is_ok());
+
+        let source = "impl i32 {\n    fn squared(self) -> i32 {\n        self * self\n    }\n}\n\
+                      fn main() {\n    let b: bool = 5.squared();\n}";
+        assert_eq!(check(source).unwrap_err().kind, ErrorKind::TypeMismatch);
+
+        let err = check("fn main() {\n    let n = 5.cubed();\n}").unwrap_err();
+        assert_eq!(err.message, "No method 'cubed' found for type 'i32'");
+    }
+
+    #[test]
+    fn test_let_shadowing() {
+        let source = "fn main() {\n    let x = 1\n    let x = x + 1\n    let x = x > 1\n    let b: bool = x\n}";
+        assert!(check(source).is_ok());
+
+        let err = check("fn f() {}\nfn f() {}").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(err.message, "Symbol 'f' already defined in current scope");
+
+        let err = check("fn f(a: i32, a: i32) {}").unwrap_err();
+        assert_eq!(err.message, "Parameter 'a' is declared more than once");
+    }
+
+    #[test]
+    fn test_unknown_binding_takes_type_of_first_use() {
+        // Nothing tells `id` what it returns, so `y` starts out unknown
+        let items = "fn takes(b: bool) {}\n";
+
+        assert!(check_main(
+            items,
+            "    let id = |x| x\n    let y = id(2)\n    let a: i32 = y\n    let b: i32 = a + y"
+        )
+        .is_ok());
+
+        let err = check_main(
+            items,
+            "    let id = |x| x\n    let y = id(2)\n    let a: i32 = y\n    let b: bool = y",
+        )
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check_main(
+            items,
+            "    let id = |x| x\n    let y = id(2)\n    let a: i32 = y\n    takes(y)",
+        )
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert_eq!(
+            err.message,
+            "Argument 1 of 'takes' expects Primitive(Bool), found Primitive(I32)"
+        );
+    }
+
+    #[test]
+    fn test_default_parameter_values() {
+        let greet = "fn greet(name: str, greeting: str = \"Hello\") -> str {\n    greeting\n}\n";
+        assert!(check(&format!("{}fn main() {{ let a = greet(\"x\"); }}", greet)).is_ok());
+        assert!(check(&format!(
+            "{}fn main() {{ let a = greet(\"x\", \"Hi\"); }}",
+            greet
+        ))
+        .is_ok());
+
+        let err = check(&format!("{}fn main() {{ let a = greet(); }}", greet)).unwrap_err();
+        assert_eq!(err.message, "'greet' takes 1 to 2 argument(s), found 0");
+
+        let err = check("fn greet(name: str, greeting: str = 5) {}").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err
+            .message
+            .starts_with("Default value of parameter 'greeting'"));
+
+        let err = check("fn greet(greeting: str = \"Hello\", name: str) {}").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert!(err.message.contains("'name' needs a default value"));
+    }
+
+    #[test]
+    fn test_method_call_on_struct_impl() {
+        let prelude = "struct Point {\n    x: i32,\n    y: i32,\n}\n\
+                       impl Point {\n    fn norm(self) -> i32 {\n        self.x + self.y\n    }\n\
+                       \x20   fn scale(self, k: i32) -> i32 {\n        self.x * k\n    }\n}\n";
+        assert!(check_main(
+            prelude,
+            "    let p = Point { x: 1, y: 2 }\n    let n: i32 = p.norm()"
+        )
+        .is_ok());
+
+        let err = check_main(
+            prelude,
+            "    let p = Point { x: 1, y: 2 }\n    let b: bool = p.norm()",
+        )
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check_main(
+            prelude,
+            "    let p = Point { x: 1, y: 2 }\n    let n = p.scale(true)",
+        )
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+
+        let err = check_main(
+            prelude,
+            "    let p = Point { x: 1, y: 2 }\n    let n = p.scale()",
         )
+        .unwrap_err();
+        assert_eq!(err.message, "Method 'scale' takes 1 argument(s), found 0");
+
+        let err = check_main(
+            prelude,
+            "    let p = Point { x: 1, y: 2 }\n    let n = p.missing()",
+        )
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(err.message, "No method 'missing' found for type 'Point'");
+    }
+
+    #[test]
+    fn test_self_outside_method() {
+        let err = check("fn free(self) {}").unwrap_err();
+        assert!(err.message.contains("takes 'self' but is not a method"));
+    }
+
+    #[test]
+    fn test_try_on_result() {
+        let source = r#"
+fn parse(s: str) -> Result<i32, str> {
+    Ok(1)
+}
+
+fn twice(s: str) -> Result<i32, str> {
+    let n = parse(s)?
+    Ok(n * 2)
+}
+"#;
+        assert!(check(source).is_ok());
+
+        let source = r#"
+fn parse(s: str) -> Result<i32, str> {
+    Error(s)
+}
+
+fn twice(s: str) -> i32 {
+    let n = parse(s)?
+    n * 2
+}
+"#;
+        let err = check(source).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::TypeMismatch);
+        assert!(err.message.contains("'?'"));
+    }
+
+    #[test]
+    fn test_option_and_result_arity() {
+        let err =
+            check("fn f(r: Result<i32>) -> Result<i32> {\n    let n = r?\n    r\n}").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::SemanticError);
+        assert_eq!(err.message, "'Result' takes 2 type arguments, found 1");
+
+        let err = check("fn f(o: Option<i32, str>) {}").unwrap_err();
+        assert_eq!(err.message, "'Option' takes 1 type argument, found 2");
     }
 }
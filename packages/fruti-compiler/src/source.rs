@@ -0,0 +1,104 @@
+// Source - Original source text with span lookups
+// Maps byte spans back to text, lines, and line/column locations
+
+use crate::span::Span;
+
+/// Source text of a single file, indexed by line for span lookups
+#[derive(Debug, Clone)]
+pub struct Source {
+    text: String,
+    /// Byte offset of the first character of each line
+    line_starts: Vec<usize>,
+}
+
+impl Source {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self { text, line_starts }
+    }
+
+    /// The full source text
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Source text covered by a span (clamped to the end of the file)
+    pub fn text(&self, span: Span) -> &str {
+        span.slice(&self.text)
+    }
+
+    /// Full text of the line containing the start of a span, without the line ending
+    pub fn line_text(&self, span: Span) -> &str {
+        let line = self.line_index(span.start);
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.text.len(), |next| next - 1);
+
+        self.text[start..end].trim_end_matches('\r')
+    }
+
+    /// 1-based (line, column) of the start of a span; columns count characters
+    pub fn location(&self, span: Span) -> (usize, usize) {
+        let offset = span.start.min(self.text.len());
+        let line = self.line_index(offset);
+        let start = self.line_starts[line];
+        // A span starting inside a multi-byte character points at that character
+        let prefix = (start..=offset)
+            .rev()
+            .find_map(|end| self.text.get(start..end))
+            .unwrap_or("");
+        let column = prefix.chars().count();
+
+        (line + 1, column + 1)
+    }
+
+    fn line_index(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main() {\n    let x = 5;\n    print(x)\n}\n";
+
+    #[test]
+    fn test_text() {
+        let source = Source::new(SOURCE);
+        assert_eq!(source.text(Span::new(0, 2)), "fn");
+        assert_eq!(source.text(Span::new(20, 21)), "x");
+        assert_eq!(source.text(Span::new(41, 100)), "\n");
+    }
+
+    #[test]
+    fn test_line_text() {
+        let source = Source::new(SOURCE);
+        assert_eq!(source.line_text(Span::new(20, 21)), "    let x = 5;");
+        assert_eq!(source.line_text(Span::new(0, 2)), "fn main() {");
+        assert_eq!(source.line_text(Span::new(40, 41)), "}");
+    }
+
+    #[test]
+    fn test_location() {
+        let source = Source::new(SOURCE);
+        assert_eq!(source.location(Span::new(0, 2)), (1, 1));
+        assert_eq!(source.location(Span::new(20, 21)), (2, 9));
+        assert_eq!(source.location(Span::new(31, 36)), (3, 5));
+
+        // Offsets inside a multi-byte character don't panic
+        let source = Source::new("x = \"é\"");
+        assert_eq!(source.location(Span::new(5, 7)), (1, 6));
+        assert_eq!(source.location(Span::new(6, 7)), (1, 6));
+        assert_eq!(source.location(Span::new(7, 8)), (1, 7));
+    }
+}
@@ -13,8 +13,17 @@ pub struct Span {
 }
 
 impl Span {
+    /// Create a span; reversed bounds are swapped so that `start <= end`
     pub fn new(start: usize, end: usize) -> Self {
-        Self { start, end }
+        Self {
+            start: start.min(end),
+            end: start.max(end),
+        }
+    }
+
+    /// The same span with `start <= end`, for spans built field by field
+    pub fn normalize(self) -> Span {
+        Span::new(self.start, self.end)
     }
 
     pub fn empty(pos: usize) -> Self {
@@ -26,18 +35,29 @@ impl Span {
 
     /// Combine two spans into one that covers both
     pub fn merge(self, other: Span) -> Span {
+        let (a, b) = (self.normalize(), other.normalize());
         Span {
-            start: self.start.min(other.start),
-            end: self.end.max(other.end),
+            start: a.start.min(b.start),
+            end: a.end.max(b.end),
         }
     }
 
     pub fn len(self) -> usize {
-        self.end - self.start
+        self.end.abs_diff(self.start)
     }
 
     pub fn is_empty(self) -> bool {
-        self.start == self.end
+        self.len() == 0
+    }
+
+    /// The text this span covers, clamped to the bounds of `text`. Returns an
+    /// empty string rather than panicking if the span falls inside a
+    /// multi-byte character.
+    pub fn slice(self, text: &str) -> &str {
+        let span = self.normalize();
+        let end = span.end.min(text.len());
+        let start = span.start.min(end);
+        text.get(start..end).unwrap_or("")
     }
 }
 
@@ -66,3 +86,33 @@ impl<T> Spanned<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reversed_span_is_normalized() {
+        assert_eq!(Span::new(7, 3), Span::new(3, 7));
+
+        let built = Span { start: 7, end: 3 };
+        assert_eq!(built.normalize(), Span::new(3, 7));
+        assert_eq!(built.merge(Span::new(9, 10)), Span::new(3, 10));
+    }
+
+    #[test]
+    fn test_len_never_underflows() {
+        assert_eq!(Span { start: 7, end: 3 }.len(), 4);
+        assert_eq!(Span::empty(5).len(), 0);
+        assert!(Span::empty(5).is_empty());
+    }
+
+    #[test]
+    fn test_slice_is_clamped() {
+        let text = "let x = 5;";
+        assert_eq!(Span { start: 5, end: 4 }.slice(text), "x");
+        assert_eq!(Span::new(8, 100).slice(text), "5;");
+        assert_eq!(Span::new(50, 60).slice(text), "");
+        assert_eq!(Span::new(0, 1).slice("é"), "");
+    }
+}
@@ -14,12 +14,17 @@ pub enum TokenKind {
     Float(f64),
     String(String),
     Char(char),
+    Byte(u8),            // b'A'
+    ByteString(Vec<u8>), // b"bytes"
     True,
     False,
 
     // Identifiers and Keywords
     Ident(String),
 
+    // Documentation: `/// text` or `/** text */`, without the markers
+    DocComment(String),
+
     // Keywords - Control Flow
     If,
     Else,
@@ -42,7 +47,9 @@ pub enum TokenKind {
     Trait,
     Impl,
     Type,
+    Where,     // Generic constraints: where T: Ord
     Import,    // Import from module
+    Mod,       // Inline module: mod name { ... }
     SelfLower, // self (lowercase)
     SelfUpper, // Self (uppercase type)
 
@@ -119,6 +126,8 @@ pub enum TokenKind {
     // Punctuation
     Comma,     // ,
     Semicolon, // ;
+    Hash,      // # (attributes: #[inline])
+    At,        // @ (binding patterns: n @ 1..=9)
 
     // Special
     Eof,           // End of file
@@ -149,7 +158,9 @@ impl TokenKind {
                 | TokenKind::Trait
                 | TokenKind::Impl
                 | TokenKind::Type
+                | TokenKind::Where
                 | TokenKind::Import
+                | TokenKind::Mod
                 | TokenKind::SelfLower
                 | TokenKind::SelfUpper
                 | TokenKind::Own
@@ -168,6 +179,22 @@ impl TokenKind {
         )
     }
 
+    /// Readable name for diagnostics: "`fn`", "identifier `x`", "end of file"
+    pub fn description(&self) -> String {
+        match self {
+            TokenKind::Integer(_) | TokenKind::Float(_) => format!("number `{}`", self),
+            TokenKind::String(_) => "string literal".to_string(),
+            TokenKind::Char(_) => "character literal".to_string(),
+            TokenKind::Byte(_) => "byte literal".to_string(),
+            TokenKind::ByteString(_) => "byte string literal".to_string(),
+            TokenKind::Ident(name) => format!("identifier `{}`", name),
+            TokenKind::DocComment(_) => "doc comment".to_string(),
+            TokenKind::Eof => "end of file".to_string(),
+            TokenKind::Error(msg) => format!("invalid token ({})", msg),
+            _ => format!("`{}`", self),
+        }
+    }
+
     /// Get keyword from string
     pub fn from_keyword(s: &str) -> Option<TokenKind> {
         match s {
@@ -190,7 +217,9 @@ impl TokenKind {
             "trait" => Some(TokenKind::Trait),
             "impl" => Some(TokenKind::Impl),
             "type" => Some(TokenKind::Type),
+            "where" => Some(TokenKind::Where),
             "import" => Some(TokenKind::Import),
+            "mod" => Some(TokenKind::Mod),
             "self" => Some(TokenKind::SelfLower),
             "Self" => Some(TokenKind::SelfUpper),
             "own" => Some(TokenKind::Own),
@@ -218,9 +247,13 @@ impl fmt::Display for TokenKind {
             TokenKind::Float(n) => write!(f, "{}", n),
             TokenKind::String(s) => write!(f, "\"{}\"", s),
             TokenKind::Char(c) => write!(f, "'{}'", c),
+            TokenKind::Byte(b) => write!(f, "b'{}'", b.escape_ascii()),
+            TokenKind::ByteString(bytes) => write!(f, "b\"{}\"", bytes.escape_ascii()),
             TokenKind::True => write!(f, "true"),
             TokenKind::False => write!(f, "false"),
             TokenKind::Ident(s) => write!(f, "{}", s),
+            TokenKind::DocComment(text) if text.contains('\n') => write!(f, "/**\n{}\n*/", text),
+            TokenKind::DocComment(text) => write!(f, "/// {}", text),
             TokenKind::If => write!(f, "if"),
             TokenKind::Else => write!(f, "else"),
             TokenKind::While => write!(f, "while"),
@@ -240,7 +273,9 @@ impl fmt::Display for TokenKind {
             TokenKind::Trait => write!(f, "trait"),
             TokenKind::Impl => write!(f, "impl"),
             TokenKind::Type => write!(f, "type"),
+            TokenKind::Where => write!(f, "where"),
             TokenKind::Import => write!(f, "import"),
+            TokenKind::Mod => write!(f, "mod"),
             TokenKind::SelfLower => write!(f, "self"),
             TokenKind::SelfUpper => write!(f, "Self"),
             TokenKind::Own => write!(f, "own"),
@@ -295,6 +330,8 @@ impl fmt::Display for TokenKind {
             TokenKind::RightBracket => write!(f, "]"),
             TokenKind::Comma => write!(f, ","),
             TokenKind::Semicolon => write!(f, ";"),
+            TokenKind::Hash => write!(f, "#"),
+            TokenKind::At => write!(f, "@"),
             TokenKind::Eof => write!(f, "EOF"),
             TokenKind::Error(msg) => write!(f, "Error: {}", msg),
         }
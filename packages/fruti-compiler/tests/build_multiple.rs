@@ -0,0 +1,54 @@
+// Integration tests for building several files in one `fruti build`
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_failing_file_does_not_stop_others() {
+    let dir = tempfile::tempdir().unwrap();
+    let bad = dir.path().join("bad.fruti");
+    let good = dir.path().join("good.fruti");
+    fs::write(&bad, "fn main() {\n    let x: bool = 1;\n}\n").unwrap();
+    fs::write(&good, "fn main() {\n}\n").unwrap();
+
+    let out_dir = dir.path().join("out");
+    fs::create_dir(&out_dir).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("build")
+        .arg(&bad)
+        .arg(&good)
+        .arg("--output")
+        .arg(&out_dir)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(out_dir.join("good.ll").is_file());
+    assert!(!out_dir.join("bad.ll").exists());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 of 2 file(s) built successfully"));
+}
+
+#[test]
+fn test_fail_fast_stops_at_first_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    let bad = dir.path().join("bad.fruti");
+    let good = dir.path().join("good.fruti");
+    fs::write(&bad, "fn main() {\n    let x: bool = 1;\n}\n").unwrap();
+    fs::write(&good, "fn main() {\n}\n").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("build")
+        .arg(&bad)
+        .arg(&good)
+        .arg("--output")
+        .arg(dir.path())
+        .arg("--fail-fast")
+        .status()
+        .unwrap();
+
+    assert!(!status.success());
+    assert!(!dir.path().join("good.ll").exists());
+}
@@ -0,0 +1,33 @@
+// Integration tests for `--color`
+
+use std::fs;
+use std::process::Command;
+
+fn check_output(color: &str) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("bad.fruti");
+    fs::write(&input, "fn main() {\n    let x: bool = 1;\n}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("check")
+        .arg(&input)
+        .arg(format!("--color={}", color))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn test_color_never_is_plain() {
+    let stderr = check_output("never");
+    assert!(stderr.contains("[ERROR] Type checking failed"));
+    assert!(!stderr.contains('\x1b'));
+}
+
+#[test]
+fn test_color_always_uses_ansi() {
+    let stderr = check_output("always");
+    assert!(stderr.contains("\x1b[1;31m[ERROR]\x1b[0m"));
+}
@@ -0,0 +1,59 @@
+// Integration tests for `fruti build --emit dep-info`
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_dep_info_lists_imported_sibling() {
+    let dir = tempfile::tempdir().unwrap();
+    let main = dir.path().join("main.fruti");
+    let util = dir.path().join("util.fruti");
+    fs::write(&main, "import util\n\nfn main() {\n}\n").unwrap();
+    fs::write(&util, "fn helper() {\n}\n").unwrap();
+
+    let output = dir.path().join("app");
+    let status = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("build")
+        .arg(&main)
+        .arg("--output")
+        .arg(&output)
+        .arg("--emit")
+        .arg("dep-info")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let dep_info = fs::read_to_string(dir.path().join("app.d")).unwrap();
+    let first_line = dep_info.lines().next().unwrap();
+    assert_eq!(
+        first_line,
+        format!(
+            "{}: {} {}",
+            output.display(),
+            main.display(),
+            util.display()
+        )
+    );
+}
+
+#[test]
+fn test_dep_info_ignores_unresolved_imports() {
+    let dir = tempfile::tempdir().unwrap();
+    let main = dir.path().join("main.fruti");
+    fs::write(&main, "import std::io\n\nfn main() {\n}\n").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("build")
+        .arg(&main)
+        .arg("--emit=dep-info")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let dep_info = fs::read_to_string(dir.path().join("main.d")).unwrap();
+    assert!(dep_info.starts_with(&format!(
+        "{}: {}\n",
+        dir.path().join("main").display(),
+        main.display()
+    )));
+}
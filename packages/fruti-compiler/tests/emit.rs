@@ -0,0 +1,76 @@
+// Integration tests for `fruti build --emit llvm-ir,asm,obj`
+
+use std::fs;
+use std::process::Command;
+
+/// Whether `llc` is available to produce assembly and object files
+fn has_llc() -> bool {
+    Command::new("llc").arg("--version").output().is_ok()
+}
+
+fn build(emit: &str) -> (tempfile::TempDir, bool) {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("hello.fruti");
+    fs::write(&input, "fn main() {\n    println(\"hi\");\n}\n").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("build")
+        .arg(&input)
+        .arg("--output")
+        .arg(dir.path().join("hello"))
+        .arg("--emit")
+        .arg(emit)
+        .status()
+        .unwrap();
+    (dir, status.success())
+}
+
+#[test]
+fn test_emit_obj_writes_object_file() {
+    if !has_llc() {
+        return;
+    }
+
+    let (dir, success) = build("obj");
+    assert!(success);
+    assert!(fs::metadata(dir.path().join("hello.o")).unwrap().len() > 0);
+    // Asking for an object file replaces the default IR output
+    assert!(!dir.path().join("hello.ll").exists());
+}
+
+#[test]
+fn test_emit_several_formats() {
+    if !has_llc() {
+        return;
+    }
+
+    let (dir, success) = build("llvm-ir,asm");
+    assert!(success);
+    let ir = fs::read_to_string(dir.path().join("hello.ll")).unwrap();
+    assert!(ir.contains("call i32 @puts"));
+    let asm = fs::read_to_string(dir.path().join("hello.s")).unwrap();
+    assert!(asm.contains("main"));
+}
+
+#[test]
+fn test_build_counts_skipped_constructs() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("array.fruti");
+    fs::write(&input, "fn main() {\n    let _t = [1, 2]\n}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("build")
+        .arg(&input)
+        .arg("--output")
+        .arg(dir.path().join("array"))
+        .arg("--emit")
+        .arg("llvm-ir")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("1 construct not yet codegen'd"),
+        "{}",
+        stdout
+    );
+}
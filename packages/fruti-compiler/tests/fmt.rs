@@ -0,0 +1,105 @@
+// Integration tests for `fruti fmt` and `fruti fmt --check`
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const UNFORMATTED: &str = "fn add(a:i32,b:i32)->i32{a+b}\n";
+const FORMATTED: &str = "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n";
+
+fn fmt(args: &[&str], path: &Path) -> (Output, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("fmt")
+        .args(args)
+        .arg(path)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    (output, stdout)
+}
+
+#[test]
+fn test_check_fails_without_writing() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("add.fruti");
+    fs::write(&input, UNFORMATTED).unwrap();
+
+    let (output, stdout) = fmt(&["--check"], &input);
+    assert!(!output.status.success());
+    assert!(stdout.contains("-fn add(a:i32,b:i32)->i32{a+b}"));
+    assert!(stdout.contains("+fn add(a: i32, b: i32) -> i32 {"));
+    assert_eq!(fs::read_to_string(&input).unwrap(), UNFORMATTED);
+
+    fs::write(&input, FORMATTED).unwrap();
+    let (output, _) = fmt(&["--check"], &input);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_formats_directories_recursively() {
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("src").join("math");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(dir.path().join("add.fruti"), UNFORMATTED).unwrap();
+    fs::write(nested.join("add.fruti"), UNFORMATTED).unwrap();
+    fs::write(nested.join("notes.txt"), UNFORMATTED).unwrap();
+
+    let (output, _) = fmt(&[], dir.path());
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("add.fruti")).unwrap(),
+        FORMATTED
+    );
+    assert_eq!(
+        fs::read_to_string(nested.join("add.fruti")).unwrap(),
+        FORMATTED
+    );
+    assert_eq!(
+        fs::read_to_string(nested.join("notes.txt")).unwrap(),
+        UNFORMATTED
+    );
+
+    let (output, _) = fmt(&["--check"], dir.path());
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_files_with_comments_are_left_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("add.fruti");
+    let source = format!("// Adds two numbers\n{}", UNFORMATTED);
+    fs::write(&input, &source).unwrap();
+
+    let (output, stdout) = fmt(&[], &input);
+    assert!(output.status.success());
+    assert!(stdout.contains("[SKIP]"));
+    assert_eq!(fs::read_to_string(&input).unwrap(), source);
+}
+
+#[test]
+fn test_doc_comments_are_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("inc.fruti");
+    fs::write(&input, "/// Adds one\nfn inc(x:i32)->i32{x+1}\n").unwrap();
+
+    let (output, stdout) = fmt(&[], &input);
+    assert!(output.status.success());
+    assert!(!stdout.contains("[SKIP]"));
+    assert_eq!(
+        fs::read_to_string(&input).unwrap(),
+        "/// Adds one\nfn inc(x: i32) -> i32 {\n    x + 1\n}\n"
+    );
+}
+
+#[test]
+fn test_files_with_field_docs_are_left_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("point.fruti");
+    let source = "struct P {\n /// the x\n x: i32,\n}\n";
+    fs::write(&input, source).unwrap();
+
+    let (output, stdout) = fmt(&[], &input);
+    assert!(output.status.success());
+    assert!(stdout.contains("[SKIP]"));
+    assert_eq!(fs::read_to_string(&input).unwrap(), source);
+}
@@ -0,0 +1,95 @@
+// Golden-file tests for LLVM IR output
+//
+// Each `tests/golden/NAME.fruti` is compiled the way `fruti build` does at
+// the default optimization level, and the IR is compared with
+// `tests/golden/NAME.ll`. To accept new output after an intended codegen
+// change, regenerate the `.ll` files and review the diff:
+//
+//     FRUTI_UPDATE_GOLDEN=1 cargo test --test golden
+
+use fruti_compiler::{desugar, optimize, parse_source, CodeGen, TypeChecker};
+use pretty_assertions::StrComparison;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Compile a golden input to IR, naming the module after the file
+fn compile(path: &Path) -> String {
+    let source = fs::read_to_string(path).unwrap();
+    let name = path.file_stem().unwrap().to_str().unwrap();
+
+    let mut ast = parse_source(&source).unwrap_or_else(|e| panic!("{}: {}", name, e));
+    TypeChecker::new()
+        .check_module(&ast)
+        .unwrap_or_else(|e| panic!("{}: {}", name, e));
+    desugar::desugar_module(&mut ast);
+    optimize::optimize_module(&mut ast, 0);
+
+    let ir = CodeGen::new(name.to_string())
+        .generate_module(&ast)
+        .unwrap_or_else(|e| panic!("{}: {}", name, e));
+    normalize(&ir)
+}
+
+/// Ignore differences that say nothing about codegen: line endings and
+/// trailing whitespace
+fn normalize(ir: &str) -> String {
+    let mut out: String = ir
+        .lines()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n");
+    out.push('\n');
+    out
+}
+
+fn golden_inputs() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden");
+    let mut inputs: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "fruti"))
+        .collect();
+    inputs.sort();
+    inputs
+}
+
+#[test]
+fn test_ir_matches_golden_files() {
+    let update = std::env::var_os("FRUTI_UPDATE_GOLDEN").is_some();
+    let inputs = golden_inputs();
+    assert!(!inputs.is_empty(), "no golden inputs found");
+
+    let mut failures = Vec::new();
+    for input in &inputs {
+        let actual = compile(input);
+        let golden = input.with_extension("ll");
+
+        if update {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+
+        let Ok(expected) = fs::read_to_string(&golden) else {
+            failures.push(format!("{}: missing {}", input.display(), golden.display()));
+            continue;
+        };
+        let expected = normalize(&expected);
+        if expected != actual {
+            failures.push(format!(
+                "{} (< expected, > actual):\n{}",
+                golden.display(),
+                StrComparison::new(&expected, &actual)
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} golden file(s) differ (set FRUTI_UPDATE_GOLDEN=1 to regenerate):\n\n{}",
+        failures.len(),
+        inputs.len(),
+        failures.join("\n\n")
+    );
+}
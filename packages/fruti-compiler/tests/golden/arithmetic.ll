@@ -0,0 +1,30 @@
+; ModuleID = 'arithmetic'
+source_filename = "arithmetic"
+
+; Built-in functions
+declare i32 @printf(ptr, ...)
+declare i32 @puts(ptr)
+declare i32 @strcmp(ptr, ptr)
+
+@.str.0 = private unnamed_addr constant [5 x i8] c"area\00"
+
+define i32 @arithmetic.area(i32 %w, i32 %h) {
+entry:
+  %inner.addr = alloca i32
+  %0 = sub i32 %w, 2
+  %1 = sub i32 %h, 2
+  %2 = mul i32 %0, %1
+  store i32 %2, ptr %inner.addr
+  %3 = mul i32 %w, %h
+  %4 = load i32, ptr %inner.addr
+  %5 = sub i32 %3, %4
+  ret i32 %5
+}
+
+define i32 @main() {
+entry:
+  %0 = getelementptr [5 x i8], ptr @.str.0, i64 0, i64 0
+  %1 = call i32 @puts(ptr %0)
+  ret i32 0
+}
+
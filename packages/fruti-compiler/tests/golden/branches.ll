@@ -0,0 +1,66 @@
+; ModuleID = 'branches'
+source_filename = "branches"
+
+; Built-in functions
+declare i32 @printf(ptr, ...)
+declare i32 @puts(ptr)
+declare i32 @strcmp(ptr, ptr)
+
+@.str.0 = private unnamed_addr constant [4 x i8] c"yes\00"
+
+define i32 @branches.pick(i32 %c) {
+entry:
+  %0 = icmp ne i32 %c, 0
+  br i1 %0, label %if0.then, label %if0.else
+
+if0.then:
+  br label %if0.merge
+
+if0.else:
+  br label %if0.merge
+
+if0.merge:
+  %1 = phi i32 [ 1, %if0.then ], [ 2, %if0.else ]
+  ret i32 %1
+}
+
+define i32 @branches.magnitude(i32 %a, i32 %limit) {
+entry:
+  %m.addr = alloca i32
+  %0 = icmp sgt i32 %a, %limit
+  br i1 %0, label %if0.then, label %if0.merge
+
+if0.then:
+  ret i32 %limit
+
+if0.merge:
+  %1 = icmp slt i32 %a, 0
+  br i1 %1, label %if1.then, label %if1.else
+
+if1.then:
+  %2 = sub i32 0, %a
+  br label %if1.merge
+
+if1.else:
+  br label %if1.merge
+
+if1.merge:
+  %3 = phi i32 [ %2, %if1.then ], [ %a, %if1.else ]
+  store i32 %3, ptr %m.addr
+  %4 = load i32, ptr %m.addr
+  ret i32 %4
+}
+
+define i32 @main() {
+entry:
+  br i1 true, label %if0.then, label %if0.merge
+
+if0.then:
+  %0 = getelementptr [4 x i8], ptr @.str.0, i64 0, i64 0
+  %1 = call i32 @puts(ptr %0)
+  br label %if0.merge
+
+if0.merge:
+  ret i32 0
+}
+
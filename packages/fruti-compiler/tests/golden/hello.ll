@@ -0,0 +1,20 @@
+; ModuleID = 'hello'
+source_filename = "hello"
+
+; Built-in functions
+declare i32 @printf(ptr, ...)
+declare i32 @puts(ptr)
+declare i32 @strcmp(ptr, ptr)
+
+@.str.0 = private unnamed_addr constant [14 x i8] c"Hello, World!\00"
+@.str.1 = private unnamed_addr constant [8 x i8] c"Goodbye\00"
+
+define i32 @main() {
+entry:
+  %0 = getelementptr [14 x i8], ptr @.str.0, i64 0, i64 0
+  %1 = call i32 @puts(ptr %0)
+  %2 = getelementptr [8 x i8], ptr @.str.1, i64 0, i64 0
+  %3 = call i32 @puts(ptr %2)
+  ret i32 0
+}
+
@@ -0,0 +1,35 @@
+; ModuleID = 'loops'
+source_filename = "loops"
+
+; Built-in functions
+declare i32 @printf(ptr, ...)
+declare i32 @puts(ptr)
+declare i32 @strcmp(ptr, ptr)
+
+define i32 @loops.count(i32 %n) {
+entry:
+  %i.addr = alloca i32
+  store i32 0, ptr %i.addr
+  br label %loop0.head
+
+loop0.head:
+  %0 = load i32, ptr %i.addr
+  %1 = icmp slt i32 %0, %n
+  br i1 %1, label %loop0.body, label %loop0.end
+
+loop0.body:
+  %2 = load i32, ptr %i.addr
+  %3 = add i32 %2, 1
+  store i32 %3, ptr %i.addr
+  br label %loop0.head
+
+loop0.end:
+  %4 = load i32, ptr %i.addr
+  ret i32 %4
+}
+
+define i32 @main() {
+entry:
+  ret i32 0
+}
+
@@ -0,0 +1,71 @@
+; ModuleID = 'shapes'
+source_filename = "shapes"
+
+; Built-in functions
+declare i32 @printf(ptr, ...)
+declare i32 @puts(ptr)
+declare i32 @strcmp(ptr, ptr)
+
+%shapes.Shape = type { i32, [2 x i32] }
+
+define void @shapes.Shape.Circle(ptr %out, i32 %p0) {
+entry:
+  %tag = getelementptr %shapes.Shape, ptr %out, i32 0, i32 0
+  store i32 0, ptr %tag
+  %slot0 = getelementptr %shapes.Shape, ptr %out, i32 0, i32 1, i32 0
+  store i32 %p0, ptr %slot0
+  ret void
+}
+
+define void @shapes.Shape.Rect(ptr %out, i32 %p0, i32 %p1) {
+entry:
+  %tag = getelementptr %shapes.Shape, ptr %out, i32 0, i32 0
+  store i32 1, ptr %tag
+  %slot0 = getelementptr %shapes.Shape, ptr %out, i32 0, i32 1, i32 0
+  store i32 %p0, ptr %slot0
+  %slot1 = getelementptr %shapes.Shape, ptr %out, i32 0, i32 1, i32 1
+  store i32 %p1, ptr %slot1
+  ret void
+}
+
+define void @shapes.Shape.Empty(ptr %out) {
+entry:
+  %tag = getelementptr %shapes.Shape, ptr %out, i32 0, i32 0
+  store i32 2, ptr %tag
+  ret void
+}
+
+define i32 @shapes.Shape.width(ptr %self) {
+entry:
+  %tag.ptr = getelementptr %shapes.Shape, ptr %self, i32 0, i32 0
+  %tag = load i32, ptr %tag.ptr
+  switch i32 %tag, label %match.none [
+    i32 0, label %arm0
+    i32 1, label %arm1
+    i32 2, label %arm2
+  ]
+
+arm0:
+  %arm0.r.ptr = getelementptr %shapes.Shape, ptr %self, i32 0, i32 1, i32 0
+  %arm0.r = load i32, ptr %arm0.r.ptr
+  ret i32 %arm0.r
+
+arm1:
+  %arm1.w.ptr = getelementptr %shapes.Shape, ptr %self, i32 0, i32 1, i32 0
+  %arm1.w = load i32, ptr %arm1.w.ptr
+  %arm1.h.ptr = getelementptr %shapes.Shape, ptr %self, i32 0, i32 1, i32 1
+  %arm1.h = load i32, ptr %arm1.h.ptr
+  ret i32 %arm1.w
+
+arm2:
+  ret i32 0
+
+match.none:
+  unreachable
+}
+
+define i32 @main() {
+entry:
+  ret i32 0
+}
+
@@ -0,0 +1,65 @@
+// Integration tests for `fruti lints` and per-rule `--allow`/`--deny`
+
+use std::fs;
+use std::process::{Command, Output};
+
+/// A function and a struct that each break a casing rule
+const SOURCE: &str = "fn parseLine() {\n}\nstruct line_reader { x: i32 }\n";
+
+fn fruti(args: &[&str]) -> (Output, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("style.fruti");
+    fs::write(&input, SOURCE).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .args(args)
+        .arg(&input)
+        .arg("--color=never")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    (output, stdout)
+}
+
+#[test]
+fn test_lints_lists_every_rule() {
+    let output = Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("lints")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("non_snake_case"));
+    assert!(stdout.contains("redundant_return"));
+    assert!(stdout.contains("unused_variable"));
+}
+
+#[test]
+fn test_allow_suppresses_only_that_rule() {
+    let (output, stdout) = fruti(&["lint", "--allow", "non_snake_case"]);
+    assert!(output.status.success());
+    assert!(!stdout.contains("non_snake_case"));
+    assert!(stdout.contains("[WARN] non_pascal_case"));
+}
+
+#[test]
+fn test_deny_fails_lint_and_check() {
+    let (output, stdout) = fruti(&["lint", "--deny", "non_pascal_case"]);
+    assert!(!output.status.success());
+    assert!(stdout.contains("[ERROR] non_pascal_case"));
+    assert!(stdout.contains("[WARN] non_snake_case"));
+
+    let (output, _) = fruti(&["check", "--deny", "non_pascal_case"]);
+    assert!(!output.status.success());
+    let (output, _) = fruti(&["check", "--allow", "non_pascal_case,non_snake_case"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_unknown_rule_is_rejected() {
+    let (output, _) = fruti(&["lint", "--allow", "no_such_rule"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Unknown lint 'no_such_rule'"));
+}
@@ -0,0 +1,61 @@
+// Integration tests for `fruti new`
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn new(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("new")
+        .arg(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_new_binary_project() {
+    let temp = tempfile::tempdir().unwrap();
+    let project = temp.path().join("demo");
+
+    let output = new(&project, &[]);
+    assert!(output.status.success());
+
+    let manifest = fs::read_to_string(project.join("fruti.toml")).unwrap();
+    assert!(manifest.contains("name = \"demo\""));
+    assert!(manifest.contains("type = \"binary\""));
+    let main = fs::read_to_string(project.join("src/main.fruti")).unwrap();
+    assert!(main.contains("println(\"Hello, World!\")"));
+    assert!(fruti_compiler::parse_source(&main).is_ok());
+}
+
+#[test]
+fn test_new_library_project() {
+    let temp = tempfile::tempdir().unwrap();
+    let project = temp.path().join("mathlib");
+
+    let output = new(&project, &["--project-type", "library"]);
+    assert!(output.status.success());
+
+    let manifest = fs::read_to_string(project.join("fruti.toml")).unwrap();
+    assert!(manifest.contains("type = \"library\""));
+    assert!(project.join("src/lib.fruti").exists());
+    assert!(!project.join("src/main.fruti").exists());
+}
+
+#[test]
+fn test_new_refuses_non_empty_directory() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("notes.txt"), "keep me").unwrap();
+
+    let output = new(temp.path(), &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("non-empty"));
+    assert!(!temp.path().join("fruti.toml").exists());
+
+    // An existing empty directory is fine
+    let empty = temp.path().join("empty");
+    fs::create_dir(&empty).unwrap();
+    assert!(new(&empty, &[]).status.success());
+    assert!(empty.join("src/main.fruti").exists());
+}
@@ -0,0 +1,54 @@
+// Integration tests for `fruti run`
+
+use std::fs;
+use std::process::{Command, Output};
+
+/// Whether `llc` is available to produce object files
+fn has_llc() -> bool {
+    Command::new("llc").arg("--version").output().is_ok()
+}
+
+fn run(source: &str) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("prog.fruti");
+    fs::write(&input, source).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_fruti"))
+        .arg("run")
+        .arg(&input)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_run_propagates_exit_code() {
+    if !has_llc() {
+        return;
+    }
+
+    let output = run("fn main() -> i32 {\n    println(\"hi\")\n    return 42\n}\n");
+    assert_eq!(output.status.code(), Some(42));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "hi\n");
+}
+
+#[test]
+fn test_run_stops_on_compile_errors() {
+    let output = run("fn main() {\n    println(\"hi\")\n    let x: bool = 1\n}\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().is_empty());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Type checking failed"));
+}
+
+#[test]
+fn test_run_refuses_unsupported_constructs() {
+    let output = run(
+        "fn helper(n: i32) -> i32 {\n    let t = [n, 1]\n    return t[0]\n}\nfn main() -> i32 {\n    return helper(37)\n}\n",
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Code generation failed"), "{}", stderr);
+    assert!(stderr.contains("    - "), "{}", stderr);
+}